@@ -1,5 +1,10 @@
 use glam::Vec3;
 
+use crate::error::SimLidarError;
+use crate::heightfield::Heightfield;
+use crate::primitive::Primitive;
+use crate::simd::F32x4;
+
 /// A ray defined by an origin and a direction.
 ///
 /// The direction should be normalised (unit-length) for the intersection
@@ -18,8 +23,7 @@ impl Ray {
 
     /// Cast this ray against a BVH and return the closest intersection within `t_max`.
     pub fn cast(&self, bvh: &Bvh, t_max: f32) -> Option<Intersection> {
-        bvh.cast_ray(self.origin, self.direction, t_max)
-            .map(|distance| Intersection { distance })
+        bvh.cast_ray_hit(self.origin, self.direction, t_max)
     }
 }
 
@@ -28,6 +32,10 @@ impl Ray {
 pub struct Intersection {
     /// Distance along the ray from the origin to the hit point.
     pub distance: f32,
+    /// Unit geometric normal of the surface that was hit.
+    pub normal: Vec3,
+    /// Object id of the surface that was hit.
+    pub object_id: u32,
 }
 
 /// An axis-aligned bounding box (AABB)
@@ -80,6 +88,19 @@ impl Aabb {
             None
         }
     }
+
+    /// [`Aabb::ray_intersect`] for a packet of rays sharing `origin`, one per
+    /// lane.  Returns a bitmask of the lanes that hit.
+    fn ray_intersect_packet(&self, origin: Vec3, inv_dir: &[F32x4; 3], t_max: F32x4) -> u32 {
+        let (lo, hi) = (self.min - origin, self.max - origin);
+        let t1 = [lo.x, lo.y, lo.z].map(F32x4::splat);
+        let t2 = [hi.x, hi.y, hi.z].map(F32x4::splat);
+        let t1: [F32x4; 3] = std::array::from_fn(|axis| t1[axis].mul(inv_dir[axis]));
+        let t2: [F32x4; 3] = std::array::from_fn(|axis| t2[axis].mul(inv_dir[axis]));
+        let t_near = t1[0].min(t2[0]).max(t1[1].min(t2[1])).max(t1[2].min(t2[2]));
+        let t_far = t1[0].max(t2[0]).min(t1[1].max(t2[1])).min(t1[2].max(t2[2]));
+        t_near.le(t_far) & F32x4::splat(0.0).le(t_far) & t_near.le(t_max)
+    }
 }
 
 /// A single triangle defined by three vertex indices into a flat vertex buffer.
@@ -103,6 +124,11 @@ impl Triangle {
         (self.a + self.b + self.c) / 3.0
     }
 
+    /// Unit geometric normal, following the `a → b → c` winding.
+    pub fn normal(&self) -> Vec3 {
+        (self.b - self.a).cross(self.c - self.a).normalize_or_zero()
+    }
+
     /// Möller–Trumbore ray-triangle intersection.
     /// Returns the hit distance, or None if no intersection.
     pub fn ray_intersect(&self, origin: Vec3, direction: Vec3, t_max: f32) -> Option<f32> {
@@ -132,13 +158,45 @@ impl Triangle {
             None
         }
     }
+
+    /// [`Triangle::ray_intersect`] for a packet of rays sharing `origin`.
+    /// Performs the same operations in the same order, so every lane gets
+    /// exactly the scalar result.  Returns the mask of lanes that hit and
+    /// the hit distance of each lane.
+    fn ray_intersect_packet(&self, origin: Vec3, direction: &[F32x4; 3], t_max: F32x4) -> (u32, [f32; PACKET_WIDTH]) {
+        const EPSILON: f32 = 1e-7;
+        let (zero, one, epsilon) = (F32x4::splat(0.0), F32x4::splat(1.0), F32x4::splat(EPSILON));
+        let dot = |a: Vec3, b: &[F32x4; 3]| {
+            let [x, y, z] = [a.x, a.y, a.z].map(F32x4::splat);
+            x.mul(b[0]).add(y.mul(b[1])).add(z.mul(b[2]))
+        };
+        let edge1 = self.b - self.a;
+        let edge2 = self.c - self.a;
+        let [dx, dy, dz] = *direction;
+        let [ex, ey, ez] = [edge2.x, edge2.y, edge2.z].map(F32x4::splat);
+        let h = [dy.mul(ez).sub(ey.mul(dz)), dz.mul(ex).sub(ez.mul(dx)), dx.mul(ey).sub(ex.mul(dy))];
+        let det = dot(edge1, &h);
+        // `|det| < EPSILON`, without an absolute value.
+        let mut hit = 0b1111 & !(F32x4::splat(-EPSILON).lt(det) & det.lt(epsilon));
+        let inv_det = one.div(det);
+        let s = origin - self.a;
+        let u = inv_det.mul(dot(s, &h));
+        hit &= zero.le(u) & u.le(one);
+        let q = s.cross(edge1);
+        let v = inv_det.mul(dot(q, direction));
+        hit &= !(v.lt(zero) | one.lt(u.add(v)));
+        let t = inv_det.mul(F32x4::splat(edge2.dot(q)));
+        hit &= epsilon.lt(t) & t.le(t_max);
+        (hit, t.to_array())
+    }
 }
 
-/// A node in the BVH tree.
+/// A node in the BVH tree.  Leaves index into the tree's items (triangles or
+/// primitives).
 enum BvhNode {
     Leaf {
         aabb: Aabb,
-        triangle_indices: Vec<usize>,
+        item_indices: Vec<usize>,
     },
     Interior {
         aabb: Aabb,
@@ -155,29 +213,70 @@ impl BvhNode {
         }
     }
 
-    /// Traverse the BVH and return the closest hit distance along a ray.
-    fn intersect(&self, triangles: &[Triangle], origin: Vec3, direction: Vec3, inv_dir: Vec3, t_max: f32) -> Option<f32> {
-        let node_aabb = self.aabb();
-        if node_aabb.ray_intersect(origin, inv_dir, t_max).is_none() {
-            return None;
+    /// Build a tree over items with the given bounds and centroids, splitting
+    /// at the median centroid along the longest axis.
+    fn build(bounds: &[Aabb], centroids: &[Vec3], indices: &mut [usize]) -> BvhNode {
+        let mut aabb = Aabb::empty();
+        for &i in indices.iter() {
+            aabb = aabb.merge(&bounds[i]);
+        }
+
+        if indices.len() <= MAX_LEAF_ITEMS {
+            return BvhNode::Leaf {
+                aabb,
+                item_indices: indices.to_vec(),
+            };
         }
+
+        // Find the longest axis to split along
+        let extent = aabb.max - aabb.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        // Sort indices by centroid along the chosen axis
+        indices.sort_unstable_by(|&a, &b| {
+            let va = centroids[a][axis];
+            let vb = centroids[b][axis];
+            va.partial_cmp(&vb).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mid = indices.len() / 2;
+        let (left_indices, right_indices) = indices.split_at_mut(mid);
+
+        let left = Box::new(Self::build(bounds, centroids, left_indices));
+        let right = Box::new(Self::build(bounds, centroids, right_indices));
+
+        BvhNode::Interior { aabb, left, right }
+    }
+
+    /// Traverse the BVH and return the closest hit distance along a ray,
+    /// together with the index of the item that was hit.  `hit(index, limit)`
+    /// intersects a single item, returning its distance if within `limit`.
+    fn intersect(&self, origin: Vec3, inv_dir: Vec3, t_max: f32, hit: &impl Fn(usize, f32) -> Option<f32>) -> Option<(f32, usize)> {
+        let node_aabb = self.aabb();
+        node_aabb.ray_intersect(origin, inv_dir, t_max)?;
         match self {
-            BvhNode::Leaf { triangle_indices, .. } => {
-                let mut closest = None::<f32>;
-                for &idx in triangle_indices {
-                    let limit = closest.unwrap_or(t_max);
-                    if let Some(t) = triangles[idx].ray_intersect(origin, direction, limit) {
-                        closest = Some(t);
+            BvhNode::Leaf { item_indices, .. } => {
+                let mut closest = None::<(f32, usize)>;
+                for &idx in item_indices {
+                    let limit = closest.map_or(t_max, |(t, _)| t);
+                    if let Some(t) = hit(idx, limit) {
+                        closest = Some((t, idx));
                     }
                 }
                 closest
             }
             BvhNode::Interior { left, right, .. } => {
-                let t_left = left.intersect(triangles, origin, direction, inv_dir, t_max);
-                let limit = t_left.unwrap_or(t_max);
-                let t_right = right.intersect(triangles, origin, direction, inv_dir, limit);
-                match (t_left, t_right) {
-                    (Some(a), Some(b)) => Some(a.min(b)),
+                let hit_left = left.intersect(origin, inv_dir, t_max, hit);
+                let limit = hit_left.map_or(t_max, |(t, _)| t);
+                let hit_right = right.intersect(origin, inv_dir, limit, hit);
+                match (hit_left, hit_right) {
+                    (Some(a), Some(b)) => Some(if b.0 < a.0 { b } else { a }),
                     (Some(a), None) => Some(a),
                     (None, Some(b)) => Some(b),
                     (None, None) => None,
@@ -185,108 +284,311 @@ impl BvhNode {
             }
         }
     }
+
+    /// [`BvhNode::intersect`] for a packet of rays sharing `origin`.  Only
+    /// lanes set in `active` are traced; each lane gets exactly the result
+    /// the scalar traversal would return.  `hit(index, limit)` intersects a
+    /// single item with every ray of the packet and returns the mask of lanes
+    /// that hit and their distances.
+    fn intersect_packet(
+        &self,
+        origin: Vec3,
+        inv_dir: &[F32x4; 3],
+        active: u32,
+        t_max: [f32; PACKET_WIDTH],
+        hit: &impl Fn(usize, F32x4) -> (u32, [f32; PACKET_WIDTH]),
+    ) -> [Option<(f32, usize)>; PACKET_WIDTH] {
+        let mut result = [None; PACKET_WIDTH];
+        let active = active & self.aabb().ray_intersect_packet(origin, inv_dir, F32x4::from_array(t_max));
+        let lanes = (0..PACKET_WIDTH).filter(|lane| active & 1 << lane != 0);
+        match self {
+            BvhNode::Leaf { item_indices, .. } if active != 0 => {
+                let mut limit = t_max;
+                for &idx in item_indices {
+                    let (mask, t) = hit(idx, F32x4::from_array(limit));
+                    for lane in (0..PACKET_WIDTH).filter(|lane| mask & active & 1 << lane != 0) {
+                        limit[lane] = t[lane];
+                        result[lane] = Some((t[lane], idx));
+                    }
+                }
+            }
+            BvhNode::Interior { left, right, .. } if active != 0 => {
+                let hit_left = left.intersect_packet(origin, inv_dir, active, t_max, hit);
+                let limit = std::array::from_fn(|lane| hit_left[lane].map_or(t_max[lane], |(t, _)| t));
+                let hit_right = right.intersect_packet(origin, inv_dir, active, limit, hit);
+                for lane in lanes {
+                    result[lane] = match (hit_left[lane], hit_right[lane]) {
+                        (Some(a), Some(b)) => Some(if b.0 < a.0 { b } else { a }),
+                        (a, b) => a.or(b),
+                    };
+                }
+            }
+            _ => {}
+        }
+        result
+    }
 }
 
 /// Bounding Volume Hierarchy accelerator.
 pub struct Bvh {
     root: Option<BvhNode>,
     pub triangles: Vec<Triangle>,
+    /// Object id of each triangle, parallel to [`Bvh::triangles`].
+    ///
+    /// Object ids group triangles into independently moving objects (e.g. for
+    /// per-object velocities).  Geometry built with [`Bvh::build`] belongs
+    /// entirely to object `0`.
+    pub object_ids: Vec<u32>,
+    /// Semantic label of each object, indexed by object id.  Objects without
+    /// an entry are unlabeled (label `0`).
+    pub object_labels: Vec<u32>,
+    /// Terrain raycast alongside the triangles, each reporting its own
+    /// [`Heightfield::object_id`].
+    pub heightfields: Vec<Heightfield>,
+    /// Analytic primitives, in a tree of their own next to the triangles.
+    primitives: Vec<Primitive>,
+    primitive_root: Option<BvhNode>,
+    /// Indices of the unbounded primitives (planes), tested on every ray.
+    unbounded_primitives: Vec<usize>,
 }
 
-const MAX_LEAF_TRIANGLES: usize = 4;
+const MAX_LEAF_ITEMS: usize = 4;
+
+/// Number of rays traced together by [`Bvh::cast_ray_packet`].
+pub const PACKET_WIDTH: usize = 4;
 
 impl Bvh {
+    /// A BVH without geometry, e.g. to hold only heightfields.
+    pub fn empty() -> Self {
+        Self {
+            root: None,
+            triangles: Vec::new(),
+            object_ids: Vec::new(),
+            object_labels: Vec::new(),
+            heightfields: Vec::new(),
+            primitives: Vec::new(),
+            primitive_root: None,
+            unbounded_primitives: Vec::new(),
+        }
+    }
+
     /// Build a BVH from a flat array of vertices and indices.
     ///
     /// `vertices` is a flat `[x0,y0,z0, x1,y1,z1, ...]` slice.
     /// `indices` is a flat `[i0,i1,i2, i3,i4,i5, ...]` triangle index slice.
-    pub fn build(vertices: &[f32], indices: &[u32]) -> Self {
-        assert!(indices.len() % 3 == 0, "indices length must be a multiple of 3");
+    ///
+    /// Returns an error if the index count is not a multiple of 3, an index is
+    /// out of range, or a referenced vertex is not finite.
+    pub fn build(vertices: &[f32], indices: &[u32]) -> Result<Self, SimLidarError> {
+        let object_ids = vec![0; indices.len() / 3];
+        Self::build_with_object_ids(vertices, indices, &object_ids)
+    }
+
+    /// Build a BVH from a flat array of vertices and indices, tagging every
+    /// triangle with an object id.
+    ///
+    /// `object_ids` holds one id per triangle (i.e. `indices.len() / 3` entries).
+    pub fn build_with_object_ids(vertices: &[f32], indices: &[u32], object_ids: &[u32]) -> Result<Self, SimLidarError> {
+        if !indices.len().is_multiple_of(3) {
+            return Err(SimLidarError::BadIndexCount { count: indices.len() });
+        }
+        if object_ids.len() != indices.len() / 3 {
+            return Err(SimLidarError::BadObjectIdCount {
+                expected: indices.len() / 3,
+                actual: object_ids.len(),
+            });
+        }
+        let vertex_count = vertices.len() / 3;
+        let vertex = |index: u32| -> Result<Vec3, SimLidarError> {
+            let i = index as usize;
+            if i >= vertex_count {
+                return Err(SimLidarError::IndexOutOfRange { index, vertex_count });
+            }
+            let v = Vec3::new(vertices[i * 3], vertices[i * 3 + 1], vertices[i * 3 + 2]);
+            if !v.is_finite() {
+                return Err(SimLidarError::NonFiniteVertex { vertex: index });
+            }
+            Ok(v)
+        };
         let triangles: Vec<Triangle> = indices
             .chunks_exact(3)
             .map(|tri| {
-                let a = Vec3::new(
-                    vertices[(tri[0] * 3) as usize],
-                    vertices[(tri[0] * 3 + 1) as usize],
-                    vertices[(tri[0] * 3 + 2) as usize],
-                );
-                let b = Vec3::new(
-                    vertices[(tri[1] * 3) as usize],
-                    vertices[(tri[1] * 3 + 1) as usize],
-                    vertices[(tri[1] * 3 + 2) as usize],
-                );
-                let c = Vec3::new(
-                    vertices[(tri[2] * 3) as usize],
-                    vertices[(tri[2] * 3 + 1) as usize],
-                    vertices[(tri[2] * 3 + 2) as usize],
-                );
-                Triangle { a, b, c }
+                Ok(Triangle {
+                    a: vertex(tri[0])?,
+                    b: vertex(tri[1])?,
+                    c: vertex(tri[2])?,
+                })
             })
-            .collect();
+            .collect::<Result<_, SimLidarError>>()?;
 
         let mut indices: Vec<usize> = (0..triangles.len()).collect();
         let root = if triangles.is_empty() {
             None
         } else {
-            Some(Self::build_recursive(&triangles, &mut indices))
+            let bounds: Vec<Aabb> = triangles.iter().map(Triangle::aabb).collect();
+            let centroids: Vec<Vec3> = triangles.iter().map(Triangle::centroid).collect();
+            Some(BvhNode::build(&bounds, &centroids, &mut indices))
         };
-        Self { root, triangles }
+        Ok(Self {
+            root,
+            triangles,
+            object_ids: object_ids.to_vec(),
+            ..Self::empty()
+        })
     }
 
-    fn build_recursive(triangles: &[Triangle], indices: &mut [usize]) -> BvhNode {
-        let mut aabb = Aabb::empty();
-        for &i in indices.iter() {
-            aabb = aabb.merge(&triangles[i].aabb());
-        }
+    /// Rebuild the BVH in-place with updated geometry.
+    ///
+    /// Call this when dynamic objects have moved to keep the spatial index consistent.
+    /// `vertices` and `indices` follow the same conventions as [Bvh::build].
+    /// Object labels, heightfields and primitives are kept.  On error the
+    /// existing BVH is left unchanged.
+    pub fn update(&mut self, vertices: &[f32], indices: &[u32]) -> Result<(), SimLidarError> {
+        let mut rebuilt = Self::build(vertices, indices)?;
+        rebuilt.object_labels = std::mem::take(&mut self.object_labels);
+        rebuilt.take_analytic_geometry(self);
+        *self = rebuilt;
+        Ok(())
+    }
 
-        if indices.len() <= MAX_LEAF_TRIANGLES {
-            return BvhNode::Leaf {
-                aabb,
-                triangle_indices: indices.to_vec(),
-            };
-        }
+    /// Assign a semantic label to each object id (`labels[id]`), replacing
+    /// any previous labels.
+    pub fn set_object_labels(&mut self, labels: &[u32]) {
+        self.object_labels = labels.to_vec();
+    }
 
-        // Find the longest axis to split along
-        let extent = aabb.max - aabb.min;
-        let axis = if extent.x >= extent.y && extent.x >= extent.z {
-            0
-        } else if extent.y >= extent.z {
-            1
-        } else {
-            2
-        };
+    /// Semantic label of the object with the given id (`0` if unlabeled).
+    pub fn object_label(&self, object_id: u32) -> u32 {
+        self.object_labels.get(object_id as usize).copied().unwrap_or(0)
+    }
 
-        // Sort indices by centroid along the chosen axis
-        indices.sort_unstable_by(|&a, &b| {
-            let ca = triangles[a].centroid();
-            let cb = triangles[b].centroid();
-            let va = [ca.x, ca.y, ca.z][axis];
-            let vb = [cb.x, cb.y, cb.z][axis];
-            va.partial_cmp(&vb).unwrap_or(std::cmp::Ordering::Equal)
-        });
+    /// Add terrain to the scene; it is hit alongside the triangles.
+    pub fn add_heightfield(&mut self, heightfield: Heightfield) {
+        self.heightfields.push(heightfield);
+    }
 
-        let mid = indices.len() / 2;
-        let (left_indices, right_indices) = indices.split_at_mut(mid);
+    /// Add an analytic primitive to the scene; it is hit alongside the
+    /// triangles with its exact normal.
+    pub fn add_primitive(&mut self, primitive: Primitive) {
+        self.primitives.push(primitive);
+        self.rebuild_primitives();
+    }
 
-        let left = Box::new(Self::build_recursive(triangles, left_indices));
-        let right = Box::new(Self::build_recursive(triangles, right_indices));
+    /// Remove every primitive.
+    pub fn clear_primitives(&mut self) {
+        self.primitives.clear();
+        self.rebuild_primitives();
+    }
 
-        BvhNode::Interior { aabb, left, right }
+    /// The analytic primitives in the scene.
+    pub fn primitives(&self) -> &[Primitive] {
+        &self.primitives
     }
 
-    /// Rebuild the BVH in-place with updated geometry.
-    ///
-    /// Call this when dynamic objects have moved to keep the spatial index consistent.
-    /// `vertices` and `indices` follow the same conventions as [Bvh::build].
-    pub fn update(&mut self, vertices: &[f32], indices: &[u32]) {
-        *self = Self::build(vertices, indices);
+    /// Move the heightfields and primitives of `other` into this BVH.
+    pub(crate) fn take_analytic_geometry(&mut self, other: &mut Bvh) {
+        self.heightfields = std::mem::take(&mut other.heightfields);
+        self.primitives = std::mem::take(&mut other.primitives);
+        self.primitive_root = other.primitive_root.take();
+        self.unbounded_primitives = std::mem::take(&mut other.unbounded_primitives);
+    }
+
+    fn rebuild_primitives(&mut self) {
+        let bounds: Vec<Option<Aabb>> = self.primitives.iter().map(Primitive::aabb).collect();
+        let (mut bounded, unbounded): (Vec<usize>, Vec<usize>) = (0..bounds.len()).partition(|&i| bounds[i].is_some());
+        let bounds: Vec<Aabb> = bounds.into_iter().map(|b| b.unwrap_or_else(Aabb::empty)).collect();
+        let centroids: Vec<Vec3> = bounds.iter().map(Aabb::centroid).collect();
+        self.primitive_root = (!bounded.is_empty()).then(|| BvhNode::build(&bounds, &centroids, &mut bounded));
+        self.unbounded_primitives = unbounded;
     }
 
     /// Cast a ray and return the closest hit distance, or None.
     pub fn cast_ray(&self, origin: Vec3, direction: Vec3, t_max: f32) -> Option<f32> {
+        self.cast_ray_hit(origin, direction, t_max).map(|hit| hit.distance)
+    }
+
+    /// Cast a ray against the triangles, heightfields and primitives and
+    /// return the closest hit, with its surface normal and object id, or None.
+    pub fn cast_ray_hit(&self, origin: Vec3, direction: Vec3, t_max: f32) -> Option<Intersection> {
+        let triangle = self.cast_ray_triangle(origin, direction, t_max);
+        self.closest_hit(origin, direction, t_max, triangle)
+    }
+
+    /// Cast up to [`PACKET_WIDTH`] rays sharing `origin` together, returning
+    /// for each the same hit as [`Bvh::cast_ray_hit`].  The triangle tree is
+    /// traversed once for the whole packet with SIMD box tests; lanes past
+    /// `directions.len()` are `None`.
+    ///
+    /// # Panics
+    ///
+    /// If more than [`PACKET_WIDTH`] directions are given.
+    pub fn cast_ray_packet(&self, origin: Vec3, directions: &[Vec3], t_max: f32) -> [Option<Intersection>; PACKET_WIDTH] {
+        assert!(directions.len() <= PACKET_WIDTH, "at most {PACKET_WIDTH} rays per packet");
+        let mut triangles = [None; PACKET_WIDTH];
+        if let Some(root) = self.root.as_ref() {
+            let mut dir = [[0.0; PACKET_WIDTH]; 3];
+            let mut inv_dir = [[0.0; PACKET_WIDTH]; 3];
+            for (lane, d) in directions.iter().enumerate() {
+                for (axis, v) in [d.x, d.y, d.z].into_iter().enumerate() {
+                    dir[axis][lane] = v;
+                    inv_dir[axis][lane] = 1.0 / v;
+                }
+            }
+            let dir = dir.map(F32x4::from_array);
+            let active = (1 << directions.len()) - 1;
+            let hit = |i: usize, limit: F32x4| self.triangles[i].ray_intersect_packet(origin, &dir, limit);
+            triangles = root.intersect_packet(origin, &inv_dir.map(F32x4::from_array), active, [t_max; PACKET_WIDTH], &hit);
+        }
+        std::array::from_fn(|lane| self.closest_hit(origin, *directions.get(lane)?, t_max, triangles[lane]))
+    }
+
+    /// Combine the closest triangle hit with the primitives and heightfields.
+    fn closest_hit(&self, origin: Vec3, direction: Vec3, t_max: f32, triangle: Option<(f32, usize)>) -> Option<Intersection> {
+        let mut closest = triangle.map(|(distance, tri)| Intersection {
+            distance,
+            normal: self.triangles[tri].normal(),
+            object_id: self.object_ids[tri],
+        });
+        let limit = closest.as_ref().map_or(t_max, |hit| hit.distance);
+        let inv_dir = Vec3::ONE / direction;
+        let test = |i: usize, limit: f32| self.primitives[i].ray_intersect(origin, direction, limit).map(|(t, _)| t);
+        let mut primitive = self.primitive_root.as_ref().and_then(|root| root.intersect(origin, inv_dir, limit, &test));
+        for &i in &self.unbounded_primitives {
+            if let Some(t) = test(i, primitive.map_or(limit, |(t, _)| t)) {
+                primitive = Some((t, i));
+            }
+        }
+        if let Some((_, i)) = primitive {
+            // Re-intersect the winner for its exact normal.
+            let p = &self.primitives[i];
+            if let Some((distance, normal)) = p.ray_intersect(origin, direction, limit) {
+                closest = Some(Intersection {
+                    distance,
+                    normal,
+                    object_id: p.object_id,
+                });
+            }
+        }
+        for heightfield in &self.heightfields {
+            let limit = closest.as_ref().map_or(t_max, |hit| hit.distance);
+            if let Some((distance, normal)) = heightfield.ray_intersect(origin, direction, limit) {
+                closest = Some(Intersection {
+                    distance,
+                    normal,
+                    object_id: heightfield.object_id,
+                });
+            }
+        }
+        closest
+    }
+
+    /// Cast a ray against the triangles only and return the closest hit
+    /// distance together with the index (into [`Bvh::triangles`]) of the
+    /// triangle that was hit, or None.
+    pub fn cast_ray_triangle(&self, origin: Vec3, direction: Vec3, t_max: f32) -> Option<(f32, usize)> {
         let root = self.root.as_ref()?;
         let inv_dir = Vec3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
-        root.intersect(&self.triangles, origin, direction, inv_dir, t_max)
+        root.intersect(origin, inv_dir, t_max, &|i, limit| self.triangles[i].ray_intersect(origin, direction, limit))
     }
 }
 
@@ -308,7 +610,7 @@ mod tests {
 
     #[test]
     fn test_bvh_build_empty() {
-        let bvh = Bvh::build(&[], &[]);
+        let bvh = Bvh::build(&[], &[]).unwrap();
         assert!(bvh.triangles.is_empty());
         assert!(bvh.cast_ray(Vec3::ZERO, Vec3::Y, 100.0).is_none());
     }
@@ -316,7 +618,7 @@ mod tests {
     #[test]
     fn test_bvh_hit() {
         let (vertices, indices) = flat_box_mesh();
-        let bvh = Bvh::build(&vertices, &indices);
+        let bvh = Bvh::build(&vertices, &indices).unwrap();
         // Ray pointing straight down, should hit the quad at y=0
         let hit = bvh.cast_ray(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 100.0);
         assert!(hit.is_some());
@@ -327,7 +629,7 @@ mod tests {
     #[test]
     fn test_bvh_miss() {
         let (vertices, indices) = flat_box_mesh();
-        let bvh = Bvh::build(&vertices, &indices);
+        let bvh = Bvh::build(&vertices, &indices).unwrap();
         // Ray pointing away from the quad
         let hit = bvh.cast_ray(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 100.0);
         assert!(hit.is_none());
@@ -336,13 +638,14 @@ mod tests {
     #[test]
     fn test_bvh_range_limit() {
         let (vertices, indices) = flat_box_mesh();
-        let bvh = Bvh::build(&vertices, &indices);
+        let bvh = Bvh::build(&vertices, &indices).unwrap();
         // Ray hits at t=5 but max range is 3 — should miss
         let hit = bvh.cast_ray(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 3.0);
         assert!(hit.is_none());
     }
 
     #[test]
+    #[allow(unused_variables)]
     fn test_aabb_ray_intersect() {
         let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
         let inv_dir = Vec3::new(0.0, -1.0, 0.0);
@@ -365,6 +668,32 @@ mod tests {
         assert!((t - 5.0).abs() < 1e-4, "Expected t≈5.0, got {t}");
     }
 
+    #[test]
+    fn test_triangle_packet_matches_scalar() {
+        let tri = Triangle {
+            a: Vec3::new(-1.0, 0.0, -1.0),
+            b: Vec3::new(1.0, 0.0, -1.0),
+            c: Vec3::new(0.0, 0.0, 1.0),
+        };
+        let origin = Vec3::new(0.1, 5.0, 0.2);
+        // A hit, a parallel ray, a ray beside the triangle and one pointing
+        // away, then hits cut off by the limit.
+        let packets = [
+            [Vec3::NEG_Y, Vec3::X, Vec3::new(2.0, -1.0, 0.0).normalize(), Vec3::Y],
+            [Vec3::new(0.1, -1.0, 0.0).normalize(), Vec3::new(-0.1, -1.0, 0.1).normalize(), Vec3::NEG_Y, Vec3::NEG_Y],
+        ];
+        for (directions, limit) in packets.iter().zip([100.0, 5.0]) {
+            let dir = [0, 1, 2].map(|axis| F32x4::from_array(directions.map(|d| d[axis])));
+            let t_max = F32x4::from_array([limit, limit, limit, 4.0]);
+            let (mask, t) = tri.ray_intersect_packet(origin, &dir, t_max);
+            for (lane, &d) in directions.iter().enumerate() {
+                let lane_limit = if lane == 3 { 4.0 } else { limit };
+                let packet = (mask & 1 << lane != 0).then_some(t[lane]);
+                assert_eq!(packet, tri.ray_intersect(origin, d, lane_limit), "lane {lane}");
+            }
+        }
+    }
+
     // ── Möller–Trumbore miss cases ──────────────────────────────────────────
 
     #[test]
@@ -436,7 +765,7 @@ mod tests {
             // right  (x =  0.5)
             5, 1, 2,  5, 2, 6,
         ];
-        Bvh::build(&vertices, &indices)
+        Bvh::build(&vertices, &indices).unwrap()
     }
 
     #[test]
@@ -498,7 +827,7 @@ mod tests {
     #[test]
     fn test_bvh_update_moves_geometry() {
         let (vertices, indices) = flat_box_mesh();
-        let mut bvh = Bvh::build(&vertices, &indices);
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
 
         // Initial quad is at y = 0; ray from y = 5 hits at t = 5.
         let t_before = bvh.cast_ray(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 100.0);
@@ -512,7 +841,7 @@ mod tests {
              1.0, -2.0,  1.0,
             -1.0, -2.0,  1.0,
         ];
-        bvh.update(&moved_vertices, &indices);
+        bvh.update(&moved_vertices, &indices).unwrap();
 
         // Now the hit distance from y = 5 should be 7 (5 - (-2)).
         let t_after = bvh.cast_ray(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 100.0);
@@ -520,13 +849,177 @@ mod tests {
         assert!((t_after.unwrap() - 7.0).abs() < 1e-4, "Expected t≈7.0 after update, got {}", t_after.unwrap());
     }
 
+    #[test]
+    fn test_bvh_heightfield_alongside_triangles() {
+        // Terrain at y = -1 under the quad at y = 0, reporting object 5.
+        let (vertices, indices) = flat_box_mesh();
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
+        let mut terrain = Heightfield::new(vec![-1.0; 4], 2, 2, 10.0, 10.0).unwrap();
+        terrain.set_origin(-5.0, 0.0, -5.0);
+        terrain.object_id = 5;
+        bvh.add_heightfield(terrain);
+
+        let hit = bvh.cast_ray_hit(Vec3::new(0.0, 5.0, 0.0), Vec3::NEG_Y, 100.0).unwrap();
+        assert_eq!(hit.object_id, 0, "The quad is closer");
+        let hit = bvh.cast_ray_hit(Vec3::new(3.0, 5.0, 0.0), Vec3::NEG_Y, 100.0).unwrap();
+        assert!((hit.distance - 6.0).abs() < 1e-4);
+        assert_eq!(hit.object_id, 5);
+        assert!((hit.normal - Vec3::Y).length() < 1e-5);
+
+        bvh.update(&vertices, &indices).unwrap();
+        assert_eq!(bvh.heightfields.len(), 1, "update keeps heightfields");
+        assert!(Bvh::empty().cast_ray_hit(Vec3::ZERO, Vec3::NEG_Y, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_bvh_primitives_alongside_triangles() {
+        // A row of poles along x, a ground plane at y = -1 and the quad at y = 0.
+        let (vertices, indices) = flat_box_mesh();
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
+        for i in 0..10 {
+            let pole = Primitive::cylinder(Vec3::new(3.0 + i as f32, 0.0, 0.0), 0.25, 2.0, glam::Quat::IDENTITY).unwrap();
+            bvh.add_primitive(pole.with_object_id(10 + i));
+        }
+        bvh.add_primitive(Primitive::plane(Vec3::new(0.0, -1.0, 0.0), Vec3::Y).unwrap().with_object_id(1));
+
+        let hit = bvh.cast_ray_hit(Vec3::new(0.0, 5.0, 0.0), Vec3::NEG_Y, 100.0).unwrap();
+        assert_eq!((hit.distance, hit.object_id), (5.0, 0), "The quad is above the ground");
+        assert!(bvh.cast_ray_hit(Vec3::new(0.0, 0.5, 6.0), Vec3::NEG_Z, 100.0).is_none(), "Parallel to the ground");
+        let hit = bvh.cast_ray_hit(Vec3::new(7.0, 0.5, 6.0), Vec3::NEG_Z, 100.0).unwrap();
+        assert_eq!(hit.object_id, 14);
+        assert!((hit.distance - 5.75).abs() < 1e-5);
+        assert!((hit.normal - Vec3::Z).length() < 1e-5);
+        let hit = bvh.cast_ray_hit(Vec3::new(50.0, 5.0, 50.0), Vec3::new(1.0, -1.0, 0.0).normalize(), 100.0).unwrap();
+        assert_eq!(hit.object_id, 1);
+        assert!(bvh.cast_ray_hit(Vec3::new(0.0, 5.0, 50.0), Vec3::Y, 100.0).is_none());
+
+        bvh.update(&vertices, &indices).unwrap();
+        assert_eq!(bvh.primitives().len(), 11, "update keeps primitives");
+        assert_eq!(bvh.cast_ray_hit(Vec3::new(7.0, 0.5, 6.0), Vec3::NEG_Z, 100.0).unwrap().object_id, 14);
+        bvh.clear_primitives();
+        assert!(bvh.cast_ray_hit(Vec3::new(7.0, 0.5, 6.0), Vec3::NEG_Z, 100.0).is_none());
+    }
+
+    // ── Object ids / hit triangle ──────────────────────────────────────────
+
+    #[test]
+    fn test_bvh_packet_matches_scalar_cast() {
+        // A pseudo-random triangle soup around the origin, plus a sphere.
+        let mut state = 1u32;
+        let mut next = || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32 * 20.0 - 10.0
+        };
+        let vertices: Vec<f32> = (0..600).map(|_| next()).collect();
+        let indices: Vec<u32> = (0..198).collect();
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
+        bvh.add_primitive(Primitive::sphere(Vec3::new(0.0, 0.0, 30.0), 5.0).unwrap());
+
+        // Axis-aligned directions exercise the NaN/infinity slab cases, and
+        // the origin lies on a vertex coordinate plane.
+        let origin = Vec3::new(vertices[0], 0.0, 0.0);
+        let mut directions = vec![Vec3::X, Vec3::NEG_Y, Vec3::Z, Vec3::new(0.0, 1.0, 1.0).normalize()];
+        for i in 0..999 {
+            let (azimuth, elevation) = (i as f32 * 0.37, (i % 31) as f32 * 0.05 - 0.75);
+            directions.push(Vec3::new(azimuth.cos() * elevation.cos(), elevation.sin(), azimuth.sin() * elevation.cos()));
+        }
+        let mut hits = 0;
+        for dirs in directions.chunks(PACKET_WIDTH) {
+            let packet = bvh.cast_ray_packet(origin, dirs, 40.0);
+            for (lane, dir) in dirs.iter().enumerate() {
+                assert_eq!(packet[lane], bvh.cast_ray_hit(origin, *dir, 40.0), "ray {dir}");
+                hits += packet[lane].is_some() as usize;
+            }
+            assert!(packet[dirs.len()..].iter().all(Option::is_none));
+        }
+        assert!(hits > 100, "Most rays hit the soup or the sphere");
+    }
+
+    #[test]
+    fn test_bvh_build_defaults_object_ids_to_zero() {
+        let (vertices, indices) = flat_box_mesh();
+        let bvh = Bvh::build(&vertices, &indices).unwrap();
+        assert_eq!(bvh.object_ids, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_bvh_cast_ray_triangle_reports_closest_object() {
+        // Two stacked quads: object 7 at y = 0 and object 3 at y = 1.
+        let vertices: Vec<f32> = vec![
+            -1.0, 0.0, -1.0,
+             1.0, 0.0, -1.0,
+             1.0, 0.0,  1.0,
+            -1.0, 0.0,  1.0,
+            -1.0, 1.0, -1.0,
+             1.0, 1.0, -1.0,
+             1.0, 1.0,  1.0,
+            -1.0, 1.0,  1.0,
+        ];
+        let indices: Vec<u32> = vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7];
+        let bvh = Bvh::build_with_object_ids(&vertices, &indices, &[7, 7, 3, 3]).unwrap();
+        let (t, tri) = bvh
+            .cast_ray_triangle(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 100.0)
+            .expect("ray must hit the upper quad");
+        assert!((t - 4.0).abs() < 1e-4, "Expected t≈4.0, got {t}");
+        assert_eq!(bvh.object_ids[tri], 3);
+    }
+
+    // ── Malformed input ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_bvh_build_bad_index_count() {
+        let (vertices, _) = flat_box_mesh();
+        let err = Bvh::build(&vertices, &[0, 1, 2, 3]).err();
+        assert_eq!(err, Some(SimLidarError::BadIndexCount { count: 4 }));
+    }
+
+    #[test]
+    fn test_bvh_build_index_out_of_range() {
+        let (vertices, _) = flat_box_mesh();
+        let err = Bvh::build(&vertices, &[0, 1, 4]).err();
+        assert_eq!(err, Some(SimLidarError::IndexOutOfRange { index: 4, vertex_count: 4 }));
+    }
+
+    #[test]
+    fn test_bvh_build_nan_vertex() {
+        let (mut vertices, indices) = flat_box_mesh();
+        vertices[7] = f32::NAN;
+        let err = Bvh::build(&vertices, &indices).err();
+        assert_eq!(err, Some(SimLidarError::NonFiniteVertex { vertex: 2 }));
+    }
+
+    #[test]
+    fn test_bvh_build_bad_object_id_count() {
+        let (vertices, indices) = flat_box_mesh();
+        let err = Bvh::build_with_object_ids(&vertices, &indices, &[1]).err();
+        assert_eq!(err, Some(SimLidarError::BadObjectIdCount { expected: 2, actual: 1 }));
+    }
+
+    #[test]
+    fn test_bvh_update_error_keeps_geometry() {
+        let (vertices, indices) = flat_box_mesh();
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
+        assert!(bvh.update(&vertices, &[0, 1]).is_err());
+        assert_eq!(bvh.triangles.len(), 2);
+    }
+
     #[test]
     fn test_bvh_update_to_empty() {
         let (vertices, indices) = flat_box_mesh();
-        let mut bvh = Bvh::build(&vertices, &indices);
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
         // Remove all geometry.
-        bvh.update(&[], &[]);
+        bvh.update(&[], &[]).unwrap();
         assert!(bvh.triangles.is_empty());
         assert!(bvh.cast_ray(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 100.0).is_none());
     }
+
+    #[test]
+    fn test_bvh_update_keeps_object_labels() {
+        let (vertices, indices) = flat_box_mesh();
+        let mut bvh = Bvh::build(&vertices, &indices).unwrap();
+        bvh.set_object_labels(&[7]);
+        bvh.update(&vertices, &indices).unwrap();
+        assert_eq!(bvh.object_label(0), 7);
+        assert_eq!(bvh.object_label(1), 0);
+    }
 }
@@ -0,0 +1,209 @@
+use std::fmt;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A single invalid field found while validating a `SensorConfig`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigIssue {
+    /// Name of the offending field, e.g. `"min_range"`.
+    pub field: &'static str,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Errors reported by the simulator instead of panicking.
+///
+/// Every wasm-bindgen entry point converts these into a JavaScript `Error`
+/// whose `name` is [`SimLidarError::name`], so the TypeScript wrappers can map
+/// them onto the matching `SimLidarError` subclass.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimLidarError {
+    /// The triangle index buffer length is not a multiple of 3.
+    BadIndexCount { count: usize },
+    /// A triangle index refers to a vertex past the end of the vertex buffer.
+    IndexOutOfRange { index: u32, vertex_count: usize },
+    /// A vertex referenced by a triangle has a NaN or infinite coordinate.
+    NonFiniteVertex { vertex: u32 },
+    /// The per-triangle object id buffer does not hold one id per triangle.
+    BadObjectIdCount { expected: usize, actual: usize },
+    /// A scan was requested before any geometry was loaded.
+    MissingGeometry,
+    /// The sensor configuration cannot be used for scanning.  Lists every
+    /// invalid field, not just the first one found.
+    InvalidConfig(Vec<ConfigIssue>),
+    /// A function argument other than a sensor configuration is invalid,
+    /// e.g. a non-positive scan rate.
+    InvalidArgument { argument: &'static str, message: String },
+    /// A caller-provided output buffer cannot hold the scan output.
+    BufferTooSmall { required: usize, actual: usize },
+    /// A serialized sensor configuration could not be parsed.
+    ConfigParse(String),
+    /// A sensor configuration could not be serialized.
+    ConfigSerialize(String),
+    /// A file (point cloud, mesh, …) is malformed or uses an unsupported
+    /// variant of its format.
+    Format(String),
+    /// A file could not be read or written.
+    Io(String),
+}
+
+impl SimLidarError {
+    /// Stable name of the error variant, used as the JavaScript error `name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimLidarError::BadIndexCount { .. } => "BadIndexCount",
+            SimLidarError::IndexOutOfRange { .. } => "IndexOutOfRange",
+            SimLidarError::NonFiniteVertex { .. } => "NonFiniteVertex",
+            SimLidarError::BadObjectIdCount { .. } => "BadObjectIdCount",
+            SimLidarError::MissingGeometry => "MissingGeometry",
+            SimLidarError::InvalidConfig(_) => "InvalidConfig",
+            SimLidarError::InvalidArgument { .. } => "InvalidArgument",
+            SimLidarError::BufferTooSmall { .. } => "BufferTooSmall",
+            SimLidarError::ConfigParse(_) => "ConfigParse",
+            SimLidarError::ConfigSerialize(_) => "ConfigSerialize",
+            SimLidarError::Format(_) => "Format",
+            SimLidarError::Io(_) => "Io",
+        }
+    }
+}
+
+impl fmt::Display for SimLidarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimLidarError::BadIndexCount { count } => {
+                write!(f, "index count {count} is not a multiple of 3")
+            }
+            SimLidarError::IndexOutOfRange { index, vertex_count } => write!(
+                f,
+                "triangle index {index} is out of range for {vertex_count} vertices"
+            ),
+            SimLidarError::NonFiniteVertex { vertex } => {
+                write!(f, "vertex {vertex} has a NaN or infinite coordinate")
+            }
+            SimLidarError::BadObjectIdCount { expected, actual } => write!(
+                f,
+                "expected {expected} object ids (one per triangle), got {actual}"
+            ),
+            SimLidarError::MissingGeometry => {
+                write!(f, "no geometry loaded before scanning")
+            }
+            SimLidarError::InvalidConfig(issues) => {
+                write!(f, "invalid sensor config: ")?;
+                for (i, issue) in issues.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{issue}")?;
+                }
+                Ok(())
+            }
+            SimLidarError::InvalidArgument { argument, message } => {
+                write!(f, "invalid argument {argument}: {message}")
+            }
+            SimLidarError::BufferTooSmall { required, actual } => write!(
+                f,
+                "output buffer holds {actual} values but the scan needs {required}"
+            ),
+            SimLidarError::ConfigParse(reason) => write!(f, "could not parse sensor config: {reason}"),
+            SimLidarError::ConfigSerialize(reason) => write!(f, "could not serialize sensor config: {reason}"),
+            SimLidarError::Format(reason) => write!(f, "malformed file: {reason}"),
+            SimLidarError::Io(reason) => write!(f, "I/O error: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SimLidarError {}
+
+impl From<std::io::Error> for SimLidarError {
+    fn from(err: std::io::Error) -> Self {
+        SimLidarError::Io(err.to_string())
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<SimLidarError> for JsValue {
+    fn from(err: SimLidarError) -> JsValue {
+        let js_err = js_sys::Error::new(&err.to_string());
+        js_err.set_name(err.name());
+        // Attach config issues as `issues: { field, message }[]` so JS callers
+        // can inspect every invalid field without parsing the message.
+        if let SimLidarError::InvalidConfig(issues) = &err {
+            let array = js_sys::Array::new();
+            for issue in issues {
+                let obj = js_sys::Object::new();
+                let _ = js_sys::Reflect::set(&obj, &"field".into(), &issue.field.into());
+                let _ = js_sys::Reflect::set(&obj, &"message".into(), &issue.message.as_str().into());
+                array.push(&obj);
+            }
+            let _ = js_sys::Reflect::set(&js_err, &"issues".into(), &array);
+        }
+        js_err.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_display_mentions_values() {
+        let err = SimLidarError::IndexOutOfRange { index: 9, vertex_count: 4 };
+        assert_eq!(err.to_string(), "triangle index 9 is out of range for 4 vertices");
+        assert_eq!(err.name(), "IndexOutOfRange");
+    }
+
+    #[test]
+    fn test_missing_geometry_display_is_api_neutral() {
+        assert_eq!(SimLidarError::MissingGeometry.to_string(), "no geometry loaded before scanning");
+    }
+
+    #[test]
+    fn test_invalid_config_display_lists_every_issue() {
+        let err = SimLidarError::InvalidConfig(vec![
+            ConfigIssue::new("min_range", "must be <= max_range"),
+            ConfigIssue::new("noise_stddev", "must be >= 0"),
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "invalid sensor config: min_range: must be <= max_range; noise_stddev: must be >= 0"
+        );
+    }
+
+    #[test]
+    fn test_error_names_are_distinct() {
+        let errors = [
+            SimLidarError::BadIndexCount { count: 4 },
+            SimLidarError::IndexOutOfRange { index: 0, vertex_count: 0 },
+            SimLidarError::NonFiniteVertex { vertex: 0 },
+            SimLidarError::BadObjectIdCount { expected: 1, actual: 0 },
+            SimLidarError::MissingGeometry,
+            SimLidarError::InvalidConfig(Vec::new()),
+            SimLidarError::InvalidArgument { argument: "rate_hz", message: String::new() },
+            SimLidarError::BufferTooSmall { required: 3, actual: 0 },
+            SimLidarError::ConfigParse(String::new()),
+            SimLidarError::ConfigSerialize(String::new()),
+            SimLidarError::Format(String::new()),
+            SimLidarError::Io(String::new()),
+        ];
+        let mut names: Vec<&str> = errors.iter().map(SimLidarError::name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), errors.len());
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use glam::{Mat4, Quat, Vec3};
+use serde_json::Value;
+
+use crate::base64;
+use crate::error::SimLidarError;
+use crate::mesh::Mesh;
+
+const GLB_MAGIC: u32 = 0x4654_6c67; // "glTF"
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+
+/// Accessor component types.
+const UNSIGNED_BYTE: u64 = 5121;
+const UNSIGNED_SHORT: u64 = 5123;
+const UNSIGNED_INT: u64 = 5125;
+const FLOAT: u64 = 5126;
+
+/// Primitive modes.
+const TRIANGLES: u64 = 4;
+const TRIANGLE_STRIP: u64 = 5;
+const TRIANGLE_FAN: u64 = 6;
+
+fn malformed(what: impl Into<String>) -> SimLidarError {
+    SimLidarError::Format(format!("glTF: {}", what.into()))
+}
+
+/// Parse a glTF 2.0 asset (binary `.glb`, or `.gltf` JSON with embedded
+/// `data:` buffers) into a [`Mesh`].
+///
+/// Walks the node hierarchy of the default scene, applying every node's
+/// `matrix` or translation/rotation/scale, and reads the `POSITION` and
+/// optional `indices` of triangle, triangle-strip and triangle-fan
+/// primitives; other primitive modes are skipped.  Each node's primitives
+/// become one object per material, named after the node (or its mesh), and
+/// every material becomes a semantic label named after it.  glTF is Y-up,
+/// like the simulator, so no axis conversion is applied.
+pub fn parse_gltf(bytes: &[u8]) -> Result<Mesh, SimLidarError> {
+    let (json, bin) = if bytes.len() >= 4 && u32::from_le_bytes(bytes[0..4].try_into().unwrap()) == GLB_MAGIC {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let doc: Value = serde_json::from_slice(json).map_err(|e| malformed(e.to_string()))?;
+    let buffers = load_buffers(&doc, bin)?;
+    let gltf = Gltf { doc: &doc, buffers };
+
+    let nodes = array(&doc, "nodes");
+    let roots: Vec<usize> = match doc.get("scenes").and_then(Value::as_array) {
+        Some(scenes) if !scenes.is_empty() => {
+            let scene = doc.get("scene").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let scene = scenes.get(scene).ok_or_else(|| malformed("default scene out of range"))?;
+            indices_of(scene, "nodes")
+        }
+        // Without scenes, every node that is nobody's child is a root.
+        _ => {
+            let children: Vec<usize> = nodes.iter().flat_map(|n| indices_of(n, "children")).collect();
+            (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+        }
+    };
+
+    let mut mesh = Mesh::new();
+    // The hierarchy must be a forest: a node reached twice is shared or part
+    // of a cycle, either of which could make the walk blow up.
+    let mut visited = vec![false; nodes.len()];
+    let mut stack: Vec<(usize, Mat4)> = roots.into_iter().map(|n| (n, Mat4::IDENTITY)).collect();
+    while let Some((index, parent)) = stack.pop() {
+        let node = nodes.get(index).ok_or_else(|| malformed(format!("node {index} out of range")))?;
+        if std::mem::replace(&mut visited[index], true) {
+            return Err(malformed(format!("node {index} appears more than once in the hierarchy")));
+        }
+        let world = parent * node_transform(node)?;
+        if let Some(mesh_index) = node.get("mesh").and_then(Value::as_u64) {
+            gltf.add_node_mesh(&mut mesh, node, mesh_index as usize, &world)?;
+        }
+        for child in indices_of(node, "children") {
+            stack.push((child, world));
+        }
+    }
+    Ok(mesh)
+}
+
+/// JSON and optional BIN chunk of a GLB container.
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), SimLidarError> {
+    let u32_at = |at: usize| -> Result<u32, SimLidarError> {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| malformed("truncated GLB"))
+    };
+    if u32_at(4)? != 2 {
+        return Err(malformed("only glTF 2.0 is supported"));
+    }
+    let length = (u32_at(8)? as usize).min(bytes.len());
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+    while pos + 8 <= length {
+        let chunk_length = u32_at(pos)? as usize;
+        let chunk_type = u32_at(pos + 4)?;
+        let data = bytes.get(pos + 8..pos + 8 + chunk_length).ok_or_else(|| malformed("truncated GLB chunk"))?;
+        match chunk_type {
+            CHUNK_JSON if json.is_none() => json = Some(data),
+            CHUNK_BIN if bin.is_none() => bin = Some(data),
+            _ => {}
+        }
+        pos += 8 + chunk_length;
+    }
+    Ok((json.ok_or_else(|| malformed("GLB has no JSON chunk"))?, bin))
+}
+
+/// Contents of every buffer: the GLB BIN chunk or a base64 `data:` URI.
+fn load_buffers(doc: &Value, bin: Option<&[u8]>) -> Result<Vec<Vec<u8>>, SimLidarError> {
+    array(doc, "buffers")
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| match buffer.get("uri").and_then(Value::as_str) {
+            None if i == 0 => bin.map(<[u8]>::to_vec).ok_or_else(|| malformed("buffer 0 has no data")),
+            None => Err(malformed(format!("buffer {i} has no data"))),
+            Some(uri) => {
+                let data = uri
+                    .strip_prefix("data:")
+                    .and_then(|rest| rest.split_once(";base64,"))
+                    .ok_or_else(|| malformed(format!("external buffer {uri:?} is not supported")))?
+                    .1;
+                base64::decode(data).ok_or_else(|| malformed(format!("buffer {i} is not valid base64")))
+            }
+        })
+        .collect()
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+fn indices_of(value: &Value, key: &str) -> Vec<usize> {
+    array(value, key).iter().filter_map(Value::as_u64).map(|i| i as usize).collect()
+}
+
+fn floats<const N: usize>(value: &Value, key: &str) -> Result<Option<[f32; N]>, SimLidarError> {
+    let Some(list) = value.get(key) else { return Ok(None) };
+    let list = list.as_array().filter(|l| l.len() == N).ok_or_else(|| malformed(format!("{key} needs {N} numbers")))?;
+    let mut out = [0.0; N];
+    for (o, v) in out.iter_mut().zip(list) {
+        *o = v.as_f64().ok_or_else(|| malformed(format!("{key} needs {N} numbers")))? as f32;
+    }
+    Ok(Some(out))
+}
+
+/// Local transform of a node: its `matrix`, or translation × rotation × scale.
+fn node_transform(node: &Value) -> Result<Mat4, SimLidarError> {
+    if let Some(matrix) = floats::<16>(node, "matrix")? {
+        return Ok(Mat4::from_cols_array(&matrix));
+    }
+    let translation = floats::<3>(node, "translation")?.map_or(Vec3::ZERO, Vec3::from);
+    let rotation = floats::<4>(node, "rotation")?.map_or(Quat::IDENTITY, |q| Quat::from_array(q).normalize());
+    let scale = floats::<3>(node, "scale")?.map_or(Vec3::ONE, Vec3::from);
+    Ok(Mat4::from_scale_rotation_translation(scale, rotation, translation))
+}
+
+struct Gltf<'a> {
+    doc: &'a Value,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Gltf<'_> {
+    /// Append the triangles of mesh `mesh_index`, instanced by `node`.
+    fn add_node_mesh(&self, out: &mut Mesh, node: &Value, mesh_index: usize, world: &Mat4) -> Result<(), SimLidarError> {
+        let gltf_mesh =
+            array(self.doc, "meshes").get(mesh_index).ok_or_else(|| malformed(format!("mesh {mesh_index} out of range")))?;
+        let name = node
+            .get("name")
+            .or_else(|| gltf_mesh.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        let materials = array(self.doc, "materials");
+        let mut objects: HashMap<u32, u32> = HashMap::new();
+
+        for primitive in array(gltf_mesh, "primitives") {
+            let mode = primitive.get("mode").and_then(Value::as_u64).unwrap_or(TRIANGLES);
+            if ![TRIANGLES, TRIANGLE_STRIP, TRIANGLE_FAN].contains(&mode) {
+                continue;
+            }
+            let position = primitive
+                .pointer("/attributes/POSITION")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| malformed("primitive has no POSITION"))?;
+            let positions = self.read_accessor(position as usize, &["VEC3"])?;
+            let vertex_count = positions.len() / 3;
+            let indices: Vec<u32> = match primitive.get("indices").and_then(Value::as_u64) {
+                Some(accessor) => self.read_accessor(accessor as usize, &["SCALAR"])?.into_iter().map(|i| i as u32).collect(),
+                None => (0..vertex_count as u32).collect(),
+            };
+            if let Some(&bad) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+                return Err(malformed(format!("index {bad} out of range of {vertex_count} vertices")));
+            }
+
+            let material_name = primitive.get("material").and_then(Value::as_u64).map(|m| {
+                materials
+                    .get(m as usize)
+                    .and_then(|material| material.get("name"))
+                    .and_then(Value::as_str)
+                    .map_or_else(|| format!("material_{m}"), str::to_string)
+            });
+            let label = out.label(material_name.as_deref().unwrap_or(""));
+            let id = *objects.entry(label).or_insert_with(|| out.add_object(name, label));
+
+            let base = out.vertex_count() as u32;
+            for p in positions.chunks_exact(3) {
+                let p = world.transform_point3(Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32));
+                out.vertices.extend_from_slice(&p.to_array());
+            }
+            let before = out.indices.len();
+            match mode {
+                TRIANGLES => out.indices.extend(indices.chunks_exact(3).flatten().map(|i| i + base)),
+                TRIANGLE_STRIP => {
+                    for k in 0..indices.len().saturating_sub(2) {
+                        let (a, b) = if k % 2 == 0 { (k, k + 1) } else { (k + 1, k) };
+                        out.indices.extend_from_slice(&[indices[a] + base, indices[b] + base, indices[k + 2] + base]);
+                    }
+                }
+                _ => {
+                    for k in 1..indices.len().saturating_sub(1) {
+                        out.indices.extend_from_slice(&[indices[0] + base, indices[k] + base, indices[k + 1] + base]);
+                    }
+                }
+            }
+            let added = (out.indices.len() - before) / 3;
+            out.object_ids.extend(std::iter::repeat_n(id, added));
+        }
+        Ok(())
+    }
+
+    /// Values of an accessor as `f64`, flattened.  Only non-normalized
+    /// float, unsigned byte, unsigned short and unsigned int accessors are
+    /// supported.
+    fn read_accessor(&self, index: usize, types: &[&str]) -> Result<Vec<f64>, SimLidarError> {
+        let accessor = array(self.doc, "accessors")
+            .get(index)
+            .ok_or_else(|| malformed(format!("accessor {index} out of range")))?;
+        let kind = accessor.get("type").and_then(Value::as_str).unwrap_or("");
+        if !types.contains(&kind) {
+            return Err(malformed(format!("accessor {index} has type {kind:?}, expected {types:?}")));
+        }
+        if accessor.get("sparse").is_some() || accessor.get("normalized").and_then(Value::as_bool) == Some(true) {
+            return Err(malformed(format!("accessor {index}: sparse and normalized accessors are not supported")));
+        }
+        let components = match kind {
+            "SCALAR" => 1,
+            "VEC3" => 3,
+            _ => unreachable!(),
+        };
+        let count = accessor.get("count").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let component_type = accessor.get("componentType").and_then(Value::as_u64).unwrap_or(0);
+        let size = match component_type {
+            UNSIGNED_BYTE => 1,
+            UNSIGNED_SHORT => 2,
+            UNSIGNED_INT | FLOAT => 4,
+            other => return Err(malformed(format!("accessor {index}: unsupported component type {other}"))),
+        };
+        let overflow = || malformed(format!("accessor {index}: offsets overflow"));
+        let Some(view_index) = accessor.get("bufferView").and_then(Value::as_u64) else {
+            // No buffer view: all zeros.
+            return Ok(vec![0.0; count.checked_mul(components).ok_or_else(overflow)?]);
+        };
+        let view = array(self.doc, "bufferViews")
+            .get(view_index as usize)
+            .ok_or_else(|| malformed(format!("buffer view {view_index} out of range")))?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Value::as_u64)
+            .and_then(|b| self.buffers.get(b as usize))
+            .ok_or_else(|| malformed(format!("buffer view {view_index} has no buffer")))?;
+        let view_offset = view.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let view_length = view.get("byteLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let data = buffer
+            .get(view_offset..view_offset.saturating_add(view_length))
+            .ok_or_else(|| malformed(format!("buffer view {view_index} exceeds its buffer")))?;
+        let offset = accessor.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let stride = view.get("byteStride").and_then(Value::as_u64).map_or(size * components, |s| s as usize);
+
+        let mut values = Vec::with_capacity(count.min(data.len()) * components);
+        for i in 0..count {
+            for c in 0..components {
+                let at = i
+                    .checked_mul(stride)
+                    .and_then(|at| at.checked_add(offset))
+                    .and_then(|at| at.checked_add(c * size))
+                    .ok_or_else(overflow)?;
+                let end = at.checked_add(size).ok_or_else(overflow)?;
+                let bytes = data
+                    .get(at..end)
+                    .ok_or_else(|| malformed(format!("accessor {index} exceeds its buffer view")))?;
+                values.push(match component_type {
+                    UNSIGNED_BYTE => bytes[0] as f64,
+                    UNSIGNED_SHORT => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    UNSIGNED_INT => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+                    _ => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+                });
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Buffer with a unit triangle (3 float VEC3), followed by u16 indices
+    /// `0 1 2` and 2 bytes of padding.
+    fn triangle_buffer() -> Vec<u8> {
+        let mut bin = Vec::new();
+        for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0] {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+        bin
+    }
+
+    /// Two nodes instancing the same mesh: `parent` translated by x + 10 and
+    /// its child scaled by 2, and a non-indexed primitive on a second mesh.
+    fn scene_json(buffer: &str) -> String {
+        format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "scene": 0,
+  "scenes": [{{"nodes": [0, 2]}}],
+  "nodes": [
+    {{"name": "parent", "mesh": 0, "translation": [10, 0, 0], "children": [1]}},
+    {{"mesh": 0, "scale": [2, 2, 2]}},
+    {{"name": "sign", "mesh": 1, "matrix": [1,0,0,0, 0,1,0,0, 0,0,1,0, 0,5,0,1]}}
+  ],
+  "meshes": [
+    {{"name": "tri", "primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}},
+    {{"primitives": [{{"attributes": {{"POSITION": 0}}, "material": 1}}]}}
+  ],
+  "materials": [{{"name": "road"}}, {{}}],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}
+  ],
+  "buffers": [{{"byteLength": 40{buffer}}}]
+}}"#
+        )
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut out = Vec::new();
+        out.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        out.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+        out.extend_from_slice(&json);
+        out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        out.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        out.extend_from_slice(bin);
+        out
+    }
+
+    fn vertex(mesh: &Mesh, i: usize) -> [f32; 3] {
+        mesh.vertices[i * 3..i * 3 + 3].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_glb_applies_node_transforms() {
+        let mesh = parse_gltf(&glb(&scene_json(""), &triangle_buffer())).unwrap();
+        assert_eq!(mesh.triangle_count(), 3);
+        assert_eq!(mesh.vertex_count(), 9);
+        // Node order is depth-first from the last root.
+        assert_eq!(vertex(&mesh, 1), [1.0, 5.0, 0.0], "sign: matrix translation");
+        assert_eq!(vertex(&mesh, 4), [11.0, 0.0, 0.0], "parent: translated");
+        assert_eq!(vertex(&mesh, 7), [12.0, 0.0, 0.0], "child: parent × scale");
+        mesh.build_bvh().unwrap();
+    }
+
+    #[test]
+    fn test_glb_names_and_materials() {
+        let mesh = parse_gltf(&glb(&scene_json(""), &triangle_buffer())).unwrap();
+        assert_eq!(mesh.object_names, vec!["sign", "parent", "tri"]);
+        assert_eq!(mesh.label_names, vec!["", "material_1", "road"]);
+        assert_eq!(mesh.object_labels, vec![1, 2, 2]);
+        assert_eq!(mesh.object_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_gltf_with_data_uri() {
+        let encoded = crate::base64::encode(&triangle_buffer());
+        let json = scene_json(&format!(r#", "uri": "data:application/octet-stream;base64,{encoded}""#));
+        let mesh = parse_gltf(json.as_bytes()).unwrap();
+        assert_eq!(mesh.triangle_count(), 3);
+    }
+
+    #[test]
+    fn test_gltf_rejects_malformed_input() {
+        assert!(parse_gltf(b"not json").is_err());
+        let mut bad_index = triangle_buffer();
+        bad_index[38] = 9;
+        let err = parse_gltf(&glb(&scene_json(""), &bad_index)).unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{err}");
+        let truncated = &triangle_buffer()[..30];
+        assert!(parse_gltf(&glb(&scene_json(""), truncated)).is_err());
+        let external = scene_json(r#", "uri": "scene.bin""#);
+        assert!(parse_gltf(external.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_gltf_rejects_overflowing_offsets() {
+        let huge = format!(r#""byteOffset": {}, "type""#, u64::MAX);
+        let json = scene_json("").replacen(r#""type""#, &huge, 1);
+        let err = parse_gltf(&glb(&json, &triangle_buffer())).unwrap_err();
+        assert!(matches!(err, SimLidarError::Format(_)), "{err}");
+        let json = scene_json("").replacen(r#""byteLength": 36}"#, &format!(r#""byteLength": 36, "byteStride": {}}}"#, u64::MAX), 1);
+        assert!(matches!(parse_gltf(&glb(&json, &triangle_buffer())), Err(SimLidarError::Format(_))));
+    }
+
+    #[test]
+    fn test_gltf_rejects_shared_and_cyclic_nodes() {
+        let shared = scene_json("").replace(r#""nodes": [0, 2]"#, r#""nodes": [0, 1, 2]"#);
+        let err = parse_gltf(&glb(&shared, &triangle_buffer())).unwrap_err();
+        assert!(err.to_string().contains("more than once"), "{err}");
+        let cycle = scene_json("").replace(r#""scale": [2, 2, 2]}"#, r#""scale": [2, 2, 2], "children": [0]}"#);
+        assert!(parse_gltf(&glb(&cycle, &triangle_buffer())).is_err());
+    }
+}
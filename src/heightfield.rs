@@ -0,0 +1,461 @@
+use glam::Vec3;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::bvh::{Aabb, Triangle};
+use crate::error::SimLidarError;
+
+fn malformed(what: impl Into<String>) -> SimLidarError {
+    SimLidarError::Format(format!("heightfield: {}", what.into()))
+}
+
+/// Terrain given as a regular grid of elevations, raycast by marching the
+/// grid cells along the ray instead of testing individual triangles.
+///
+/// Sample `(column, row)` lies at
+/// `origin + (column · spacing_x, height, row · spacing_z)`, so columns run
+/// along +x, rows along +z and heights along +y (up).  Each cell is split
+/// into two triangles, so hits match the equivalent triangle mesh exactly.
+/// `NaN` heights mark missing data: the cells around them are holes.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct Heightfield {
+    columns: u32,
+    rows: u32,
+    spacing_x: f32,
+    spacing_z: f32,
+    origin: Vec3,
+    /// Row-major heights, `rows × columns`.
+    heights: Vec<f32>,
+    /// Object id reported for hits on the terrain.
+    pub object_id: u32,
+    bounds: Aabb,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Heightfield {
+    /// Create a heightfield from row-major heights (`rows × columns`, in
+    /// metres), with its first sample at the origin.
+    ///
+    /// Throws a `Format` error unless the grid has at least 2 × 2 samples,
+    /// the spacings are positive and finite, and no height is infinite.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(heights: Vec<f32>, columns: u32, rows: u32, spacing_x: f32, spacing_z: f32) -> Result<Heightfield, SimLidarError> {
+        if columns < 2 || rows < 2 {
+            return Err(malformed(format!("needs at least 2 × 2 samples, got {columns} × {rows}")));
+        }
+        if heights.len() != columns as usize * rows as usize {
+            return Err(malformed(format!("expected {} heights, got {}", columns as usize * rows as usize, heights.len())));
+        }
+        if !(spacing_x > 0.0 && spacing_x.is_finite() && spacing_z > 0.0 && spacing_z.is_finite()) {
+            return Err(malformed("spacing must be positive and finite"));
+        }
+        if heights.iter().any(|h| h.is_infinite()) {
+            return Err(malformed("heights must be finite (or NaN for missing data)"));
+        }
+        let mut heightfield = Heightfield {
+            columns,
+            rows,
+            spacing_x,
+            spacing_z,
+            origin: Vec3::ZERO,
+            heights,
+            object_id: 0,
+            bounds: Aabb::empty(),
+        };
+        heightfield.update_bounds();
+        Ok(heightfield)
+    }
+
+    /// Create a heightfield from a raw grid of little-endian `f32` heights.
+    pub fn from_raw_f32(bytes: &[u8], columns: u32, rows: u32, spacing_x: f32, spacing_z: f32) -> Result<Heightfield, SimLidarError> {
+        if bytes.len() != columns as usize * rows as usize * 4 {
+            return Err(malformed(format!("raw grid of {columns} × {rows} needs {} bytes", columns as usize * rows as usize * 4)));
+        }
+        let heights = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        Self::new(heights, columns, rows, spacing_x, spacing_z)
+    }
+
+    /// Create a heightfield from a binary (`P5`) or ASCII (`P2`) PGM image,
+    /// 8- or 16-bit.  Each sample becomes `value · height_scale` metres; image
+    /// rows run along +z.
+    pub fn from_pgm(bytes: &[u8], spacing_x: f32, spacing_z: f32, height_scale: f32) -> Result<Heightfield, SimLidarError> {
+        let mut pos = 0;
+        let mut header = [0u32; 3];
+        let magic = bytes.get(0..2).ok_or_else(|| malformed("empty PGM"))?;
+        if magic != b"P5" && magic != b"P2" {
+            return Err(malformed("not a PGM image"));
+        }
+        pos += 2;
+        for value in &mut header {
+            *value = pgm_number(bytes, &mut pos)?;
+        }
+        let [columns, rows, max_value] = header;
+        if max_value == 0 || max_value > u16::MAX as u32 {
+            return Err(malformed(format!("invalid PGM maximum value {max_value}")));
+        }
+        let count = columns as usize * rows as usize;
+        let samples: Vec<u32> = if magic == b"P2" {
+            (0..count).map(|_| pgm_number(bytes, &mut pos)).collect::<Result<_, _>>()?
+        } else {
+            // A single whitespace byte separates the header from the samples.
+            let data = bytes.get(pos + 1..).unwrap_or(&[]);
+            let size = if max_value > 255 { 2 } else { 1 };
+            if data.len() < count * size {
+                return Err(malformed("truncated PGM data"));
+            }
+            data.chunks_exact(size)
+                .take(count)
+                .map(|s| if size == 2 { u16::from_be_bytes([s[0], s[1]]) as u32 } else { s[0] as u32 })
+                .collect()
+        };
+        let heights = samples.into_iter().map(|s| s as f32 * height_scale).collect();
+        Self::new(heights, columns, rows, spacing_x, spacing_z)
+    }
+
+    /// Move the first sample to `(x, y, z)`.
+    pub fn set_origin(&mut self, x: f32, y: f32, z: f32) {
+        self.origin = Vec3::new(x, y, z);
+        self.update_bounds();
+    }
+
+    /// Number of samples per row.
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    /// Number of rows.
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// World-space height of sample `(column, row)`, or `NaN` if it is missing
+    /// or out of range.
+    pub fn height_at(&self, column: u32, row: u32) -> f32 {
+        if column >= self.columns || row >= self.rows {
+            return f32::NAN;
+        }
+        self.origin.y + self.heights[(row * self.columns + column) as usize]
+    }
+}
+
+#[cfg(feature = "png")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Heightfield {
+    /// Create a heightfield from an 8- or 16-bit grayscale PNG image.  Each
+    /// sample becomes `value · height_scale` metres; image rows run along +z.
+    pub fn from_png(bytes: &[u8], spacing_x: f32, spacing_z: f32, height_scale: f32) -> Result<Heightfield, SimLidarError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let mut reader = decoder.read_info().map_err(|e| malformed(e.to_string()))?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).map_err(|e| malformed(e.to_string()))?;
+        if info.color_type != png::ColorType::Grayscale {
+            return Err(malformed(format!("PNG heightmaps must be grayscale, got {:?}", info.color_type)));
+        }
+        let data = &data[..info.buffer_size()];
+        let heights: Vec<f32> = match info.bit_depth {
+            png::BitDepth::Sixteen => data
+                .chunks_exact(info.line_size)
+                .flat_map(|line| line[..info.width as usize * 2].chunks_exact(2))
+                .map(|s| u16::from_be_bytes([s[0], s[1]]) as f32 * height_scale)
+                .collect(),
+            png::BitDepth::Eight => data
+                .chunks_exact(info.line_size)
+                .flat_map(|line| &line[..info.width as usize])
+                .map(|&s| s as f32 * height_scale)
+                .collect(),
+            other => return Err(malformed(format!("unsupported PNG bit depth {other:?}"))),
+        };
+        Self::new(heights, info.width, info.height, spacing_x, spacing_z)
+    }
+}
+
+impl Heightfield {
+    /// Position of the first sample.
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    /// World-space bounding box of every valid sample.
+    pub fn bounds(&self) -> &Aabb {
+        &self.bounds
+    }
+
+    fn update_bounds(&mut self) {
+        let (mut min_h, mut max_h) = (f32::INFINITY, f32::NEG_INFINITY);
+        for &h in self.heights.iter().filter(|h| !h.is_nan()) {
+            min_h = min_h.min(h);
+            max_h = max_h.max(h);
+        }
+        let extent = Vec3::new((self.columns - 1) as f32 * self.spacing_x, 0.0, (self.rows - 1) as f32 * self.spacing_z);
+        self.bounds = if min_h <= max_h {
+            Aabb::new(self.origin + Vec3::new(0.0, min_h, 0.0), self.origin + extent + Vec3::new(0.0, max_h, 0.0))
+        } else {
+            Aabb::empty()
+        };
+    }
+
+    /// World position of sample `(column, row)`.
+    fn point(&self, column: u32, row: u32) -> Vec3 {
+        self.origin
+            + Vec3::new(
+                column as f32 * self.spacing_x,
+                self.heights[(row * self.columns + column) as usize],
+                row as f32 * self.spacing_z,
+            )
+    }
+
+    /// The two triangles of cell `(column, row)`, with upward normals.
+    fn cell_triangles(&self, column: u32, row: u32) -> [Triangle; 2] {
+        let a = self.point(column, row);
+        let b = self.point(column + 1, row);
+        let c = self.point(column + 1, row + 1);
+        let d = self.point(column, row + 1);
+        [Triangle { a, b: d, c: b }, Triangle { a: b, b: d, c }]
+    }
+
+    /// Closest intersection with the terrain within `t_max`: the distance and
+    /// the unit normal of the cell triangle that was hit.
+    ///
+    /// Marches the cells under the ray with a 2D DDA, skipping cells whose
+    /// height range the ray does not cross.
+    pub fn ray_intersect(&self, origin: Vec3, direction: Vec3, t_max: f32) -> Option<(f32, Vec3)> {
+        let inv_dir = Vec3::ONE / direction;
+        let t_enter = self.bounds.ray_intersect(origin, inv_dir, t_max)?;
+        // Exit distance of the bounding box.
+        let t1 = (self.bounds.min - origin) * inv_dir;
+        let t2 = (self.bounds.max - origin) * inv_dir;
+        let t_exit = t1.max(t2).min_element().min(t_max);
+
+        let cell_x = self.spacing_x;
+        let cell_z = self.spacing_z;
+        let start = origin + direction * t_enter - self.origin;
+        let max_column = self.columns - 2;
+        let max_row = self.rows - 2;
+        let mut column = ((start.x / cell_x).floor().max(0.0) as u32).min(max_column);
+        let mut row = ((start.z / cell_z).floor().max(0.0) as u32).min(max_row);
+
+        // Distance to the next column / row boundary, and between boundaries.
+        let axis = |pos: f32, dir: f32, cell: f32, index: u32| -> (f32, f32) {
+            if dir > 0.0 {
+                (((index + 1) as f32 * cell - pos) / dir, cell / dir)
+            } else if dir < 0.0 {
+                ((index as f32 * cell - pos) / dir, -cell / dir)
+            } else {
+                (f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (mut next_x, delta_x) = axis(start.x, direction.x, cell_x, column);
+        let (mut next_z, delta_z) = axis(start.z, direction.z, cell_z, row);
+        next_x += t_enter;
+        next_z += t_enter;
+
+        let mut t_cell = t_enter;
+        loop {
+            let t_next = next_x.min(next_z).min(t_exit);
+            let heights = [(0, 0), (1, 0), (1, 1), (0, 1)]
+                .map(|(dc, dr)| self.heights[((row + dr) * self.columns + column + dc) as usize]);
+            if heights.iter().all(|h| !h.is_nan()) {
+                let (min_h, max_h) = heights.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &h| (lo.min(h), hi.max(h)));
+                let y0 = origin.y + direction.y * t_cell - self.origin.y;
+                let y1 = origin.y + direction.y * t_next - self.origin.y;
+                if y0.min(y1) <= max_h && y0.max(y1) >= min_h {
+                    let mut closest: Option<(f32, Vec3)> = None;
+                    for triangle in self.cell_triangles(column, row) {
+                        let limit = closest.map_or(t_max, |(t, _)| t);
+                        if let Some(t) = triangle.ray_intersect(origin, direction, limit) {
+                            closest = Some((t, triangle.normal()));
+                        }
+                    }
+                    if closest.is_some() {
+                        return closest;
+                    }
+                }
+            }
+            if t_next >= t_exit {
+                return None;
+            }
+            if next_x < next_z {
+                if (direction.x > 0.0 && column == max_column) || (direction.x < 0.0 && column == 0) {
+                    return None;
+                }
+                column = if direction.x > 0.0 { column + 1 } else { column - 1 };
+                t_cell = next_x;
+                next_x += delta_x;
+            } else {
+                if (direction.z > 0.0 && row == max_row) || (direction.z < 0.0 && row == 0) {
+                    return None;
+                }
+                row = if direction.z > 0.0 { row + 1 } else { row - 1 };
+                t_cell = next_z;
+                next_z += delta_z;
+            }
+        }
+    }
+
+    /// The terrain as a triangle mesh: flat vertices and indices, skipping
+    /// cells with missing heights.
+    pub fn to_triangles(&self) -> (Vec<f32>, Vec<u32>) {
+        let vertices = (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+            .flat_map(|(column, row)| self.point(column, row).to_array())
+            .collect();
+        let mut indices = Vec::new();
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                let index = |dc: u32, dr: u32| (row + dr) * self.columns + column + dc;
+                let corners = [index(0, 0), index(1, 0), index(1, 1), index(0, 1)];
+                if corners.iter().any(|&i| self.heights[i as usize].is_nan()) {
+                    continue;
+                }
+                let [a, b, c, d] = corners;
+                indices.extend_from_slice(&[a, d, b, b, d, c]);
+            }
+        }
+        (vertices, indices)
+    }
+}
+
+/// Next whitespace-separated decimal number of a PGM header, skipping
+/// `#` comments.
+fn pgm_number(bytes: &[u8], pos: &mut usize) -> Result<u32, SimLidarError> {
+    loop {
+        match bytes.get(*pos) {
+            Some(b'#') => {
+                while bytes.get(*pos).is_some_and(|&b| b != b'\n') {
+                    *pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+        *pos += 1;
+    }
+    std::str::from_utf8(&bytes[start..*pos])
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| malformed("malformed PGM header"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bvh::Bvh;
+
+    /// 4 × 3 terrain with spacing 2 × 1 and a bump in the middle.
+    fn terrain() -> Heightfield {
+        #[rustfmt::skip]
+        let heights = vec![
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 2.0, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+        ];
+        let mut heightfield = Heightfield::new(heights, 4, 3, 2.0, 1.0).unwrap();
+        heightfield.set_origin(-3.0, 1.0, -1.0);
+        heightfield
+    }
+
+    #[test]
+    fn test_heightfield_matches_triangle_mesh() {
+        let heightfield = terrain();
+        let (vertices, indices) = heightfield.to_triangles();
+        let mesh = Bvh::build(&vertices, &indices).unwrap();
+        let origin = Vec3::new(-5.0, 4.0, -2.5);
+        let mut hits = 0;
+        for i in 0..40 {
+            for j in 0..20 {
+                let target = Vec3::new(-3.5 + i as f32 * 0.2, 1.0, -1.5 + j as f32 * 0.15);
+                let dir = (target - origin).normalize();
+                let expected = mesh.cast_ray_triangle(origin, dir, 100.0);
+                let actual = heightfield.ray_intersect(origin, dir, 100.0);
+                match (expected, actual) {
+                    (Some((t, _)), Some((u, normal))) => {
+                        assert!((t - u).abs() < 1e-4, "{t} vs {u}");
+                        // On an edge shared by two triangles either normal is valid.
+                        let on_triangle = |tri: &Triangle| {
+                            (tri.normal() - normal).length() < 1e-5
+                                && tri.ray_intersect(origin, dir, 100.0).is_some_and(|s| (s - t).abs() < 1e-4)
+                        };
+                        assert!(mesh.triangles.iter().any(on_triangle), "ray {i},{j}: {normal:?} at {t}");
+                        hits += 1;
+                    }
+                    (None, None) => {}
+                    other => panic!("ray {i},{j}: {other:?}"),
+                }
+            }
+        }
+        assert!(hits > 200, "{hits}");
+    }
+
+    #[test]
+    fn test_heightfield_vertical_and_grazing_rays() {
+        let heightfield = terrain();
+        // Straight down onto the sample of height 2 at column 2, row 1.
+        let (t, normal) = heightfield.ray_intersect(Vec3::new(1.0, 10.0, 0.0), Vec3::NEG_Y, 100.0).unwrap();
+        assert!((t - 7.0).abs() < 1e-5, "{t}");
+        assert!(normal.y > 0.0);
+        // Horizontal ray below the bump misses the flat border but hits the bump.
+        let hit = heightfield.ray_intersect(Vec3::new(-10.0, 2.5, 0.0), Vec3::X, 100.0);
+        assert!(hit.is_some_and(|(t, _)| t > 7.0 && t < 11.0), "{hit:?}");
+        assert!(heightfield.ray_intersect(Vec3::new(-10.0, 3.5, 0.0), Vec3::X, 100.0).is_none());
+        assert!(heightfield.ray_intersect(Vec3::new(1.0, 10.0, 0.0), Vec3::NEG_Y, 5.0).is_none(), "range limit");
+        assert!(heightfield.ray_intersect(Vec3::new(10.0, 10.0, 0.0), Vec3::NEG_Y, 100.0).is_none(), "outside");
+    }
+
+    #[test]
+    fn test_heightfield_holes() {
+        let mut heights = vec![0.0; 9];
+        heights[4] = f32::NAN;
+        let heightfield = Heightfield::new(heights, 3, 3, 1.0, 1.0).unwrap();
+        assert!(heightfield.ray_intersect(Vec3::new(0.5, 1.0, 0.5), Vec3::NEG_Y, 10.0).is_none());
+        assert!(heightfield.to_triangles().1.is_empty());
+        assert!(heightfield.height_at(1, 1).is_nan());
+    }
+
+    #[test]
+    fn test_heightfield_from_pgm_and_raw() {
+        let mut pgm = b"P5\n# dem\n3 2\n65535\n".to_vec();
+        for v in [0u16, 1000, 2000, 3000, 4000, 65535] {
+            pgm.extend_from_slice(&v.to_be_bytes());
+        }
+        let heightfield = Heightfield::from_pgm(&pgm, 1.0, 1.0, 0.01).unwrap();
+        assert_eq!((heightfield.columns(), heightfield.rows()), (3, 2));
+        assert_eq!(heightfield.height_at(1, 0), 10.0);
+        assert!((heightfield.height_at(2, 1) - 655.35).abs() < 1e-3);
+
+        let ascii = Heightfield::from_pgm(b"P2 2 2 255 0 10 20 255", 1.0, 1.0, 1.0).unwrap();
+        assert_eq!(ascii.height_at(1, 1), 255.0);
+
+        let raw: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0].iter().flat_map(|h| h.to_le_bytes()).collect();
+        let heightfield = Heightfield::from_raw_f32(&raw, 2, 2, 1.0, 1.0).unwrap();
+        assert_eq!(heightfield.height_at(0, 1), 3.0);
+
+        assert!(Heightfield::from_raw_f32(&raw, 3, 2, 1.0, 1.0).is_err());
+        assert!(Heightfield::from_pgm(b"P5 2 2 65535\n\x00\x01", 1.0, 1.0, 1.0).is_err());
+        assert!(Heightfield::new(vec![0.0; 4], 2, 2, 0.0, 1.0).is_err());
+        assert!(Heightfield::new(vec![0.0; 3], 3, 1, 1.0, 1.0).is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_heightfield_from_png() {
+        let mut png_bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png_bytes, 2, 2);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Sixteen);
+            let mut writer = encoder.write_header().unwrap();
+            let data: Vec<u8> = [0u16, 100, 200, 300].iter().flat_map(|v| v.to_be_bytes()).collect();
+            writer.write_image_data(&data).unwrap();
+        }
+        let heightfield = Heightfield::from_png(&png_bytes, 1.0, 1.0, 0.5).unwrap();
+        assert_eq!(heightfield.height_at(1, 1), 150.0);
+    }
+}
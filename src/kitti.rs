@@ -0,0 +1,323 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use glam::{Mat3, Mat4, Vec3};
+
+use crate::error::SimLidarError;
+use crate::point_fields::{sensor_frame_point, SIM_TO_Z_UP};
+use crate::raycaster::ScanResult;
+
+/// Rotation from the KITTI Velodyne frame to the KITTI camera frame
+/// (x right, y down, z forward), written to `calib.txt` as `Tr`.
+const VELO_TO_CAM: Mat3 = Mat3::from_cols(Vec3::Z, Vec3::NEG_X, Vec3::NEG_Y);
+
+/// Indices of the valid (non-`NaN`) points of `result`.
+fn valid_points(result: &ScanResult) -> impl Iterator<Item = usize> + '_ {
+    result
+        .hits
+        .chunks_exact(3)
+        .enumerate()
+        .filter(|(_, p)| !p.iter().any(|v| v.is_nan()))
+        .map(|(i, _)| i)
+}
+
+/// Encode a scan as a KITTI Velodyne `.bin` file: little-endian `f32`
+/// `x, y, z, intensity` per point, in the sensor's own KITTI frame (x
+/// forward, y left, z up).
+///
+/// Misses (`NaN` points of organized scans) are skipped.  Intensity is `0`
+/// when the scan has none.
+pub fn kitti_velodyne_bin(result: &ScanResult) -> Vec<u8> {
+    let mut out = Vec::with_capacity(result.hit_count * 16);
+    for i in valid_points(result) {
+        let p = sensor_frame_point(result, i);
+        let intensity = result.intensities.get(i).copied().unwrap_or(0.0);
+        for v in [p.x, p.y, p.z, intensity] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Encode a scan as a SemanticKITTI `.label` file, parallel to
+/// [`kitti_velodyne_bin`]: one little-endian `u32` per point holding the
+/// semantic label in the lower 16 bits and the object id (instance) in the
+/// upper 16 bits.
+///
+/// Returns `None` if the scan carries no per-point labels.
+pub fn kitti_labels(result: &ScanResult) -> Option<Vec<u8>> {
+    if result.labels.len() != result.hit_count {
+        return None;
+    }
+    let mut out = Vec::with_capacity(result.hit_count * 4);
+    for i in valid_points(result) {
+        let instance = result.object_ids.get(i).copied().unwrap_or(0) & 0xffff;
+        let value = (instance << 16) | (result.labels[i] & 0xffff);
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    Some(out)
+}
+
+/// Append the top 3×4 block of `m` as twelve row-major numbers.
+fn push_matrix_row(text: &mut String, m: &Mat4) {
+    let values: Vec<String> = (0..3)
+        .flat_map(|row| (0..4).map(move |col| m.col(col)[row]))
+        .map(|v| format!("{v:.6e}"))
+        .collect();
+    text.push_str(&values.join(" "));
+}
+
+/// Poses and timestamps of a KITTI odometry sequence.
+///
+/// Follows the KITTI odometry convention: each entry of `poses.txt` maps
+/// camera-frame points of a frame into the camera frame of the first frame,
+/// with the camera rigidly attached to the LiDAR by the `Tr` transform of
+/// `calib.txt`.  Since the simulator has no cameras, `Tr` is a pure axis
+/// change and the projection matrices `P0`–`P3` are `[I | 0]`.
+#[derive(Clone, Debug, Default)]
+pub struct KittiSequence {
+    /// Velodyne-to-world transform of each frame.
+    velodyne_to_world: Vec<Mat4>,
+    /// Timestamp of each frame in seconds.
+    timestamps: Vec<f64>,
+}
+
+impl KittiSequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the pose of `result` as the next frame, taken at `timestamp`
+    /// seconds.  Returns the frame index.
+    pub fn add_scan(&mut self, result: &ScanResult, timestamp: f64) -> usize {
+        let sensor_to_world = Mat4::from_rotation_translation(result.sensor_rotation, result.sensor_position);
+        self.velodyne_to_world
+            .push(sensor_to_world * Mat4::from_mat3(SIM_TO_Z_UP.transpose()));
+        self.timestamps.push(timestamp);
+        self.velodyne_to_world.len() - 1
+    }
+
+    /// Number of frames recorded.
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// Whether no frame has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Contents of `poses.txt`.
+    pub fn poses_txt(&self) -> String {
+        let Some(first) = self.velodyne_to_world.first() else {
+            return String::new();
+        };
+        let world_to_first = first.inverse();
+        let cam = Mat4::from_mat3(VELO_TO_CAM);
+        let cam_inv = Mat4::from_mat3(VELO_TO_CAM.transpose());
+        let mut text = String::new();
+        for pose in &self.velodyne_to_world {
+            push_matrix_row(&mut text, &(cam * world_to_first * *pose * cam_inv));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Contents of `times.txt`: seconds since the first frame.
+    pub fn times_txt(&self) -> String {
+        let start = self.timestamps.first().copied().unwrap_or(0.0);
+        let mut text = String::new();
+        for t in &self.timestamps {
+            let _ = writeln!(text, "{:.6e}", t - start);
+        }
+        text
+    }
+
+    /// Contents of `calib.txt`.
+    pub fn calib_txt() -> String {
+        let mut text = String::new();
+        let projection = Mat4::IDENTITY;
+        for name in ["P0", "P1", "P2", "P3"] {
+            text.push_str(name);
+            text.push_str(": ");
+            push_matrix_row(&mut text, &projection);
+            text.push('\n');
+        }
+        text.push_str("Tr: ");
+        push_matrix_row(&mut text, &Mat4::from_mat3(VELO_TO_CAM));
+        text.push('\n');
+        text
+    }
+}
+
+/// Writes a sequence of scans to disk in the KITTI odometry layout:
+///
+/// ```text
+/// <dir>/velodyne/000000.bin …
+/// <dir>/labels/000000.label …   (SemanticKITTI, optional)
+/// <dir>/poses.txt
+/// <dir>/times.txt
+/// <dir>/calib.txt
+/// ```
+pub struct KittiWriter {
+    dir: PathBuf,
+    write_labels: bool,
+    sequence: KittiSequence,
+}
+
+impl KittiWriter {
+    /// Create the sequence directory (and its `velodyne`/`labels`
+    /// sub-directories).
+    ///
+    /// * `dir`          – Sequence directory, e.g. `dataset/sequences/00`.
+    /// * `write_labels` – Also write SemanticKITTI `.label` files for scans
+    ///   that carry semantic labels.
+    pub fn new(dir: impl AsRef<Path>, write_labels: bool) -> Result<Self, SimLidarError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join("velodyne"))?;
+        if write_labels {
+            fs::create_dir_all(dir.join("labels"))?;
+        }
+        Ok(Self {
+            dir,
+            write_labels,
+            sequence: KittiSequence::new(),
+        })
+    }
+
+    /// Write the next frame, taken at `timestamp` seconds.
+    pub fn write_scan(&mut self, result: &ScanResult, timestamp: f64) -> Result<(), SimLidarError> {
+        let frame = self.sequence.len();
+        fs::write(self.dir.join(format!("velodyne/{frame:06}.bin")), kitti_velodyne_bin(result))?;
+        if self.write_labels {
+            if let Some(labels) = kitti_labels(result) {
+                fs::write(self.dir.join(format!("labels/{frame:06}.label")), labels)?;
+            }
+        }
+        self.sequence.add_scan(result, timestamp);
+        Ok(())
+    }
+
+    /// Write `poses.txt`, `times.txt` and `calib.txt`.
+    pub fn finish(self) -> Result<(), SimLidarError> {
+        fs::write(self.dir.join("poses.txt"), self.sequence.poses_txt())?;
+        fs::write(self.dir.join("times.txt"), self.sequence.times_txt())?;
+        fs::write(self.dir.join("calib.txt"), KittiSequence::calib_txt())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    fn scan_at(position: Vec3, rotation: Quat, hits: &[[f32; 3]]) -> ScanResult {
+        let n = hits.len();
+        ScanResult {
+            hits: hits.concat(),
+            hit_count: n,
+            intensities: vec![0.5; n],
+            labels: vec![40; n],
+            object_ids: vec![3; n],
+            sensor_position: position,
+            sensor_rotation: rotation,
+            ..ScanResult::default()
+        }
+    }
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect()
+    }
+
+    fn matrix(line: &str) -> Vec<f32> {
+        line.split_whitespace()
+            .filter(|t| !t.ends_with(':'))
+            .map(|t| t.parse().unwrap())
+            .collect()
+    }
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-5, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn test_kitti_bin_uses_sensor_frame() {
+        // Forward (+x), right (+z) and straight down, seen from 5 m up.
+        let hits = [[3.0, 5.0, 0.0], [0.0, 5.0, 2.0], [0.0, 0.0, 0.0], [f32::NAN; 3]];
+        let scan = scan_at(Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY, &hits);
+        let points = floats(&kitti_velodyne_bin(&scan));
+        assert_close(&points, &[3.0, 0.0, 0.0, 0.5, 0.0, -2.0, 0.0, 0.5, 0.0, 0.0, -5.0, 0.5]);
+    }
+
+    #[test]
+    fn test_kitti_bin_undoes_sensor_rotation() {
+        // Sensor yawed so its forward axis points along world -z.
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        assert!((rotation * Vec3::X).abs_diff_eq(Vec3::NEG_Z, 1e-6));
+        let scan = scan_at(Vec3::ZERO, rotation, &[[0.0, 0.0, -4.0]]);
+        assert_close(&floats(&kitti_velodyne_bin(&scan))[..3], &[4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_kitti_labels_pack_instance_and_label() {
+        let scan = scan_at(Vec3::ZERO, Quat::IDENTITY, &[[1.0, 0.0, 0.0], [f32::NAN; 3]]);
+        let labels = kitti_labels(&scan).unwrap();
+        assert_eq!(labels, ((3u32 << 16) | 40).to_le_bytes());
+
+        let unlabeled = ScanResult {
+            labels: Vec::new(),
+            ..scan
+        };
+        assert!(kitti_labels(&unlabeled).is_none());
+    }
+
+    #[test]
+    fn test_kitti_poses_are_relative_camera_poses() {
+        let mut sequence = KittiSequence::new();
+        sequence.add_scan(&scan_at(Vec3::new(10.0, 1.0, 5.0), Quat::IDENTITY, &[]), 100.0);
+        // Drive 2 m forward along the sensor's x axis.
+        sequence.add_scan(&scan_at(Vec3::new(12.0, 1.0, 5.0), Quat::IDENTITY, &[]), 100.1);
+        let poses = sequence.poses_txt();
+        let lines: Vec<&str> = poses.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let identity = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        assert_close(&matrix(lines[0]), &identity);
+        // KITTI camera frame: forward motion is +z.
+        assert_close(&matrix(lines[1]), &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0]);
+
+        let times: Vec<f32> = sequence.times_txt().lines().map(|l| l.parse().unwrap()).collect();
+        assert_close(&times, &[0.0, 0.1]);
+    }
+
+    #[test]
+    fn test_kitti_calib_has_projection_and_tr() {
+        let calib = KittiSequence::calib_txt();
+        let lines: Vec<&str> = calib.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("P0: "));
+        assert!(lines[4].starts_with("Tr: "));
+        // Velodyne x (forward) becomes camera z, y (left) becomes -x, z (up) becomes -y.
+        assert_close(&matrix(lines[4]), &[0.0, -1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_kitti_writer_layout() {
+        let dir = std::env::temp_dir().join(format!("sim_lidar_kitti_{}", std::process::id()));
+        let mut writer = KittiWriter::new(&dir, true).unwrap();
+        let scan = scan_at(Vec3::ZERO, Quat::IDENTITY, &[[1.0, 0.0, 0.0]]);
+        writer.write_scan(&scan, 0.0).unwrap();
+        writer.write_scan(&scan, 0.1).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(fs::read(dir.join("velodyne/000001.bin")).unwrap().len(), 16);
+        assert_eq!(fs::read(dir.join("labels/000001.label")).unwrap().len(), 4);
+        assert_eq!(fs::read_to_string(dir.join("poses.txt")).unwrap().lines().count(), 2);
+        assert!(dir.join("calib.txt").exists() && dir.join("times.txt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,364 @@
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::error::SimLidarError;
+use crate::raycaster::ScanResult;
+
+/// Size of a LAS 1.4 public header block in bytes.
+const HEADER_SIZE: u16 = 375;
+/// Point data record format 6: XYZ, intensity, returns, classification and
+/// GPS time.
+const POINT_FORMAT: u8 = 6;
+/// Size of a format 6 point record in bytes.
+const POINT_RECORD_SIZE: u16 = 30;
+/// Global encoding: GPS time is adjusted standard GPS time (bit 0) and the
+/// CRS, if any, is WKT (bit 4, required for formats 6–10).
+const GLOBAL_ENCODING: u16 = 0b1_0001;
+/// ASPRS class "Unclassified", used for labels that do not fit in a byte.
+const CLASS_UNCLASSIFIED: u8 = 1;
+
+/// Quantization and classification settings of a [`LasWriter`].
+#[derive(Clone, Debug)]
+pub struct LasOptions {
+    /// Size of one coordinate step in metres for X, Y and Z.
+    pub scale: [f64; 3],
+    /// Value subtracted from X, Y and Z before quantizing.  Pick a point near
+    /// the surveyed area so large world coordinates keep full precision.
+    pub offset: [f64; 3],
+    /// ASPRS classification of each semantic label, indexed by label.
+    /// Labels without an entry are written as-is when they fit in a byte and
+    /// as "Unclassified" (1) otherwise.
+    pub classification: Vec<u8>,
+}
+
+impl Default for LasOptions {
+    /// Millimetre precision around the origin, labels written as-is.
+    fn default() -> Self {
+        Self {
+            scale: [0.001; 3],
+            offset: [0.0; 3],
+            classification: Vec::new(),
+        }
+    }
+}
+
+impl LasOptions {
+    fn classify(&self, label: u32) -> u8 {
+        match self.classification.get(label as usize) {
+            Some(&class) => class,
+            None => u8::try_from(label).unwrap_or(CLASS_UNCLASSIFIED),
+        }
+    }
+}
+
+/// Streams scans into a single LAS 1.4 file (point data record format 6).
+///
+/// Points are converted from the simulator's Y-up frame to the Z-up frame
+/// GIS tools expect: `(x, y, z)` is written as `(x, -z, y)`.  Misses (`NaN`
+/// points of organized scans) are skipped.  The header, including point
+/// counts and bounds, is written when the writer is [finished].
+///
+/// [finished]: LasWriter::finish
+pub struct LasWriter<W: Write + Seek> {
+    out: W,
+    options: LasOptions,
+    point_count: u64,
+    scan_count: u16,
+    min: [f64; 3],
+    max: [f64; 3],
+}
+
+impl<W: Write + Seek> LasWriter<W> {
+    /// Start a LAS file at the current position of `out`, reserving room for
+    /// the header.  Fails with [`SimLidarError::InvalidArgument`] unless every
+    /// scale is finite and positive and every offset is finite.
+    pub fn new(mut out: W, options: LasOptions) -> Result<Self, SimLidarError> {
+        if !options.scale.iter().all(|&s| s.is_finite() && s > 0.0) {
+            return Err(SimLidarError::InvalidArgument {
+                argument: "scale",
+                message: format!("must be finite and > 0, got {:?}", options.scale),
+            });
+        }
+        if !options.offset.iter().all(|o| o.is_finite()) {
+            return Err(SimLidarError::InvalidArgument {
+                argument: "offset",
+                message: format!("must be finite, got {:?}", options.offset),
+            });
+        }
+        out.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(Self {
+            out,
+            options,
+            point_count: 0,
+            scan_count: 0,
+            min: [f64::INFINITY; 3],
+            max: [f64::NEG_INFINITY; 3],
+        })
+    }
+
+    /// Append every valid point of `result`.
+    ///
+    /// * `gps_time` – Time of the start of the scan; each point is stamped
+    ///   with `gps_time + result.times[i]`.
+    ///
+    /// Every point gets return number 1 of 1, its classification from its
+    /// semantic label, its ring as user data and the index of the scan as
+    /// point source id.  Returns [`SimLidarError::Format`] if a point cannot
+    /// be represented with the configured scale and offset; the whole scan
+    /// is quantized before anything is written, so a rejected scan leaves
+    /// the file unchanged.
+    pub fn write_scan(&mut self, result: &ScanResult, gps_time: f64) -> Result<(), SimLidarError> {
+        let mut records = Vec::with_capacity(result.hits.len() / 3 * POINT_RECORD_SIZE as usize);
+        let (mut min, mut max) = (self.min, self.max);
+        let mut count = 0;
+        for (i, p) in result.hits.chunks_exact(3).enumerate() {
+            if p.iter().any(|v| v.is_nan()) {
+                continue;
+            }
+            let position = [p[0] as f64, -p[2] as f64, p[1] as f64];
+            let mut quantized = [0i32; 3];
+            for axis in 0..3 {
+                let steps = ((position[axis] - self.options.offset[axis]) / self.options.scale[axis]).round();
+                if !(i32::MIN as f64..=i32::MAX as f64).contains(&steps) {
+                    return Err(SimLidarError::Format(format!(
+                        "point {position:?} does not fit in LAS coordinates with scale {:?} and offset {:?}",
+                        self.options.scale, self.options.offset
+                    )));
+                }
+                quantized[axis] = steps as i32;
+            }
+            for (axis, &q) in quantized.iter().enumerate() {
+                let stored = q as f64 * self.options.scale[axis] + self.options.offset[axis];
+                min[axis] = min[axis].min(stored);
+                max[axis] = max[axis].max(stored);
+            }
+
+            let intensity = result.intensities.get(i).map_or(0, |&v| (v.clamp(0.0, 1.0) * 65535.0).round() as u16);
+            let label = result.labels.get(i).copied().unwrap_or(0);
+            let ring = result.rings.get(i).copied().unwrap_or(0);
+            let time = result.times.get(i).copied().unwrap_or(0.0);
+
+            for q in quantized {
+                records.extend_from_slice(&q.to_le_bytes());
+            }
+            records.extend_from_slice(&intensity.to_le_bytes());
+            records.push(0x11); // return 1 of 1
+            records.push(0); // classification flags, scanner channel, scan direction, edge
+            records.push(self.options.classify(label));
+            records.push(u8::try_from(ring).unwrap_or(u8::MAX)); // user data
+            records.extend_from_slice(&0i16.to_le_bytes()); // scan angle
+            records.extend_from_slice(&self.scan_count.to_le_bytes()); // point source id
+            records.extend_from_slice(&(gps_time + time as f64).to_le_bytes());
+            count += 1;
+        }
+        self.out.write_all(&records)?;
+        // Only scans that are written contribute to the counts and bounds.
+        self.point_count += count;
+        (self.min, self.max) = (min, max);
+        self.scan_count = self.scan_count.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Number of points written so far.
+    pub fn point_count(&self) -> u64 {
+        self.point_count
+    }
+
+    /// Write the header and return the underlying writer, positioned after
+    /// the last point.
+    pub fn finish(mut self) -> Result<W, SimLidarError> {
+        let end = self.out.stream_position()?;
+        let start = end - HEADER_SIZE as u64 - self.point_count * POINT_RECORD_SIZE as u64;
+        self.out.seek(SeekFrom::Start(start))?;
+        self.out.write_all(&self.header())?;
+        self.out.seek(SeekFrom::Start(end))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut h = Vec::with_capacity(HEADER_SIZE as usize);
+        h.extend_from_slice(b"LASF");
+        h.extend_from_slice(&0u16.to_le_bytes()); // file source id
+        h.extend_from_slice(&GLOBAL_ENCODING.to_le_bytes());
+        h.extend_from_slice(&[0; 16]); // project id
+        h.extend_from_slice(&[1, 4]); // version 1.4
+        h.extend_from_slice(&fixed_str::<32>("SIMULATION"));
+        h.extend_from_slice(&fixed_str::<32>(concat!("sim-lidar-rs ", env!("CARGO_PKG_VERSION"))));
+        h.extend_from_slice(&0u16.to_le_bytes()); // creation day of year (unknown)
+        h.extend_from_slice(&0u16.to_le_bytes()); // creation year (unknown)
+        h.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+        h.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes()); // offset to point data
+        h.extend_from_slice(&0u32.to_le_bytes()); // number of VLRs
+        h.push(POINT_FORMAT);
+        h.extend_from_slice(&POINT_RECORD_SIZE.to_le_bytes());
+        // Legacy point counts must be zero for point formats 6 and above.
+        h.extend_from_slice(&[0; 4 + 5 * 4]);
+        for v in self.options.scale.iter().chain(&self.options.offset) {
+            h.extend_from_slice(&v.to_le_bytes());
+        }
+        let (min, max) = if self.point_count == 0 {
+            ([0.0; 3], [0.0; 3])
+        } else {
+            (self.min, self.max)
+        };
+        for axis in 0..3 {
+            h.extend_from_slice(&max[axis].to_le_bytes());
+            h.extend_from_slice(&min[axis].to_le_bytes());
+        }
+        h.extend_from_slice(&0u64.to_le_bytes()); // start of waveform data
+        h.extend_from_slice(&0u64.to_le_bytes()); // start of first EVLR
+        h.extend_from_slice(&0u32.to_le_bytes()); // number of EVLRs
+        h.extend_from_slice(&self.point_count.to_le_bytes());
+        // Every point is a first (and only) return.
+        h.extend_from_slice(&self.point_count.to_le_bytes());
+        h.extend_from_slice(&[0; 14 * 8]);
+        debug_assert_eq!(h.len(), HEADER_SIZE as usize);
+        h
+    }
+}
+
+/// `s` as a NUL-padded fixed-size field.
+fn fixed_str<const N: usize>(s: &str) -> [u8; N] {
+    let mut field = [0; N];
+    let len = s.len().min(N);
+    field[..len].copy_from_slice(&s.as_bytes()[..len]);
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn scan_of(points: &[[f32; 3]], labels: &[u32]) -> ScanResult {
+        let n = points.len();
+        ScanResult {
+            hits: points.concat(),
+            hit_count: n,
+            rings: (0..n as u16).collect(),
+            intensities: vec![0.5; n],
+            times: (0..n).map(|i| i as f32 * 0.01).collect(),
+            labels: labels.to_vec(),
+            ..ScanResult::default()
+        }
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn u64_at(bytes: &[u8], at: usize) -> u64 {
+        u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    fn f64_at(bytes: &[u8], at: usize) -> f64 {
+        f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_las_header_and_point_record() {
+        let mut writer = LasWriter::new(Cursor::new(Vec::new()), LasOptions::default()).unwrap();
+        writer.write_scan(&scan_of(&[[1.0, 2.0, 3.0]], &[2]), 1000.0).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        assert_eq!(bytes.len(), 375 + 30);
+        assert_eq!(&bytes[0..4], b"LASF");
+        assert_eq!((bytes[24], bytes[25]), (1, 4));
+        assert_eq!(bytes[104], 6);
+        assert_eq!(u32_at(&bytes, 107), 0, "Legacy point count must be zero");
+        assert_eq!(f64_at(&bytes, 131), 0.001);
+        assert_eq!(u64_at(&bytes, 247), 1);
+        assert_eq!(u64_at(&bytes, 255), 1);
+
+        // Y-up (1, 2, 3) becomes Z-up (1, -3, 2).
+        let point = &bytes[375..];
+        let xyz: Vec<i32> = (0..3).map(|a| u32_at(point, a * 4) as i32).collect();
+        assert_eq!(xyz, vec![1000, -3000, 2000]);
+        assert_eq!(u16::from_le_bytes([point[12], point[13]]), 32768);
+        assert_eq!(point[14], 0x11);
+        assert_eq!(point[16], 2, "Classification comes from the label");
+        assert_eq!(f64_at(point, 22), 1000.0);
+    }
+
+    #[test]
+    fn test_las_appends_scans_and_tracks_bounds() {
+        let options = LasOptions {
+            offset: [100.0, 0.0, 0.0],
+            ..LasOptions::default()
+        };
+        let mut writer = LasWriter::new(Cursor::new(Vec::new()), options).unwrap();
+        writer
+            .write_scan(&scan_of(&[[100.0, 0.0, 0.0], [f32::NAN; 3], [102.0, 5.0, -1.0]], &[0, 0, 0]), 10.0)
+            .unwrap();
+        writer.write_scan(&scan_of(&[[99.0, -2.0, 4.0]], &[0]), 20.0).unwrap();
+        assert_eq!(writer.point_count(), 3, "NaN misses are skipped");
+        let bytes = writer.finish().unwrap().into_inner();
+
+        assert_eq!(bytes.len(), 375 + 3 * 30);
+        assert_eq!(u64_at(&bytes, 247), 3);
+        // max/min pairs for X, Y (= -z) and Z (= y)
+        let bounds: Vec<f64> = (0..6).map(|k| f64_at(&bytes, 179 + k * 8)).collect();
+        assert_eq!(bounds, vec![102.0, 99.0, 1.0, -4.0, 5.0, -2.0]);
+        // Second point of the first scan: time offset 0.02 s (index 2), scan 0.
+        let second = &bytes[375 + 30..];
+        assert!((f64_at(second, 22) - 10.02).abs() < 1e-6);
+        let third = &bytes[375 + 60..];
+        assert_eq!(u16::from_le_bytes([third[20], third[21]]), 1, "Point source id is the scan index");
+        assert_eq!(f64_at(third, 22), 20.0);
+    }
+
+    #[test]
+    fn test_las_classification_mapping() {
+        let options = LasOptions {
+            classification: vec![0, 2, 6],
+            ..LasOptions::default()
+        };
+        let mut writer = LasWriter::new(Cursor::new(Vec::new()), options).unwrap();
+        let scan = scan_of(&[[0.0; 3], [0.0; 3], [0.0; 3]], &[2, 17, 300]);
+        writer.write_scan(&scan, 0.0).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        let classes: Vec<u8> = (0..3).map(|i| bytes[375 + i * 30 + 16]).collect();
+        assert_eq!(classes, vec![6, 17, 1]);
+    }
+
+    #[test]
+    fn test_las_rejects_unrepresentable_coordinates() {
+        let options = LasOptions {
+            scale: [1e-6; 3],
+            ..LasOptions::default()
+        };
+        let mut writer = LasWriter::new(Cursor::new(Vec::new()), options).unwrap();
+        let result = writer.write_scan(&scan_of(&[[5000.0, 0.0, 0.0]], &[0]), 0.0);
+        assert!(matches!(result, Err(SimLidarError::Format(_))));
+        writer.write_scan(&scan_of(&[[1.0, 2.0, 3.0]], &[0]), 0.0).unwrap();
+        // The first point fits but the second does not: the whole scan is
+        // rejected, writing nothing and leaving the bounds alone.
+        let result = writer.write_scan(&scan_of(&[[-2.0, 0.0, -3.0], [-2000.0, 0.0, 5000.0]], &[0, 0]), 0.0);
+        assert!(matches!(result, Err(SimLidarError::Format(_))));
+        assert_eq!(writer.point_count(), 1);
+        let bytes = writer.finish().unwrap().into_inner();
+        assert_eq!(bytes.len(), 375 + 30);
+        let bounds: Vec<f64> = (0..6).map(|k| f64_at(&bytes, 179 + k * 8)).collect();
+        assert_eq!(bounds, vec![1.0, 1.0, -3.0, -3.0, 2.0, 2.0]);
+        // Rejected scans do not use up a point source id.
+        assert_eq!(u16::from_le_bytes([bytes[375 + 20], bytes[375 + 21]]), 0);
+    }
+
+    #[test]
+    fn test_las_rejects_bad_scale_and_offset() {
+        for scale in [0.0, -0.001, f64::NAN, f64::INFINITY] {
+            let options = LasOptions {
+                scale: [0.001, scale, 0.001],
+                ..LasOptions::default()
+            };
+            let result = LasWriter::new(Cursor::new(Vec::new()), options);
+            assert!(matches!(result, Err(SimLidarError::InvalidArgument { argument: "scale", .. })));
+        }
+        let options = LasOptions {
+            offset: [0.0, 0.0, f64::NAN],
+            ..LasOptions::default()
+        };
+        let result = LasWriter::new(Cursor::new(Vec::new()), options);
+        assert!(matches!(result, Err(SimLidarError::InvalidArgument { argument: "offset", .. })));
+    }
+}
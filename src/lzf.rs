@@ -0,0 +1,157 @@
+// Minimal LZF codec, byte-compatible with liblzf as used by PCL's
+// `binary_compressed` PCD encoding.
+
+use crate::error::SimLidarError;
+
+const HASH_BITS: u32 = 14;
+const MAX_LITERAL: usize = 32;
+const MAX_OFFSET: usize = 1 << 13;
+const MAX_MATCH: usize = 264;
+
+fn hash(input: &[u8], i: usize) -> usize {
+    let v = (u32::from(input[i]) << 16) | (u32::from(input[i + 1]) << 8) | u32::from(input[i + 2]);
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Append a literal run of at most [`MAX_LITERAL`] bytes.
+fn flush_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(MAX_LITERAL) {
+        out.push((chunk.len() - 1) as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+/// Compress `input` into the LZF format.
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() + input.len() / 16 + 1);
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i + 2 < input.len() {
+        let h = hash(input, i);
+        let candidate = table[h];
+        table[h] = i;
+
+        if candidate != usize::MAX && i - candidate <= MAX_OFFSET && input[candidate..candidate + 3] == input[i..i + 3] {
+            let max_len = (input.len() - i).min(MAX_MATCH);
+            let mut len = 3;
+            while len < max_len && input[candidate + len] == input[i + len] {
+                len += 1;
+            }
+            flush_literals(&mut out, &input[literal_start..i]);
+
+            let offset = i - candidate - 1;
+            let encoded_len = len - 2;
+            if encoded_len < 7 {
+                out.push(((encoded_len << 5) | (offset >> 8)) as u8);
+            } else {
+                out.push(((7 << 5) | (offset >> 8)) as u8);
+                out.push((encoded_len - 7) as u8);
+            }
+            out.push((offset & 0xff) as u8);
+
+            i += len;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    flush_literals(&mut out, &input[literal_start..]);
+    out
+}
+
+/// Decompress LZF `input` that is expected to expand to `expected_len` bytes.
+///
+/// `expected_len` usually comes from an untrusted file header, so the output
+/// is only pre-allocated up to what `input` can actually expand to: each
+/// 2–3 byte back-reference yields at most [`MAX_MATCH`] bytes.
+pub fn decompress(input: &[u8], expected_len: usize) -> Result<Vec<u8>, SimLidarError> {
+    let corrupt = || SimLidarError::Format("corrupt LZF data".into());
+    let mut out = Vec::with_capacity(expected_len.min(input.len().saturating_mul(MAX_MATCH / 2)));
+    let mut i = 0;
+
+    while i < input.len() {
+        let ctrl = input[i] as usize;
+        i += 1;
+        if ctrl < MAX_LITERAL {
+            let len = ctrl + 1;
+            let literal = input.get(i..i + len).ok_or_else(corrupt)?;
+            out.extend_from_slice(literal);
+            i += len;
+        } else {
+            let mut len = ctrl >> 5;
+            if len == 7 {
+                len += *input.get(i).ok_or_else(corrupt)? as usize;
+                i += 1;
+            }
+            len += 2;
+            let low = *input.get(i).ok_or_else(corrupt)? as usize;
+            i += 1;
+            let back = ((ctrl & 0x1f) << 8) + low + 1;
+            if back > out.len() {
+                return Err(corrupt());
+            }
+            let start = out.len() - back;
+            // Copy byte by byte: the source may overlap the bytes being written.
+            for k in 0..len {
+                let b = out[start + k];
+                out.push(b);
+            }
+        }
+        if out.len() > expected_len {
+            return Err(corrupt());
+        }
+    }
+    if out.len() != expected_len {
+        return Err(corrupt());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lzf_round_trip_repetitive() {
+        let input: Vec<u8> = (0..10_000u32).flat_map(|i| ((i % 97) as f32).to_le_bytes()).collect();
+        let compressed = compress(&input);
+        assert!(compressed.len() < input.len() / 2, "repetitive data must compress");
+        assert_eq!(decompress(&compressed, input.len()).unwrap(), input);
+    }
+
+    #[test]
+    fn test_lzf_round_trip_incompressible_and_tiny() {
+        let mut state = 0x1234_5678u32;
+        let noise: Vec<u8> = (0..1000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        for input in [&noise[..], b"ab", b""] {
+            assert_eq!(decompress(&compress(input), input.len()).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_lzf_decompress_known_stream() {
+        // liblzf output for "aaaaaaaaaa": literal "a", then a 9-byte back-reference at distance 1.
+        let stream = [0x00, b'a', 0xe0, 0x00, 0x00];
+        assert_eq!(decompress(&stream, 10).unwrap(), b"aaaaaaaaaa");
+    }
+
+    #[test]
+    fn test_lzf_decompress_rejects_bad_reference() {
+        assert!(decompress(&[0x20, 0x05], 3).is_err());
+    }
+
+    #[test]
+    fn test_lzf_decompress_does_not_trust_expected_len() {
+        // A 2-byte literal claiming to expand to 4 GiB.
+        assert!(decompress(&[0x01, b'a', b'b'], u32::MAX as usize).is_err());
+    }
+}
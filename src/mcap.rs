@@ -0,0 +1,659 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use glam::{Quat, Vec3};
+
+use crate::base64;
+use crate::error::SimLidarError;
+use crate::point_fields::z_up_sensor_pose;
+use crate::raycaster::ScanResult;
+use crate::ros::{self, PointCloud2Layout};
+use crate::sensor::SensorConfig;
+
+const MAGIC: &[u8; 8] = b"\x89MCAP0\r\n";
+
+/// Record opcodes.
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_STATISTICS: u8 = 0x0b;
+const OP_METADATA: u8 = 0x0c;
+const OP_METADATA_INDEX: u8 = 0x0d;
+const OP_SUMMARY_OFFSET: u8 = 0x0e;
+const OP_DATA_END: u8 = 0x0f;
+
+const CLOUD_CHANNEL: u16 = 1;
+const TF_CHANNEL: u16 = 2;
+
+/// Separator between the dependent definitions of a `ros2msg` schema.
+const MSG_SEPARATOR: &str = "================================================================================";
+
+const HEADER_MSGDEF: &str = "builtin_interfaces/Time stamp\nstring frame_id\n";
+const TIME_MSGDEF: &str = "int32 sec\nuint32 nanosec\n";
+
+const POINT_CLOUD2_MSGDEF: &str = "std_msgs/Header header\nuint32 height\nuint32 width\nPointField[] fields\n\
+bool is_bigendian\nuint32 point_step\nuint32 row_step\nuint8[] data\nbool is_dense\n";
+const POINT_FIELD_MSGDEF: &str = "uint8 INT8 = 1\nuint8 UINT8 = 2\nuint8 INT16 = 3\nuint8 UINT16 = 4\n\
+uint8 INT32 = 5\nuint8 UINT32 = 6\nuint8 FLOAT32 = 7\nuint8 FLOAT64 = 8\n\
+string name\nuint32 offset\nuint8 datatype\nuint32 count\n";
+
+const TF_MESSAGE_MSGDEF: &str = "geometry_msgs/TransformStamped[] transforms\n";
+const TRANSFORM_STAMPED_MSGDEF: &str = "std_msgs/Header header\nstring child_frame_id\nTransform transform\n";
+const TRANSFORM_MSGDEF: &str = "Vector3 translation\nQuaternion rotation\n";
+const VECTOR3_MSGDEF: &str = "float64 x\nfloat64 y\nfloat64 z\n";
+const QUATERNION_MSGDEF: &str = "float64 x 0\nfloat64 y 0\nfloat64 z 0\nfloat64 w 1\n";
+
+const FOXGLOVE_POINT_CLOUD_SCHEMA: &str = r#"{"title":"foxglove.PointCloud","type":"object","properties":{"timestamp":{"type":"object","properties":{"sec":{"type":"integer"},"nsec":{"type":"integer"}}},"frame_id":{"type":"string"},"pose":{"type":"object","properties":{"position":{"type":"object","properties":{"x":{"type":"number"},"y":{"type":"number"},"z":{"type":"number"}}},"orientation":{"type":"object","properties":{"x":{"type":"number"},"y":{"type":"number"},"z":{"type":"number"},"w":{"type":"number"}}}}},"point_stride":{"type":"integer"},"fields":{"type":"array","items":{"type":"object","properties":{"name":{"type":"string"},"offset":{"type":"integer"},"type":{"type":"integer"}}}},"data":{"type":"string","contentEncoding":"base64"}}}"#;
+const FOXGLOVE_FRAME_TRANSFORM_SCHEMA: &str = r#"{"title":"foxglove.FrameTransform","type":"object","properties":{"timestamp":{"type":"object","properties":{"sec":{"type":"integer"},"nsec":{"type":"integer"}}},"parent_frame_id":{"type":"string"},"child_frame_id":{"type":"string"},"translation":{"type":"object","properties":{"x":{"type":"number"},"y":{"type":"number"},"z":{"type":"number"}}},"rotation":{"type":"object","properties":{"x":{"type":"number"},"y":{"type":"number"},"z":{"type":"number"},"w":{"type":"number"}}}}}"#;
+
+/// Message type used for point clouds (and, with it, for poses).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum McapCloudFormat {
+    /// CDR `sensor_msgs/msg/PointCloud2` and `tf2_msgs/msg/TFMessage`
+    /// (`ros2` profile), as recorded by `ros2 bag`.
+    PointCloud2,
+    /// JSON `foxglove.PointCloud` and `foxglove.FrameTransform`.
+    FoxglovePointCloud,
+}
+
+impl FromStr for McapCloudFormat {
+    type Err = SimLidarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pointcloud2" | "ros2" => Ok(McapCloudFormat::PointCloud2),
+            "foxglove" | "foxglove.pointcloud" => Ok(McapCloudFormat::FoxglovePointCloud),
+            _ => Err(SimLidarError::Format(format!("unknown MCAP point cloud format {s:?}"))),
+        }
+    }
+}
+
+/// Topics, frames and point layout of an MCAP recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct McapOptions {
+    pub format: McapCloudFormat,
+    /// Point record layout, shared by both formats.
+    pub layout: PointCloud2Layout,
+    /// Topic of the point cloud messages.
+    pub cloud_topic: String,
+    /// Topic of the sensor pose transforms.
+    pub tf_topic: String,
+    /// Frame of the sensor; points are expressed in it.
+    pub frame_id: String,
+    /// Fixed frame the sensor poses are relative to.
+    pub world_frame_id: String,
+}
+
+impl Default for McapOptions {
+    /// `PointCloud2` messages with the Velodyne layout on `/points`, and
+    /// `map` → `lidar` transforms on `/tf`.
+    fn default() -> Self {
+        Self {
+            format: McapCloudFormat::PointCloud2,
+            layout: PointCloud2Layout::velodyne(),
+            cloud_topic: "/points".to_string(),
+            tf_topic: "/tf".to_string(),
+            frame_id: "lidar".to_string(),
+            world_frame_id: "map".to_string(),
+        }
+    }
+}
+
+/// Records a sequence of scans into an MCAP file: one point cloud message and
+/// one sensor pose transform per scan, plus the sensor configuration as
+/// metadata.
+///
+/// Points are expressed in the Z-up sensor frame and poses in a Z-up world
+/// frame, following ROS conventions, so Foxglove shows the scans in place.
+/// Messages are written unchunked and uncompressed, with a summary section
+/// (schemas, channels, statistics and metadata index); CRCs are left as 0,
+/// meaning "not computed".
+pub struct McapWriter<W: Write> {
+    out: W,
+    options: McapOptions,
+    /// Bytes written so far, for summary offsets.
+    position: u64,
+    /// Schema and channel records, repeated in the summary section.
+    definitions: Vec<(u8, Vec<u8>)>,
+    /// `(offset, length, name)` of every metadata record.
+    metadata_index: Vec<(u64, u64, String)>,
+    sequence: u32,
+    message_counts: [u64; 2],
+    time_range: Option<(u64, u64)>,
+}
+
+impl<W: Write> McapWriter<W> {
+    /// Start a recording by writing the MCAP header, schemas and channels.
+    /// Fails with [`SimLidarError::Format`] if `options.layout` is invalid.
+    pub fn new(out: W, options: McapOptions) -> Result<Self, SimLidarError> {
+        options.layout.validate()?;
+        let mut writer = Self {
+            out,
+            options,
+            position: 0,
+            definitions: Vec::new(),
+            metadata_index: Vec::new(),
+            sequence: 0,
+            message_counts: [0; 2],
+            time_range: None,
+        };
+        writer.write_raw(MAGIC)?;
+
+        let (profile, cloud, tf, schema_encoding, message_encoding) = match writer.options.format {
+            McapCloudFormat::PointCloud2 => (
+                "ros2",
+                ("sensor_msgs/msg/PointCloud2", point_cloud2_msgdef()),
+                ("tf2_msgs/msg/TFMessage", tf_message_msgdef()),
+                "ros2msg",
+                "cdr",
+            ),
+            McapCloudFormat::FoxglovePointCloud => (
+                "",
+                ("foxglove.PointCloud", FOXGLOVE_POINT_CLOUD_SCHEMA.to_string()),
+                ("foxglove.FrameTransform", FOXGLOVE_FRAME_TRANSFORM_SCHEMA.to_string()),
+                "jsonschema",
+                "json",
+            ),
+        };
+        let mut header = Vec::new();
+        put_str(&mut header, profile);
+        put_str(&mut header, concat!("sim-lidar-rs ", env!("CARGO_PKG_VERSION")));
+        writer.write_record(OP_HEADER, &header)?;
+
+        let cloud_topic = writer.options.cloud_topic.clone();
+        let tf_topic = writer.options.tf_topic.clone();
+        for (id, (name, definition), topic) in [(CLOUD_CHANNEL, cloud, cloud_topic), (TF_CHANNEL, tf, tf_topic)] {
+            let mut schema = Vec::new();
+            schema.extend_from_slice(&id.to_le_bytes());
+            put_str(&mut schema, name);
+            put_str(&mut schema, schema_encoding);
+            put_bytes(&mut schema, definition.as_bytes());
+            writer.write_record(OP_SCHEMA, &schema)?;
+
+            let mut channel = Vec::new();
+            channel.extend_from_slice(&id.to_le_bytes()); // channel id
+            channel.extend_from_slice(&id.to_le_bytes()); // schema id
+            put_str(&mut channel, &topic);
+            put_str(&mut channel, message_encoding);
+            put_map(&mut channel, &[]);
+            writer.write_record(OP_CHANNEL, &channel)?;
+
+            writer.definitions.push((OP_SCHEMA, schema));
+            writer.definitions.push((OP_CHANNEL, channel));
+        }
+        Ok(writer)
+    }
+
+    /// Store `config` as a `sensor_config` metadata record, one entry per
+    /// field.
+    pub fn write_config(&mut self, config: &SensorConfig) -> Result<(), SimLidarError> {
+        let entries = [
+            ("horizontal_resolution", config.horizontal_resolution.to_string()),
+            ("vertical_channels", config.vertical_channels.to_string()),
+            ("vertical_fov_upper", config.vertical_fov_upper.to_string()),
+            ("vertical_fov_lower", config.vertical_fov_lower.to_string()),
+            ("min_range", config.min_range.to_string()),
+            ("max_range", config.max_range.to_string()),
+            ("noise_stddev", config.noise_stddev.to_string()),
+            ("doppler", config.doppler.to_string()),
+            ("velocity_noise_stddev", config.velocity_noise_stddev.to_string()),
+            ("organized", config.organized.to_string()),
+            ("scan_frequency_hz", config.scan_frequency_hz.to_string()),
+        ];
+        let entries: Vec<(&str, &str)> = entries.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        self.write_metadata("sensor_config", &entries)
+    }
+
+    /// Store a named set of key/value pairs as a metadata record.
+    pub fn write_metadata(&mut self, name: &str, entries: &[(&str, &str)]) -> Result<(), SimLidarError> {
+        let mut content = Vec::new();
+        put_str(&mut content, name);
+        put_map(&mut content, entries);
+        let offset = self.position;
+        self.write_record(OP_METADATA, &content)?;
+        self.metadata_index.push((offset, self.position - offset, name.to_string()));
+        Ok(())
+    }
+
+    /// Record a scan taken at `stamp` seconds (Unix time): its point cloud and
+    /// the sensor pose at that time.  In the Foxglove format, fails with
+    /// [`SimLidarError::Format`] if the sensor pose is not finite.
+    pub fn write_scan(&mut self, result: &ScanResult, stamp: f64) -> Result<(), SimLidarError> {
+        let (translation, rotation) = z_up_sensor_pose(result);
+        let options = &self.options;
+        let (cloud, tf) = match options.format {
+            McapCloudFormat::PointCloud2 => (
+                ros::encode_point_cloud2(result, &options.layout, &options.frame_id, stamp)?,
+                ros::encode_tf_message(&options.world_frame_id, &options.frame_id, stamp, translation, rotation),
+            ),
+            McapCloudFormat::FoxglovePointCloud => (
+                foxglove_point_cloud_json(result, options, stamp)?.into_bytes(),
+                foxglove_frame_transform_json(options, stamp, translation, rotation)?.into_bytes(),
+            ),
+        };
+        let log_time = (stamp.max(0.0) * 1e9).round() as u64;
+        self.write_message(TF_CHANNEL, log_time, &tf)?;
+        self.write_message(CLOUD_CHANNEL, log_time, &cloud)
+    }
+
+    /// Number of scans recorded so far.
+    pub fn scan_count(&self) -> u64 {
+        self.message_counts[0]
+    }
+
+    /// Write the summary section and footer, and return the underlying writer.
+    pub fn finish(mut self) -> Result<W, SimLidarError> {
+        self.write_record(OP_DATA_END, &0u32.to_le_bytes())?;
+
+        let summary_start = self.position;
+        let mut groups: Vec<(u8, u64, u64)> = Vec::new();
+        let definitions = std::mem::take(&mut self.definitions);
+        for op in [OP_SCHEMA, OP_CHANNEL] {
+            let start = self.position;
+            for (_, content) in definitions.iter().filter(|(record_op, _)| *record_op == op) {
+                self.write_record(op, content)?;
+            }
+            groups.push((op, start, self.position - start));
+        }
+
+        let (start_time, end_time) = self.time_range.unwrap_or((0, 0));
+        let mut statistics = Vec::new();
+        statistics.extend_from_slice(&self.message_counts.iter().sum::<u64>().to_le_bytes());
+        statistics.extend_from_slice(&2u16.to_le_bytes()); // schemas
+        statistics.extend_from_slice(&2u32.to_le_bytes()); // channels
+        statistics.extend_from_slice(&0u32.to_le_bytes()); // attachments
+        statistics.extend_from_slice(&(self.metadata_index.len() as u32).to_le_bytes());
+        statistics.extend_from_slice(&0u32.to_le_bytes()); // chunks
+        statistics.extend_from_slice(&start_time.to_le_bytes());
+        statistics.extend_from_slice(&end_time.to_le_bytes());
+        statistics.extend_from_slice(&20u32.to_le_bytes()); // two (u16, u64) entries
+        for (id, count) in [(CLOUD_CHANNEL, self.message_counts[0]), (TF_CHANNEL, self.message_counts[1])] {
+            statistics.extend_from_slice(&id.to_le_bytes());
+            statistics.extend_from_slice(&count.to_le_bytes());
+        }
+        let start = self.position;
+        self.write_record(OP_STATISTICS, &statistics)?;
+        groups.push((OP_STATISTICS, start, self.position - start));
+
+        if !self.metadata_index.is_empty() {
+            let start = self.position;
+            for (offset, length, name) in std::mem::take(&mut self.metadata_index) {
+                let mut content = Vec::new();
+                content.extend_from_slice(&offset.to_le_bytes());
+                content.extend_from_slice(&length.to_le_bytes());
+                put_str(&mut content, &name);
+                self.write_record(OP_METADATA_INDEX, &content)?;
+            }
+            groups.push((OP_METADATA_INDEX, start, self.position - start));
+        }
+
+        let summary_offset_start = self.position;
+        for (op, start, length) in groups {
+            let mut content = vec![op];
+            content.extend_from_slice(&start.to_le_bytes());
+            content.extend_from_slice(&length.to_le_bytes());
+            self.write_record(OP_SUMMARY_OFFSET, &content)?;
+        }
+
+        let mut footer = Vec::new();
+        footer.extend_from_slice(&summary_start.to_le_bytes());
+        footer.extend_from_slice(&summary_offset_start.to_le_bytes());
+        footer.extend_from_slice(&0u32.to_le_bytes()); // summary CRC
+        self.write_record(OP_FOOTER, &footer)?;
+        self.write_raw(MAGIC)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn write_message(&mut self, channel: u16, log_time: u64, data: &[u8]) -> Result<(), SimLidarError> {
+        let mut content = Vec::with_capacity(22 + data.len());
+        content.extend_from_slice(&channel.to_le_bytes());
+        content.extend_from_slice(&self.sequence.to_le_bytes());
+        content.extend_from_slice(&log_time.to_le_bytes());
+        content.extend_from_slice(&log_time.to_le_bytes()); // publish time
+        content.extend_from_slice(data);
+        self.write_record(OP_MESSAGE, &content)?;
+        self.sequence = self.sequence.wrapping_add(1);
+        self.message_counts[(channel - 1) as usize] += 1;
+        self.time_range = Some(match self.time_range {
+            Some((start, end)) => (start.min(log_time), end.max(log_time)),
+            None => (log_time, log_time),
+        });
+        Ok(())
+    }
+
+    fn write_record(&mut self, op: u8, content: &[u8]) -> Result<(), SimLidarError> {
+        self.write_raw(&[op])?;
+        self.write_raw(&(content.len() as u64).to_le_bytes())?;
+        self.write_raw(content)
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> Result<(), SimLidarError> {
+        self.out.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_bytes(out, s.as_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// `Map<string, string>`: byte length, then the key/value pairs.
+fn put_map(out: &mut Vec<u8>, entries: &[(&str, &str)]) {
+    let mut map = Vec::new();
+    for (key, value) in entries {
+        put_str(&mut map, key);
+        put_str(&mut map, value);
+    }
+    put_bytes(out, &map);
+}
+
+/// Join a message definition with its dependencies, as `ros2 bag` does.
+fn ros2msg(main: &str, dependencies: &[(&str, &str)]) -> String {
+    let mut text = main.to_string();
+    for (name, definition) in dependencies {
+        text.push_str(&format!("{MSG_SEPARATOR}\nMSG: {name}\n{definition}"));
+    }
+    text
+}
+
+fn point_cloud2_msgdef() -> String {
+    ros2msg(
+        POINT_CLOUD2_MSGDEF,
+        &[
+            ("std_msgs/Header", HEADER_MSGDEF),
+            ("builtin_interfaces/Time", TIME_MSGDEF),
+            ("sensor_msgs/PointField", POINT_FIELD_MSGDEF),
+        ],
+    )
+}
+
+fn tf_message_msgdef() -> String {
+    ros2msg(
+        TF_MESSAGE_MSGDEF,
+        &[
+            ("geometry_msgs/TransformStamped", TRANSFORM_STAMPED_MSGDEF),
+            ("std_msgs/Header", HEADER_MSGDEF),
+            ("builtin_interfaces/Time", TIME_MSGDEF),
+            ("geometry_msgs/Transform", TRANSFORM_MSGDEF),
+            ("geometry_msgs/Vector3", VECTOR3_MSGDEF),
+            ("geometry_msgs/Quaternion", QUATERNION_MSGDEF),
+        ],
+    )
+}
+
+fn json_timestamp(stamp: f64) -> String {
+    let (sec, nsec) = ros::split_stamp(stamp);
+    format!(r#"{{"sec":{sec},"nsec":{nsec}}}"#)
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Fails unless the pose can be written as JSON, which has no `NaN` or
+/// infinities.
+fn check_finite_pose(translation: Vec3, rotation: Quat) -> Result<(), SimLidarError> {
+    if translation.is_finite() && rotation.is_finite() {
+        Ok(())
+    } else {
+        Err(SimLidarError::Format(format!(
+            "sensor pose ({translation}, {rotation}) is not finite and cannot be written as JSON"
+        )))
+    }
+}
+
+fn json_vec3(v: Vec3) -> String {
+    format!(r#"{{"x":{},"y":{},"z":{}}}"#, v.x, v.y, v.z)
+}
+
+fn json_quat(q: Quat) -> String {
+    format!(r#"{{"x":{},"y":{},"z":{},"w":{}}}"#, q.x, q.y, q.z, q.w)
+}
+
+/// `foxglove.PointCloud` message with an identity pose, as JSON.
+fn foxglove_point_cloud_json(result: &ScanResult, options: &McapOptions, stamp: f64) -> Result<String, SimLidarError> {
+    let (data, ..) = ros::pack_points(result, &options.layout)?;
+    let fields: Vec<String> = options
+        .layout
+        .fields
+        .iter()
+        .map(|field| {
+            // `foxglove.NumericType`: UINT16 = 3, UINT32 = 5, FLOAT32 = 7.
+            let numeric_type = match field.channel.datatype() {
+                4 => 3,
+                6 => 5,
+                _ => 7,
+            };
+            format!(r#"{{"name":{},"offset":{},"type":{numeric_type}}}"#, json_string(&field.name), field.offset)
+        })
+        .collect();
+    Ok(format!(
+        r#"{{"timestamp":{},"frame_id":{},"pose":{{"position":{},"orientation":{}}},"point_stride":{},"fields":[{}],"data":"{}"}}"#,
+        json_timestamp(stamp),
+        json_string(&options.frame_id),
+        json_vec3(Vec3::ZERO),
+        json_quat(Quat::IDENTITY),
+        options.layout.point_step,
+        fields.join(","),
+        base64::encode(&data)
+    ))
+}
+
+/// `foxglove.FrameTransform` message, as JSON.
+fn foxglove_frame_transform_json(
+    options: &McapOptions,
+    stamp: f64,
+    translation: Vec3,
+    rotation: Quat,
+) -> Result<String, SimLidarError> {
+    check_finite_pose(translation, rotation)?;
+    Ok(format!(
+        r#"{{"timestamp":{},"parent_frame_id":{},"child_frame_id":{},"translation":{},"rotation":{}}}"#,
+        json_timestamp(stamp),
+        json_string(&options.world_frame_id),
+        json_string(&options.frame_id),
+        json_vec3(translation),
+        json_quat(rotation)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records parsed back from an MCAP file: `(offset, opcode, content)`.
+    fn records(bytes: &[u8]) -> Vec<(usize, u8, &[u8])> {
+        assert_eq!(&bytes[..8], MAGIC);
+        assert_eq!(&bytes[bytes.len() - 8..], MAGIC);
+        let mut records = Vec::new();
+        let mut pos = 8;
+        while pos < bytes.len() - 8 {
+            let op = bytes[pos];
+            let len = u64::from_le_bytes(bytes[pos + 1..pos + 9].try_into().unwrap()) as usize;
+            records.push((pos, op, &bytes[pos + 9..pos + 9 + len]));
+            pos += 9 + len;
+        }
+        assert_eq!(pos, bytes.len() - 8);
+        records
+    }
+
+    fn string_at(content: &[u8], at: usize) -> (&str, usize) {
+        let len = u32::from_le_bytes(content[at..at + 4].try_into().unwrap()) as usize;
+        (std::str::from_utf8(&content[at + 4..at + 4 + len]).unwrap(), at + 4 + len)
+    }
+
+    fn sample_scan(x: f32) -> ScanResult {
+        ScanResult {
+            hits: vec![x + 2.0, 1.0, 0.0],
+            hit_count: 1,
+            rings: vec![3],
+            intensities: vec![0.5],
+            times: vec![0.0],
+            sensor_position: Vec3::new(x, 1.0, 0.0),
+            sensor_rotation: Quat::IDENTITY,
+            ..ScanResult::default()
+        }
+    }
+
+    fn record_scans(options: McapOptions) -> Vec<u8> {
+        let mut writer = McapWriter::new(Vec::new(), options).unwrap();
+        writer.write_config(&SensorConfig::vlp16()).unwrap();
+        writer.write_scan(&sample_scan(0.0), 100.0).unwrap();
+        writer.write_scan(&sample_scan(1.0), 100.1).unwrap();
+        assert_eq!(writer.scan_count(), 2);
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_mcap_ros2_structure() {
+        let bytes = record_scans(McapOptions::default());
+        let records = records(&bytes);
+        let ops: Vec<u8> = records.iter().map(|r| r.1).collect();
+        assert_eq!(
+            ops,
+            [
+                OP_HEADER, OP_SCHEMA, OP_CHANNEL, OP_SCHEMA, OP_CHANNEL, OP_METADATA, OP_MESSAGE, OP_MESSAGE,
+                OP_MESSAGE, OP_MESSAGE, OP_DATA_END, OP_SCHEMA, OP_SCHEMA, OP_CHANNEL, OP_CHANNEL, OP_STATISTICS,
+                OP_METADATA_INDEX, OP_SUMMARY_OFFSET, OP_SUMMARY_OFFSET, OP_SUMMARY_OFFSET, OP_SUMMARY_OFFSET,
+                OP_FOOTER
+            ]
+        );
+        assert_eq!(string_at(records[0].2, 0).0, "ros2");
+        let schema = records[1].2;
+        let (name, next) = string_at(schema, 2);
+        assert_eq!(name, "sensor_msgs/msg/PointCloud2");
+        assert_eq!(string_at(schema, next).0, "ros2msg");
+        let channel = records[2].2;
+        assert_eq!(string_at(channel, 4).0, "/points");
+
+        // Metadata holds the configuration.
+        let metadata = records[5].2;
+        assert_eq!(string_at(metadata, 0).0, "sensor_config");
+        let (key, next) = string_at(metadata, 4 + 13 + 4);
+        assert_eq!(key, "horizontal_resolution");
+        assert_eq!(string_at(metadata, next).0, "1800");
+
+        // The footer points at the first summary record and the summary offsets.
+        let footer = records.last().unwrap().2;
+        let summary_start = u64::from_le_bytes(footer[0..8].try_into().unwrap()) as usize;
+        let summary_offset_start = u64::from_le_bytes(footer[8..16].try_into().unwrap()) as usize;
+        assert_eq!(summary_start, records[11].0);
+        assert_eq!(summary_offset_start, records[17].0);
+
+        let statistics = records[15].2;
+        assert_eq!(u64::from_le_bytes(statistics[0..8].try_into().unwrap()), 4);
+        assert_eq!(u64::from_le_bytes(statistics[26..34].try_into().unwrap()), 100_000_000_000);
+        assert_eq!(u64::from_le_bytes(statistics[34..42].try_into().unwrap()), 100_100_000_000);
+
+        let index = records[16].2;
+        assert_eq!(u64::from_le_bytes(index[0..8].try_into().unwrap()) as usize, records[5].0);
+    }
+
+    #[test]
+    fn test_mcap_ros2_messages() {
+        let bytes = record_scans(McapOptions::default());
+        let records = records(&bytes);
+        let (tf, cloud) = (records[8].2, records[9].2);
+        assert_eq!(u16::from_le_bytes([tf[0], tf[1]]), TF_CHANNEL);
+        assert_eq!(u16::from_le_bytes([cloud[0], cloud[1]]), CLOUD_CHANNEL);
+        assert_eq!(u32::from_le_bytes(cloud[2..6].try_into().unwrap()), 3, "sequence");
+        assert_eq!(&cloud[22..], ros::encode_point_cloud2(&sample_scan(1.0), &PointCloud2Layout::velodyne(), "lidar", 100.1).unwrap());
+        // The TF translation is the sensor position in the Z-up world: x stays 1, sim y (up) becomes z.
+        let data = &tf[22..];
+        let translation_at = data.len() - 7 * 8;
+        let read = |k: usize| f64::from_le_bytes(data[translation_at + 8 * k..translation_at + 8 * k + 8].try_into().unwrap());
+        assert_eq!([read(0), read(1), read(2)], [1.0, 0.0, 1.0]);
+        assert_eq!(read(6), 1.0);
+    }
+
+    #[test]
+    fn test_mcap_foxglove_messages() {
+        let options = McapOptions {
+            format: McapCloudFormat::FoxglovePointCloud,
+            layout: "x,y,z,intensity".parse().unwrap(),
+            ..McapOptions::default()
+        };
+        let bytes = record_scans(options);
+        let records = records(&bytes);
+        assert_eq!(string_at(records[0].2, 0).0, "");
+        assert_eq!(string_at(records[1].2, 2).0, "foxglove.PointCloud");
+        let cloud = std::str::from_utf8(&records[7].2[22..]).unwrap();
+        assert!(cloud.starts_with(r#"{"timestamp":{"sec":100,"nsec":0},"frame_id":"lidar""#));
+        assert!(cloud.contains(r#""point_stride":16"#));
+        assert!(cloud.contains(r#"{"name":"intensity","offset":12,"type":7}"#));
+        // x = 2, y = 0, z = 0, intensity = 0.5
+        assert!(cloud.contains(r#""data":"AAAAQAAAAAAAAAAAAAAAPw=="}"#));
+        let tf = std::str::from_utf8(&records[6].2[22..]).unwrap();
+        assert!(tf.contains(r#""parent_frame_id":"map","child_frame_id":"lidar","translation":{"x":0,"y":0,"z":1}"#));
+        #[cfg(feature = "serde")]
+        {
+            let value: serde_json::Value = serde_json::from_str(cloud).unwrap();
+            assert_eq!(value["fields"].as_array().unwrap().len(), 4);
+            serde_json::from_str::<serde_json::Value>(FOXGLOVE_POINT_CLOUD_SCHEMA).unwrap();
+            serde_json::from_str::<serde_json::Value>(FOXGLOVE_FRAME_TRANSFORM_SCHEMA).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_mcap_rejects_invalid_layout() {
+        let options = McapOptions {
+            layout: PointCloud2Layout::packed(&[]),
+            ..McapOptions::default()
+        };
+        assert!(matches!(McapWriter::new(Vec::new(), options), Err(SimLidarError::Format(_))));
+    }
+
+    #[test]
+    fn test_mcap_foxglove_escapes_strings_and_rejects_nan_poses() {
+        let frame = "a\"b\\c\u{7f}\n\u{1}é";
+        assert_eq!(json_string(frame), "\"a\\\"b\\\\c\u{7f}\\n\\u0001é\"");
+        let mut options = McapOptions {
+            format: McapCloudFormat::FoxglovePointCloud,
+            frame_id: frame.to_string(),
+            ..McapOptions::default()
+        };
+        options.layout.fields[0].name = "x\"".to_string();
+        let bytes = record_scans(options.clone());
+        let records = records(&bytes);
+        let cloud = std::str::from_utf8(&records[7].2[22..]).unwrap();
+        let tf = std::str::from_utf8(&records[6].2[22..]).unwrap();
+        assert!(cloud.contains(r#"{"name":"x\"","offset":0,"type":7}"#));
+        assert!(tf.contains(&format!(r#""child_frame_id":{}"#, json_string(frame))));
+        #[cfg(feature = "serde")]
+        {
+            let cloud: serde_json::Value = serde_json::from_str(cloud).unwrap();
+            assert_eq!(cloud["frame_id"], frame);
+            let tf: serde_json::Value = serde_json::from_str(tf).unwrap();
+            assert_eq!(tf["child_frame_id"], frame);
+        }
+
+        let mut writer = McapWriter::new(Vec::new(), options).unwrap();
+        let mut scan = sample_scan(0.0);
+        scan.sensor_position.x = f32::NAN;
+        assert!(matches!(writer.write_scan(&scan, 0.0), Err(SimLidarError::Format(_))));
+    }
+}
@@ -0,0 +1,157 @@
+use glam::{Mat4, Vec3};
+
+use crate::bvh::Bvh;
+use crate::error::SimLidarError;
+
+/// Triangle mesh produced by the file loaders, ready for [`Mesh::build_bvh`].
+///
+/// Every triangle belongs to an object, and every object carries a name and a
+/// semantic label, so loader metadata such as OBJ groups or glTF node names
+/// survives into the scan (see [`ScanResult::object_ids`] and
+/// [`ScanResult::labels`]).
+///
+/// [`ScanResult::object_ids`]: crate::ScanResult::object_ids
+/// [`ScanResult::labels`]: crate::ScanResult::labels
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    /// Flat `[x0,y0,z0, x1,y1,z1, ...]` vertex positions.
+    pub vertices: Vec<f32>,
+    /// Flat triangle vertex indices.
+    pub indices: Vec<u32>,
+    /// Object id of each triangle.
+    pub object_ids: Vec<u32>,
+    /// Name of each object, indexed by object id.
+    pub object_names: Vec<String>,
+    /// Semantic label of each object, indexed by object id.
+    pub object_labels: Vec<u32>,
+    /// Name of each semantic label, indexed by label.  Label `0` is reserved
+    /// for unlabeled geometry and named `""`.
+    pub label_names: Vec<String>,
+}
+
+impl Mesh {
+    /// An empty mesh.
+    pub fn new() -> Self {
+        Self {
+            label_names: vec![String::new()],
+            ..Self::default()
+        }
+    }
+
+    /// Number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len() / 3
+    }
+
+    /// Number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Build a BVH over the mesh, with its object ids and labels.
+    pub fn build_bvh(&self) -> Result<Bvh, SimLidarError> {
+        let mut bvh = Bvh::build_with_object_ids(&self.vertices, &self.indices, &self.object_ids)?;
+        bvh.set_object_labels(&self.object_labels);
+        Ok(bvh)
+    }
+
+    /// Append `other`, renumbering its vertices and objects after ours and
+    /// merging semantic labels by name.
+    pub fn append(&mut self, other: &Mesh) {
+        if self.label_names.is_empty() {
+            self.label_names.push(String::new());
+        }
+        let vertex_offset = self.vertex_count() as u32;
+        let object_offset = self.object_names.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| i + vertex_offset));
+        self.object_ids.extend(other.object_ids.iter().map(|id| id + object_offset));
+        for (id, name) in other.object_names.iter().enumerate() {
+            let label = other.object_labels.get(id).copied().unwrap_or(0);
+            let label_name = other.label_names.get(label as usize).map_or("", String::as_str);
+            let label = if label == 0 { 0 } else { self.label(label_name) };
+            self.object_names.push(name.clone());
+            self.object_labels.push(label);
+        }
+    }
+
+    /// Scale every vertex by `factor` (e.g. `0.001` for millimetres to metres).
+    pub fn scale(&mut self, factor: f32) {
+        for v in &mut self.vertices {
+            *v *= factor;
+        }
+    }
+
+    /// Transform every vertex by `matrix`.
+    pub fn transform(&mut self, matrix: &Mat4) {
+        for v in self.vertices.chunks_exact_mut(3) {
+            let p = matrix.transform_point3(Vec3::new(v[0], v[1], v[2]));
+            v.copy_from_slice(&p.to_array());
+        }
+    }
+
+    /// Label named `name`, registering it if new.  The empty name is label `0`.
+    pub(crate) fn label(&mut self, name: &str) -> u32 {
+        if self.label_names.is_empty() {
+            self.label_names.push(String::new());
+        }
+        if name.is_empty() {
+            return 0;
+        }
+        match self.label_names.iter().position(|n| n == name) {
+            Some(label) => label as u32,
+            None => {
+                self.label_names.push(name.to_string());
+                (self.label_names.len() - 1) as u32
+            }
+        }
+    }
+
+    /// Register a new object and return its id.
+    pub(crate) fn add_object(&mut self, name: &str, label: u32) -> u32 {
+        self.object_names.push(name.to_string());
+        self.object_labels.push(label);
+        (self.object_names.len() - 1) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad(name: &str, label: &str, x: f32) -> Mesh {
+        let mut mesh = Mesh::new();
+        let label = mesh.label(label);
+        let id = mesh.add_object(name, label);
+        mesh.vertices = vec![x, 0.0, 0.0, x + 1.0, 0.0, 0.0, x + 1.0, 1.0, 0.0, x, 1.0, 0.0];
+        mesh.indices = vec![0, 1, 2, 0, 2, 3];
+        mesh.object_ids = vec![id; 2];
+        mesh
+    }
+
+    #[test]
+    fn test_mesh_append_merges_labels() {
+        let mut mesh = quad("wall", "building", 0.0);
+        mesh.append(&quad("car", "vehicle", 5.0));
+        mesh.append(&quad("house", "building", 10.0));
+        assert_eq!(mesh.triangle_count(), 6);
+        assert_eq!(&mesh.indices[6..9], &[4, 5, 6]);
+        assert_eq!(mesh.object_ids, vec![0, 0, 1, 1, 2, 2]);
+        assert_eq!(mesh.object_names, vec!["wall", "car", "house"]);
+        assert_eq!(mesh.label_names, vec!["", "building", "vehicle"]);
+        assert_eq!(mesh.object_labels, vec![1, 2, 1]);
+
+        let bvh = mesh.build_bvh().unwrap();
+        assert_eq!(bvh.object_label(2), 1);
+    }
+
+    #[test]
+    fn test_mesh_scale_and_transform() {
+        let mut mesh = quad("", "", 1000.0);
+        mesh.scale(0.001);
+        assert_eq!(&mesh.vertices[0..3], &[1.0, 0.0, 0.0]);
+        mesh.transform(&Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0)));
+        assert_eq!(&mesh.vertices[0..3], &[1.0, 2.0, 0.0]);
+        assert_eq!(mesh.object_labels, vec![0]);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::error::SimLidarError;
+use crate::mesh::Mesh;
+
+/// How OBJ faces are grouped into objects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjGrouping {
+    /// One object per `o` statement.
+    Object,
+    /// One object per `g` statement.
+    Group,
+    /// One object per `usemtl` material.
+    Material,
+}
+
+impl FromStr for ObjGrouping {
+    type Err = SimLidarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "object" => Ok(ObjGrouping::Object),
+            "group" => Ok(ObjGrouping::Group),
+            "material" => Ok(ObjGrouping::Material),
+            _ => Err(SimLidarError::Format(format!("unknown OBJ grouping {s:?}"))),
+        }
+    }
+}
+
+/// Parse a Wavefront OBJ file into a [`Mesh`].
+///
+/// Reads `v` positions and `f` faces (with `v`, `v/vt`, `v//vn` or `v/vt/vn`
+/// references, 1-based or negative/relative indices); polygons are
+/// triangulated as fans, so they should be convex.  Faces are grouped into
+/// objects by `grouping`, and every `usemtl` material becomes a semantic
+/// label named after it.  An object whose faces use several materials is
+/// split into one object per material, so each object keeps a single label.
+/// Other statements (normals, texture coordinates, lines, `mtllib`, …) are
+/// ignored.
+pub fn parse_obj(bytes: &[u8], grouping: ObjGrouping) -> Result<Mesh, SimLidarError> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut mesh = Mesh::new();
+    let mut object_name = String::new();
+    let mut group_name = String::new();
+    let mut material = String::new();
+    let mut objects: HashMap<(String, u32), u32> = HashMap::new();
+    let mut face = Vec::new();
+
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+        let malformed = |what: &str| SimLidarError::Format(format!("OBJ line {}: {what}", line_no + 1));
+        match keyword {
+            "v" => {
+                for _ in 0..3 {
+                    let value: f32 = tokens
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| malformed("expected three vertex coordinates"))?;
+                    mesh.vertices.push(value);
+                }
+            }
+            "f" => {
+                face.clear();
+                let vertex_count = mesh.vertex_count() as i64;
+                for token in tokens {
+                    let index: i64 = token
+                        .split('/')
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| malformed("bad face vertex"))?;
+                    let resolved = if index < 0 { vertex_count + index } else { index - 1 };
+                    if index == 0 || !(0..vertex_count).contains(&resolved) {
+                        return Err(malformed(&format!("face index {index} out of range")));
+                    }
+                    face.push(resolved as u32);
+                }
+                if face.len() < 3 {
+                    return Err(malformed("face needs at least three vertices"));
+                }
+                let name = match grouping {
+                    ObjGrouping::Object => &object_name,
+                    ObjGrouping::Group => &group_name,
+                    ObjGrouping::Material => &material,
+                };
+                let label = mesh.label(&material);
+                let id = match objects.get(&(name.clone(), label)) {
+                    Some(&id) => id,
+                    None => {
+                        let id = mesh.add_object(name, label);
+                        objects.insert((name.clone(), label), id);
+                        id
+                    }
+                };
+                for k in 1..face.len() - 1 {
+                    mesh.indices.extend_from_slice(&[face[0], face[k], face[k + 1]]);
+                    mesh.object_ids.push(id);
+                }
+            }
+            "o" => object_name = tokens.collect::<Vec<_>>().join(" "),
+            "g" => group_name = tokens.collect::<Vec<_>>().join(" "),
+            "usemtl" => material = tokens.collect::<Vec<_>>().join(" "),
+            _ => {}
+        }
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = "\
+# two objects
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+o ground
+g floor
+usemtl asphalt
+f 1/1/1 2/2/1 3/3/1 4/4/1
+o car
+g body
+usemtl metal
+v 0 0 1
+v 1 0 1
+v 1 1 1
+f -3//1 -2//1 -1//1
+usemtl glass
+f 5 6 7
+";
+
+    #[test]
+    fn test_obj_triangulates_and_resolves_negative_indices() {
+        let mesh = parse_obj(SCENE.as_bytes(), ObjGrouping::Object).unwrap();
+        assert_eq!(mesh.vertex_count(), 7);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 5, 6]);
+        mesh.build_bvh().unwrap();
+    }
+
+    #[test]
+    fn test_obj_groups_objects_and_materials() {
+        let mesh = parse_obj(SCENE.as_bytes(), ObjGrouping::Object).unwrap();
+        assert_eq!(mesh.object_ids, vec![0, 0, 1, 2]);
+        assert_eq!(mesh.object_names, vec!["ground", "car", "car"]);
+        assert_eq!(mesh.label_names, vec!["", "asphalt", "metal", "glass"]);
+        assert_eq!(mesh.object_labels, vec![1, 2, 3]);
+
+        let mesh = parse_obj(SCENE.as_bytes(), ObjGrouping::Group).unwrap();
+        assert_eq!(mesh.object_names, vec!["floor", "body", "body"]);
+
+        let mesh = parse_obj(SCENE.as_bytes(), ObjGrouping::Material).unwrap();
+        assert_eq!(mesh.object_names, vec!["asphalt", "metal", "glass"]);
+    }
+
+    #[test]
+    fn test_obj_without_groups_or_materials() {
+        let mesh = parse_obj(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n", ObjGrouping::Group).unwrap();
+        assert_eq!(mesh.object_ids, vec![0]);
+        assert_eq!(mesh.object_names, vec![""]);
+        assert_eq!(mesh.object_labels, vec![0]);
+    }
+
+    #[test]
+    fn test_obj_rejects_malformed_input() {
+        for bad in ["v 0 0\n", "v 0 0 0\nf 1 2\n", "v 0 0 0\nf 1 2 3\n", "v 0 0 0\nf 0 1 1\n", "f -1 -2 -3\n"] {
+            let err = parse_obj(bad.as_bytes(), ObjGrouping::Object).unwrap_err();
+            assert!(matches!(err, SimLidarError::Format(_)), "{bad:?}");
+        }
+        let err = parse_obj(b"v 0 0 0\nv 1 0 0\nf 1 2 9\n", ObjGrouping::Object).unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{err}");
+    }
+}
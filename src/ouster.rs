@@ -0,0 +1,425 @@
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+use glam::Vec3;
+
+use crate::error::{ConfigIssue, SimLidarError};
+use crate::pcap::{PcapWriter, UdpEndpoints};
+use crate::raycaster::ScanResult;
+use crate::sensor::SensorConfig;
+
+/// Measurement columns carried by one lidar packet.
+pub const OUSTER_COLUMNS_PER_PACKET: usize = 16;
+/// Encoder ticks per revolution reported in legacy column headers.
+const ENCODER_TICKS_PER_REV: u64 = 90_112;
+/// Size of a pixel in both supported profiles.
+const PIXEL_SIZE: usize = 12;
+/// Packet header and footer of the non-legacy profiles.
+const PACKET_HEADER_SIZE: usize = 32;
+const PACKET_FOOTER_SIZE: usize = 32;
+/// Largest return photon count, reached by a fully reflective target at 1 m.
+const SIGNAL_AT_ONE_METRE: f32 = 1000.0;
+
+/// Default network settings of an Ouster sensor streaming to a host: lidar
+/// packets are sent to UDP port 7502.
+pub const OUSTER_ENDPOINTS: UdpEndpoints = UdpEndpoints {
+    source_mac: [0xbc, 0x0f, 0xa7, 0x00, 0x00, 0x01],
+    source_ip: Ipv4Addr::new(192, 168, 1, 100),
+    source_port: 7502,
+    destination_ip: Ipv4Addr::new(192, 168, 1, 1),
+    destination_port: 7502,
+};
+
+/// Lidar modes (`columns x Hz`) accepted by the sensors and `ouster-sdk`.
+const LIDAR_MODES: [(u32, u32); 6] = [(512, 10), (512, 20), (1024, 10), (1024, 20), (2048, 10), (4096, 5)];
+
+/// UDP lidar packet profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OusterProfile {
+    /// Pre-2.2 firmware format: 20-bit range, 16-bit reflectivity, signal and
+    /// NIR, with encoder counts in every column header.
+    Legacy,
+    /// `RNG19_RFL8_SIG16_NIR16`: 19-bit range, 8-bit reflectivity, 16-bit
+    /// signal and NIR, with a packet header and footer.
+    Rng19Rfl8Sig16Nir16,
+}
+
+impl OusterProfile {
+    /// Name used by `udp_profile_lidar` in the sensor metadata.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OusterProfile::Legacy => "LEGACY",
+            OusterProfile::Rng19Rfl8Sig16Nir16 => "RNG19_RFL8_SIG16_NIR16",
+        }
+    }
+
+    fn column_header_size(self) -> usize {
+        match self {
+            OusterProfile::Legacy => 16,
+            OusterProfile::Rng19Rfl8Sig16Nir16 => 12,
+        }
+    }
+
+    fn column_footer_size(self) -> usize {
+        match self {
+            OusterProfile::Legacy => 4,
+            OusterProfile::Rng19Rfl8Sig16Nir16 => 0,
+        }
+    }
+
+    /// Largest range in millimetres the range field can hold.
+    fn max_range_mm(self) -> u32 {
+        match self {
+            OusterProfile::Legacy => 0xf_ffff,
+            OusterProfile::Rng19Rfl8Sig16Nir16 => 0x7_ffff,
+        }
+    }
+
+    /// Size of a lidar packet for `channels` pixels per column.
+    pub fn packet_size(self, channels: usize) -> usize {
+        let column = self.column_header_size() + channels * PIXEL_SIZE + self.column_footer_size();
+        let framing = match self {
+            OusterProfile::Legacy => 0,
+            OusterProfile::Rng19Rfl8Sig16Nir16 => PACKET_HEADER_SIZE + PACKET_FOOTER_SIZE,
+        };
+        framing + OUSTER_COLUMNS_PER_PACKET * column
+    }
+}
+
+impl FromStr for OusterProfile {
+    type Err = SimLidarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "legacy" => Ok(OusterProfile::Legacy),
+            "rng19" | "rng19_rfl8_sig16_nir16" => Ok(OusterProfile::Rng19Rfl8Sig16Nir16),
+            _ => Err(SimLidarError::Format(format!("unknown Ouster UDP profile {s:?}"))),
+        }
+    }
+}
+
+/// One encoded Ouster lidar packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OusterPacket {
+    /// Time of the first column in the packet, in seconds.
+    pub timestamp: f64,
+    /// The UDP payload.
+    pub data: Vec<u8>,
+}
+
+/// Check that `config` describes a sensor `ouster-sdk` can decode.
+fn check_config(config: &SensorConfig) -> Result<(), SimLidarError> {
+    let mut issues = Vec::new();
+    if ![16, 32, 64, 128].contains(&config.vertical_channels) {
+        issues.push(ConfigIssue::new("vertical_channels", "Ouster sensors have 16, 32, 64 or 128 channels"));
+    }
+    if lidar_mode(config).is_none() {
+        issues.push(ConfigIssue::new(
+            "horizontal_resolution",
+            "with scan_frequency_hz must form an Ouster lidar mode (512x10, 512x20, 1024x10, 1024x20, 2048x10 or 4096x5)",
+        ));
+    }
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(SimLidarError::InvalidConfig(issues))
+    }
+}
+
+/// `lidar_mode` string of `config`, if it matches one.
+fn lidar_mode(config: &SensorConfig) -> Option<String> {
+    let hz = config.scan_frequency_hz;
+    LIDAR_MODES
+        .iter()
+        .find(|&&(columns, mode_hz)| columns == config.horizontal_resolution && (hz - mode_hz as f32).abs() < 1e-3)
+        .map(|(columns, hz)| format!("{columns}x{hz}"))
+}
+
+/// Encode an organized scan as Ouster lidar packets.
+///
+/// * `result`     – Scan taken with `config`, with `organized` enabled.
+/// * `config`     – Sensor configuration; `horizontal_resolution` and
+///   `scan_frequency_hz` must form an Ouster lidar mode such as `1024x10`.
+/// * `profile`    – UDP packet profile.
+/// * `frame_id`   – Frame counter written to every packet.
+/// * `scan_start` – Time of the first column, in seconds (Unix time).
+///
+/// Column `c` of the scan becomes measurement id `c`, and pixel row 0 is the
+/// highest beam, as in the sensor.  Range is the distance to the sensor in
+/// millimetres (0 for misses and out-of-range returns), reflectivity is the
+/// point intensity scaled to `0..=255`, signal falls off with the square of
+/// the range, and NIR is 0 since ambient light is not simulated.  Pair the
+/// packets with [`ouster_metadata_json`] for the same configuration.
+pub fn encode_ouster_packets(
+    result: &ScanResult,
+    config: &SensorConfig,
+    profile: OusterProfile,
+    frame_id: u16,
+    scan_start: f64,
+) -> Result<Vec<OusterPacket>, SimLidarError> {
+    check_config(config)?;
+    let width = config.horizontal_resolution as usize;
+    let channels = config.vertical_channels as usize;
+    if result.width as usize != width || result.height as usize != channels || result.hit_count != width * channels {
+        return Err(SimLidarError::InvalidConfig(vec![ConfigIssue::new(
+            "organized",
+            format!(
+                "Ouster packets need an organized {channels} × {width} scan, got {} × {}",
+                result.height, result.width
+            ),
+        )]));
+    }
+
+    let packet_count = width.div_ceil(OUSTER_COLUMNS_PER_PACKET);
+    let mut packets = Vec::with_capacity(packet_count);
+    for p in 0..packet_count {
+        let first_column = p * OUSTER_COLUMNS_PER_PACKET;
+        let mut data = Vec::with_capacity(profile.packet_size(channels));
+        if profile == OusterProfile::Rng19Rfl8Sig16Nir16 {
+            let mut header = [0u8; PACKET_HEADER_SIZE];
+            header[0..2].copy_from_slice(&1u16.to_le_bytes()); // packet type: lidar data
+            header[2..4].copy_from_slice(&frame_id.to_le_bytes());
+            // initialization_id and serial number stay 0, matching the metadata.
+            data.extend_from_slice(&header);
+        }
+        for column in first_column..first_column + OUSTER_COLUMNS_PER_PACKET {
+            let valid = column < width;
+            let time = if valid { result.times.get(column).copied().unwrap_or(0.0) as f64 } else { 0.0 };
+            let timestamp_ns = ((scan_start + time) * 1e9).round().max(0.0) as u64;
+            let measurement_id = column as u16;
+            data.extend_from_slice(&timestamp_ns.to_le_bytes());
+            data.extend_from_slice(&measurement_id.to_le_bytes());
+            match profile {
+                OusterProfile::Legacy => {
+                    let encoder_count = (column as u64 * ENCODER_TICKS_PER_REV / width as u64) as u32;
+                    data.extend_from_slice(&frame_id.to_le_bytes());
+                    data.extend_from_slice(&encoder_count.to_le_bytes());
+                }
+                OusterProfile::Rng19Rfl8Sig16Nir16 => data.extend_from_slice(&(valid as u16).to_le_bytes()),
+            }
+            for row in 0..channels {
+                let ring = channels - 1 - row;
+                let (range_mm, reflectivity, signal) = if valid {
+                    encode_pixel(result, ring * width + column, profile.max_range_mm())
+                } else {
+                    (0, 0, 0)
+                };
+                data.extend_from_slice(&range_mm.to_le_bytes());
+                match profile {
+                    OusterProfile::Legacy => data.extend_from_slice(&(reflectivity as u16).to_le_bytes()),
+                    OusterProfile::Rng19Rfl8Sig16Nir16 => data.extend_from_slice(&[reflectivity, 0]),
+                }
+                data.extend_from_slice(&signal.to_le_bytes());
+                data.extend_from_slice(&0u16.to_le_bytes()); // NIR
+                data.extend_from_slice(&0u16.to_le_bytes());
+            }
+            if profile == OusterProfile::Legacy {
+                let status: u32 = if valid { 0xffff_ffff } else { 0 };
+                data.extend_from_slice(&status.to_le_bytes());
+            }
+        }
+        if profile == OusterProfile::Rng19Rfl8Sig16Nir16 {
+            data.extend_from_slice(&[0u8; PACKET_FOOTER_SIZE]);
+        }
+        debug_assert_eq!(data.len(), profile.packet_size(channels));
+        let time = result.times.get(first_column).copied().unwrap_or(0.0) as f64;
+        packets.push(OusterPacket { timestamp: scan_start + time, data });
+    }
+    Ok(packets)
+}
+
+/// Range in millimetres, reflectivity and signal of point `i`.
+fn encode_pixel(result: &ScanResult, i: usize, max_range_mm: u32) -> (u32, u8, u16) {
+    let hit = Vec3::from_slice(&result.hits[i * 3..i * 3 + 3]);
+    let range = (hit - result.sensor_position).length();
+    let range_mm = (range * 1000.0).round();
+    if !range_mm.is_finite() || range_mm > max_range_mm as f32 {
+        return (0, 0, 0);
+    }
+    let intensity = result.intensities.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+    let signal = intensity * SIGNAL_AT_ONE_METRE / range.max(1.0).powi(2);
+    (range_mm as u32, (intensity * 255.0).round() as u8, signal.round() as u16)
+}
+
+/// Sensor metadata JSON describing `config`, in the flat format written by
+/// firmware 2.x and read by `ouster-sdk`.
+///
+/// Beam altitudes follow the channel spacing of
+/// [`SensorConfig::generate_local_ray_directions`] (highest beam first),
+/// azimuth offsets and the beam origin offset are zero, and every transform
+/// is the identity, so the SDK's point cloud matches the simulated one.
+pub fn ouster_metadata_json(config: &SensorConfig, profile: OusterProfile) -> Result<String, SimLidarError> {
+    check_config(config)?;
+    let channels = config.vertical_channels as usize;
+    let width = config.horizontal_resolution;
+    let step = (config.vertical_fov_upper - config.vertical_fov_lower) / (channels - 1) as f32;
+    let altitudes: Vec<String> =
+        (0..channels).map(|row| (config.vertical_fov_upper - row as f32 * step).to_string()).collect();
+    let zeros = vec!["0"; channels].join(", ");
+    let identity = "[1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]";
+    let mode = lidar_mode(config).unwrap_or_default();
+
+    let mut json = String::from("{\n");
+    json.push_str(&format!("  \"prod_line\": \"OS-1-{channels}\",\n"));
+    json.push_str("  \"prod_sn\": \"000000000000\",\n");
+    json.push_str("  \"build_rev\": \"v2.3.0\",\n");
+    json.push_str("  \"status\": \"RUNNING\",\n");
+    json.push_str("  \"initialization_id\": 0,\n");
+    json.push_str(&format!("  \"lidar_mode\": \"{mode}\",\n"));
+    json.push_str(&format!("  \"beam_altitude_angles\": [{}],\n", altitudes.join(", ")));
+    json.push_str(&format!("  \"beam_azimuth_angles\": [{zeros}],\n"));
+    json.push_str("  \"lidar_origin_to_beam_origin_mm\": 0,\n");
+    json.push_str(&format!("  \"beam_to_lidar_transform\": {identity},\n"));
+    json.push_str(&format!("  \"lidar_to_sensor_transform\": {identity},\n"));
+    json.push_str(&format!("  \"imu_to_sensor_transform\": {identity},\n"));
+    json.push_str(&format!("  \"udp_port_lidar\": {},\n", OUSTER_ENDPOINTS.destination_port));
+    json.push_str("  \"udp_port_imu\": 7503,\n");
+    json.push_str("  \"data_format\": {\n");
+    json.push_str(&format!("    \"pixels_per_column\": {channels},\n"));
+    json.push_str(&format!("    \"columns_per_packet\": {OUSTER_COLUMNS_PER_PACKET},\n"));
+    json.push_str(&format!("    \"columns_per_frame\": {width},\n"));
+    json.push_str(&format!("    \"pixel_shift_by_row\": [{zeros}],\n"));
+    json.push_str(&format!("    \"column_window\": [0, {}],\n", width - 1));
+    json.push_str(&format!("    \"udp_profile_lidar\": \"{}\",\n", profile.as_str()));
+    json.push_str("    \"udp_profile_imu\": \"LEGACY\"\n");
+    json.push_str("  }\n}\n");
+    Ok(json)
+}
+
+/// Write packets into a pcap capture as sent by a sensor streaming to a host
+/// ([`OUSTER_ENDPOINTS`]).
+pub fn write_ouster_pcap<W: Write>(out: W, packets: &[OusterPacket]) -> Result<W, SimLidarError> {
+    let mut pcap = PcapWriter::new(out)?;
+    for packet in packets {
+        pcap.write_udp(packet.timestamp, &OUSTER_ENDPOINTS, &packet.data)?;
+    }
+    pcap.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    /// Organized scan for `config` where every point of ring `r`, column `c`
+    /// lies `1 + r + c / 1000` metres in front of the sensor.
+    fn organized_scan(config: &SensorConfig) -> ScanResult {
+        let (channels, width) = (config.vertical_channels as usize, config.horizontal_resolution as usize);
+        let mut result = ScanResult {
+            width: width as u32,
+            height: channels as u32,
+            hit_count: channels * width,
+            sensor_position: Vec3::new(1.0, 0.0, 0.0),
+            sensor_rotation: Quat::IDENTITY,
+            ..ScanResult::default()
+        };
+        for r in 0..channels {
+            for c in 0..width {
+                let d = 1.0 + r as f32 + c as f32 / 1000.0;
+                result.hits.extend_from_slice(&[1.0 + d, 0.0, 0.0]);
+                result.intensities.push(0.8);
+                result.times.push(c as f32 / (width as f32 * config.scan_frequency_hz));
+            }
+        }
+        result
+    }
+
+    fn u16_at(data: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([data[at], data[at + 1]])
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_ouster_legacy_packet_layout() {
+        let config = SensorConfig::ouster_os1_32();
+        let mut scan = organized_scan(&config);
+        // Top beam (ring 31), column 0 is a miss.
+        let miss = 31 * 1024;
+        scan.hits[miss * 3..miss * 3 + 3].copy_from_slice(&[f32::NAN; 3]);
+        let packets = encode_ouster_packets(&scan, &config, OusterProfile::Legacy, 7, 100.0).unwrap();
+        assert_eq!(packets.len(), 64);
+        let data = &packets[1].data;
+        assert_eq!(data.len(), 16 * (16 + 32 * 12 + 4));
+
+        // Column 16 is the first of the second packet.
+        let column = 16 + 32 * 12 + 4;
+        let expected_ns = ((100.0 + 16.0 / 10_240.0) * 1e9f64).round() as u64;
+        let timestamp = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        assert!(timestamp.abs_diff(expected_ns) < 1_000, "{timestamp} vs {expected_ns}");
+        assert_eq!(u16_at(data, 8), 16);
+        assert_eq!(u16_at(data, 10), 7);
+        assert_eq!(u32_at(data, 12), 16 * 90_112 / 1024);
+        // Row 0 is the top beam: ring 31 at 32.016 m; row 31 is ring 0.
+        assert_eq!(u32_at(data, 16), 32_016);
+        assert_eq!(u16_at(data, 20), 204);
+        assert_eq!(u32_at(data, 16 + 31 * 12), 1_016);
+        assert_eq!(u32_at(data, column - 4), 0xffff_ffff);
+
+        let first = &packets[0].data;
+        assert_eq!(u32_at(first, 16), 0, "Misses encode range 0");
+    }
+
+    #[test]
+    fn test_ouster_rng19_packet_layout() {
+        let config = SensorConfig::ouster_os1_64();
+        let scan = organized_scan(&config);
+        let packets = encode_ouster_packets(&scan, &config, OusterProfile::Rng19Rfl8Sig16Nir16, 3, 0.0).unwrap();
+        assert_eq!(packets.len(), 128);
+        let data = &packets[0].data;
+        assert_eq!(data.len(), 32 + 16 * (12 + 64 * 12) + 32);
+        assert_eq!(u16_at(data, 0), 1);
+        assert_eq!(u16_at(data, 2), 3);
+        let column = 12 + 64 * 12;
+        assert_eq!(u16_at(data, 32 + column + 8), 1, "Measurement id");
+        assert_eq!(u16_at(data, 32 + column + 10), 1, "Column valid");
+        // Bottom beam of column 1: 1.001 m, reflectivity 0.8 × 255.
+        let pixel = 32 + column + 12 + 63 * 12;
+        assert_eq!(u32_at(data, pixel) & 0x7_ffff, 1_001);
+        assert_eq!(data[pixel + 4], 204);
+        assert_eq!(u16_at(data, pixel + 6), 798);
+    }
+
+    #[test]
+    fn test_ouster_rejects_mismatched_scans() {
+        let config = SensorConfig::ouster_os1_32();
+        let mut other = config.clone();
+        other.vertical_channels = 64;
+        let scan = organized_scan(&config);
+        assert!(encode_ouster_packets(&scan, &other, OusterProfile::Legacy, 0, 0.0).is_err());
+
+        let mut bad_mode = config.clone();
+        bad_mode.scan_frequency_hz = 15.0;
+        let err = encode_ouster_packets(&scan, &bad_mode, OusterProfile::Legacy, 0, 0.0).unwrap_err();
+        assert!(matches!(err, SimLidarError::InvalidConfig(issues) if issues[0].field == "horizontal_resolution"));
+    }
+
+    #[test]
+    fn test_ouster_metadata_json() {
+        let json = ouster_metadata_json(&SensorConfig::ouster_os1_64(), OusterProfile::Rng19Rfl8Sig16Nir16).unwrap();
+        assert!(json.contains("\"lidar_mode\": \"2048x10\""));
+        assert!(json.contains("\"beam_altitude_angles\": [22.5, "));
+        assert!(json.contains("-22.5],"));
+        assert!(json.contains("\"columns_per_frame\": 2048"));
+        assert!(json.contains("\"udp_profile_lidar\": \"RNG19_RFL8_SIG16_NIR16\""));
+        #[cfg(feature = "serde")]
+        {
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value["beam_altitude_angles"].as_array().unwrap().len(), 64);
+            assert_eq!(value["data_format"]["column_window"][1], 2047);
+        }
+    }
+
+    #[test]
+    fn test_ouster_pcap_frames() {
+        let config = SensorConfig::ouster_os1_32();
+        let packets = encode_ouster_packets(&organized_scan(&config), &config, OusterProfile::Legacy, 0, 0.0).unwrap();
+        let bytes = write_ouster_pcap(Vec::new(), &packets).unwrap();
+        let frame = 16 + 42 + OusterProfile::Legacy.packet_size(32);
+        assert_eq!(bytes.len(), 24 + 64 * frame);
+        assert_eq!(u16::from_be_bytes([bytes[24 + 16 + 36], bytes[24 + 16 + 37]]), 7502);
+    }
+}
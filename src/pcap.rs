@@ -0,0 +1,147 @@
+use std::io::Write;
+use std::net::Ipv4Addr;
+
+use crate::error::SimLidarError;
+
+/// Sizes of the synthetic headers wrapped around every UDP payload.
+const ETHERNET_HEADER: usize = 14;
+const IPV4_HEADER: usize = 20;
+const UDP_HEADER: usize = 8;
+
+/// Source and destination of the UDP datagrams written by a [`PcapWriter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UdpEndpoints {
+    pub source_mac: [u8; 6],
+    pub source_ip: Ipv4Addr,
+    pub source_port: u16,
+    pub destination_ip: Ipv4Addr,
+    pub destination_port: u16,
+}
+
+/// Writes UDP datagrams into a classic (microsecond) pcap capture, with
+/// synthetic Ethernet, IPv4 and UDP headers, so sensor drivers and tools such
+/// as Wireshark or VeloView can replay them.
+pub struct PcapWriter<W: Write> {
+    out: W,
+    ip_id: u16,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Start a capture by writing the pcap global header.
+    pub fn new(mut out: W) -> Result<Self, SimLidarError> {
+        out.write_all(&0xa1b2_c3d4u32.to_le_bytes())?; // magic, microsecond timestamps
+        out.write_all(&2u16.to_le_bytes())?; // version 2.4
+        out.write_all(&4u16.to_le_bytes())?;
+        out.write_all(&0i32.to_le_bytes())?; // UTC
+        out.write_all(&0u32.to_le_bytes())?; // timestamp accuracy
+        out.write_all(&65_535u32.to_le_bytes())?; // snapshot length
+        out.write_all(&1u32.to_le_bytes())?; // LINKTYPE_ETHERNET
+        Ok(Self { out, ip_id: 0 })
+    }
+
+    /// Append one UDP datagram captured at `timestamp` seconds (Unix time).
+    pub fn write_udp(&mut self, timestamp: f64, endpoints: &UdpEndpoints, payload: &[u8]) -> Result<(), SimLidarError> {
+        let udp_len = UDP_HEADER + payload.len();
+        let ip_len = IPV4_HEADER + udp_len;
+        if ip_len > u16::MAX as usize {
+            return Err(SimLidarError::Format(format!("UDP payload of {} bytes is too large", payload.len())));
+        }
+        let frame_len = (ETHERNET_HEADER + ip_len) as u32;
+
+        let seconds = timestamp.max(0.0).floor();
+        let micros = ((timestamp.max(0.0) - seconds) * 1e6).round().min(999_999.0);
+        self.out.write_all(&(seconds as u32).to_le_bytes())?;
+        self.out.write_all(&(micros as u32).to_le_bytes())?;
+        self.out.write_all(&frame_len.to_le_bytes())?;
+        self.out.write_all(&frame_len.to_le_bytes())?;
+
+        // Ethernet II, broadcast destination.
+        let mut frame = Vec::with_capacity(frame_len as usize);
+        frame.extend_from_slice(&[0xff; 6]);
+        frame.extend_from_slice(&endpoints.source_mac);
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+
+        // IPv4, don't fragment.
+        let mut ip = [0u8; IPV4_HEADER];
+        ip[0] = 0x45;
+        ip[2..4].copy_from_slice(&(ip_len as u16).to_be_bytes());
+        ip[4..6].copy_from_slice(&self.ip_id.to_be_bytes());
+        ip[6] = 0x40;
+        ip[8] = 64; // TTL
+        ip[9] = 17; // UDP
+        ip[12..16].copy_from_slice(&endpoints.source_ip.octets());
+        ip[16..20].copy_from_slice(&endpoints.destination_ip.octets());
+        let checksum = ipv4_checksum(&ip);
+        ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+        frame.extend_from_slice(&ip);
+        self.ip_id = self.ip_id.wrapping_add(1);
+
+        // UDP, checksum omitted (allowed over IPv4).
+        frame.extend_from_slice(&endpoints.source_port.to_be_bytes());
+        frame.extend_from_slice(&endpoints.destination_port.to_be_bytes());
+        frame.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        frame.extend_from_slice(&0u16.to_be_bytes());
+        frame.extend_from_slice(payload);
+
+        self.out.write_all(&frame)?;
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn finish(mut self) -> Result<W, SimLidarError> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// One's-complement sum of the 16-bit words of an IPv4 header.
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header
+        .chunks_exact(2)
+        .map(|w| u16::from_be_bytes([w[0], w[1]]) as u32)
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> UdpEndpoints {
+        UdpEndpoints {
+            source_mac: [0x60, 0x76, 0x88, 0, 0, 1],
+            source_ip: Ipv4Addr::new(192, 168, 1, 201),
+            source_port: 2368,
+            destination_ip: Ipv4Addr::BROADCAST,
+            destination_port: 2368,
+        }
+    }
+
+    #[test]
+    fn test_pcap_headers() {
+        let mut pcap = PcapWriter::new(Vec::new()).unwrap();
+        pcap.write_udp(1_700_000_000.25, &endpoints(), &[0xab; 10]).unwrap();
+        let bytes = pcap.finish().unwrap();
+
+        assert_eq!(&bytes[0..4], &[0xd4, 0xc3, 0xb2, 0xa1]);
+        assert_eq!(bytes.len(), 24 + 16 + 14 + 20 + 8 + 10);
+        let record = &bytes[24..];
+        assert_eq!(u32::from_le_bytes(record[0..4].try_into().unwrap()), 1_700_000_000);
+        assert_eq!(u32::from_le_bytes(record[4..8].try_into().unwrap()), 250_000);
+        assert_eq!(u32::from_le_bytes(record[8..12].try_into().unwrap()), 52);
+
+        let frame = &record[16..];
+        assert_eq!(&frame[12..14], &[0x08, 0x00]);
+        let ip = &frame[14..34];
+        assert_eq!(u16::from_be_bytes([ip[2], ip[3]]), 38);
+        assert_eq!(ipv4_checksum(ip), 0, "A header including its checksum sums to zero");
+        assert_eq!(&ip[12..16], &[192, 168, 1, 201]);
+        let udp = &frame[34..42];
+        assert_eq!(u16::from_be_bytes([udp[2], udp[3]]), 2368);
+        assert_eq!(u16::from_be_bytes([udp[4], udp[5]]), 18);
+        assert_eq!(&frame[42..], &[0xab; 10]);
+    }
+}
@@ -0,0 +1,502 @@
+use std::str::FromStr;
+
+use glam::{Quat, Vec3};
+
+use crate::error::SimLidarError;
+use crate::lzf;
+use crate::point_fields::{point_fields, PointField, ScalarKind};
+use crate::raycaster::ScanResult;
+
+/// Encoding of the `DATA` section of a PCD file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcdEncoding {
+    /// One whitespace-separated line of text per point.
+    Ascii,
+    /// Packed little-endian records, one per point.
+    Binary,
+    /// LZF-compressed little-endian data laid out field by field, as written
+    /// by PCL's `binary_compressed` mode.
+    BinaryCompressed,
+}
+
+impl PcdEncoding {
+    /// Name used on the PCD `DATA` line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PcdEncoding::Ascii => "ascii",
+            PcdEncoding::Binary => "binary",
+            PcdEncoding::BinaryCompressed => "binary_compressed",
+        }
+    }
+}
+
+impl FromStr for PcdEncoding {
+    type Err = SimLidarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(PcdEncoding::Ascii),
+            "binary" => Ok(PcdEncoding::Binary),
+            "binary_compressed" => Ok(PcdEncoding::BinaryCompressed),
+            _ => Err(SimLidarError::Format(format!("unknown PCD encoding {s:?}"))),
+        }
+    }
+}
+
+/// A per-point field of a PCD file (`FIELDS` / `SIZE` / `TYPE` / `COUNT`).
+#[derive(Clone, Debug, PartialEq)]
+struct Field {
+    name: String,
+    size: usize,
+    kind: char,
+    count: usize,
+}
+
+impl Field {
+    /// Bytes taken by this field in one point record.
+    fn stride(&self) -> usize {
+        self.size * self.count
+    }
+
+    /// Decode one little-endian element of this field.
+    fn decode(&self, bytes: &[u8]) -> Result<f64, SimLidarError> {
+        let value = match (self.kind, self.size) {
+            ('F', 4) => f32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ('F', 8) => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            ('U', 1) => bytes[0] as f64,
+            ('U', 2) => u16::from_le_bytes(bytes[..2].try_into().unwrap()) as f64,
+            ('U', 4) => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ('U', 8) => u64::from_le_bytes(bytes[..8].try_into().unwrap()) as f64,
+            ('I', 1) => bytes[0] as i8 as f64,
+            ('I', 2) => i16::from_le_bytes(bytes[..2].try_into().unwrap()) as f64,
+            ('I', 4) => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ('I', 8) => i64::from_le_bytes(bytes[..8].try_into().unwrap()) as f64,
+            _ => {
+                return Err(SimLidarError::Format(format!(
+                    "unsupported PCD field type {}{} for {:?}",
+                    self.kind, self.size, self.name
+                )))
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// PCD `TYPE` letter of a scalar kind.
+fn type_letter(kind: ScalarKind) -> char {
+    match kind {
+        ScalarKind::F32 => 'F',
+        ScalarKind::U16 | ScalarKind::U32 => 'U',
+    }
+}
+
+/// Serialize a scan as a PCD v0.7 file.
+///
+/// Writes `x y z` plus every optional per-point field the scan produced
+/// (`intensity`, `ring`, `time`, `label`, `object_id`, and `velocity` for
+/// Doppler scans).  The sensor pose is stored in `VIEWPOINT`.  Organized
+/// scans keep their `width × height` layout, with misses written as `NaN`.
+pub fn write_pcd(result: &ScanResult, encoding: PcdEncoding) -> Vec<u8> {
+    let fields = point_fields(result);
+    let n = result.hit_count;
+    // Unorganized results built by hand may leave width/height unset.
+    let (width, height) = if result.width as usize * result.height as usize == n {
+        (result.width as usize, result.height as usize)
+    } else {
+        (n, 1)
+    };
+
+    let join = |f: &dyn Fn(&PointField) -> String| fields.iter().map(f).collect::<Vec<_>>().join(" ");
+    let header = format!(
+        "# .PCD v0.7 - Point Cloud Data file format\n\
+         VERSION 0.7\n\
+         FIELDS {}\n\
+         SIZE {}\n\
+         TYPE {}\n\
+         COUNT {}\n\
+         WIDTH {width}\n\
+         HEIGHT {height}\n\
+         VIEWPOINT {}\n\
+         POINTS {n}\n\
+         DATA {}\n",
+        join(&|f| f.name().to_string()),
+        join(&|f| f.kind().size().to_string()),
+        join(&|f| type_letter(f.kind()).to_string()),
+        join(&|_| "1".to_string()),
+        viewpoint(result.sensor_position, result.sensor_rotation),
+        encoding.as_str(),
+    );
+    let mut out = header.into_bytes();
+
+    match encoding {
+        PcdEncoding::Ascii => {
+            let mut text = String::new();
+            for i in 0..n {
+                for (k, field) in fields.iter().enumerate() {
+                    if k > 0 {
+                        text.push(' ');
+                    }
+                    field.value(result, i).write_ascii(&mut text);
+                }
+                text.push('\n');
+            }
+            out.extend_from_slice(text.as_bytes());
+        }
+        PcdEncoding::Binary => {
+            for i in 0..n {
+                for field in &fields {
+                    field.value(result, i).write_le(&mut out);
+                }
+            }
+        }
+        PcdEncoding::BinaryCompressed => {
+            let mut raw = Vec::with_capacity(n * fields.iter().map(|f| f.kind().size()).sum::<usize>());
+            for field in &fields {
+                for i in 0..n {
+                    field.value(result, i).write_le(&mut raw);
+                }
+            }
+            let compressed = lzf::compress(&raw);
+            out.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(raw.len() as u32).to_le_bytes());
+            out.extend_from_slice(&compressed);
+        }
+    }
+    out
+}
+
+/// `VIEWPOINT` values: translation followed by the `w x y z` quaternion.
+fn viewpoint(position: Vec3, rotation: Quat) -> String {
+    let values = [position.x, position.y, position.z, rotation.w, rotation.x, rotation.y, rotation.z];
+    values.map(|v| v.to_string()).join(" ")
+}
+
+/// Parsed PCD header.
+struct Header {
+    fields: Vec<Field>,
+    viewpoint: (Vec3, Quat),
+    width: usize,
+    height: usize,
+    points: usize,
+    encoding: PcdEncoding,
+}
+
+fn format_err(msg: impl Into<String>) -> SimLidarError {
+    SimLidarError::Format(msg.into())
+}
+
+/// Parse the header, returning it with the byte offset of the data section.
+fn parse_header(bytes: &[u8]) -> Result<(Header, usize), SimLidarError> {
+    let mut names: Vec<String> = Vec::new();
+    let mut sizes: Vec<usize> = Vec::new();
+    let mut kinds: Vec<char> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+    let (mut width, mut height, mut points) = (None, 1usize, None);
+    let mut viewpoint = (Vec3::ZERO, Quat::IDENTITY);
+    let mut offset = 0;
+
+    loop {
+        let rest = &bytes[offset..];
+        let line_len = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| format_err("PCD header has no DATA line"))?;
+        let line = std::str::from_utf8(&rest[..line_len])
+            .map_err(|_| format_err("PCD header is not valid UTF-8"))?
+            .trim();
+        offset += line_len + 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let key = parts.next().unwrap_or_default().to_ascii_uppercase();
+        let values: Vec<&str> = parts.collect();
+        let parse_usize = |v: &str| v.parse::<usize>().map_err(|_| format_err(format!("bad {key} value {v:?}")));
+        match key.as_str() {
+            "FIELDS" => names = values.iter().map(|v| v.to_string()).collect(),
+            "SIZE" => sizes = values.iter().map(|v| parse_usize(v)).collect::<Result<_, _>>()?,
+            "TYPE" => {
+                kinds = values
+                    .iter()
+                    .map(|v| v.chars().next().map(|c| c.to_ascii_uppercase()))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| format_err("empty PCD TYPE"))?
+            }
+            "COUNT" => counts = values.iter().map(|v| parse_usize(v)).collect::<Result<_, _>>()?,
+            "WIDTH" => width = Some(parse_usize(values.first().copied().unwrap_or_default())?),
+            "HEIGHT" => height = parse_usize(values.first().copied().unwrap_or_default())?,
+            "VIEWPOINT" => {
+                let v = values
+                    .iter()
+                    .map(|v| v.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()
+                    .filter(|v| v.len() == 7)
+                    .ok_or_else(|| format_err("PCD VIEWPOINT needs 7 numbers"))?;
+                viewpoint = (Vec3::new(v[0], v[1], v[2]), Quat::from_xyzw(v[4], v[5], v[6], v[3]));
+            }
+            "POINTS" => points = Some(parse_usize(values.first().copied().unwrap_or_default())?),
+            "DATA" => {
+                let encoding = values.first().copied().unwrap_or_default().parse()?;
+                if counts.is_empty() {
+                    counts = vec![1; names.len()];
+                }
+                if sizes.len() != names.len() || kinds.len() != names.len() || counts.len() != names.len() {
+                    return Err(format_err("PCD FIELDS, SIZE, TYPE and COUNT lengths differ"));
+                }
+                let fields = names
+                    .into_iter()
+                    .zip(sizes)
+                    .zip(kinds)
+                    .zip(counts)
+                    .map(|(((name, size), kind), count)| Field { name, size, kind, count })
+                    .collect();
+                let width = width.ok_or_else(|| format_err("PCD header has no WIDTH"))?;
+                let points = match points {
+                    Some(points) => points,
+                    None => width.checked_mul(height).ok_or_else(|| format_err("PCD WIDTH × HEIGHT overflows"))?,
+                };
+                let header = Header {
+                    fields,
+                    viewpoint,
+                    width,
+                    height,
+                    points,
+                    encoding,
+                };
+                return Ok((header, offset));
+            }
+            // VERSION and unknown keys carry nothing we need.
+            _ => {}
+        }
+    }
+}
+
+/// Parse a PCD file (ASCII, binary or binary_compressed) into a [`ScanResult`].
+///
+/// `x`, `y` and `z` are required; the optional fields written by
+/// [`write_pcd`] are read when present and any other fields are ignored.
+/// `width`, `height` and the `VIEWPOINT` pose are preserved, so organized
+/// clouds round-trip.
+pub fn read_pcd(bytes: &[u8]) -> Result<ScanResult, SimLidarError> {
+    let (header, data_offset) = parse_header(bytes)?;
+    let n = header.points;
+    let data = &bytes[data_offset..];
+    let fields = &header.fields;
+    let column = |name: &str| fields.iter().position(|f| f.name == name);
+    let (x, y, z) = match (column("x"), column("y"), column("z")) {
+        (Some(x), Some(y), Some(z)) => (x, y, z),
+        _ => return Err(format_err("PCD file has no x/y/z fields")),
+    };
+    if fields.iter().any(|f| f.size == 0 || f.count == 0) {
+        return Err(format_err("PCD fields must have non-zero SIZE and COUNT"));
+    }
+
+    // values[f][i]: first element of field `f` for point `i`.
+    let mut values: Vec<Vec<f64>> = vec![Vec::new(); fields.len()];
+    match header.encoding {
+        PcdEncoding::Ascii => {
+            let text = std::str::from_utf8(data).map_err(|_| format_err("PCD ascii data is not valid UTF-8"))?;
+            let mut tokens = text.split_whitespace();
+            for _ in 0..n {
+                for (f, field) in fields.iter().enumerate() {
+                    for element in 0..field.count {
+                        let token = tokens.next().ok_or_else(|| format_err("PCD ascii data ends early"))?;
+                        if element == 0 {
+                            let v = token
+                                .parse::<f64>()
+                                .map_err(|_| format_err(format!("bad PCD value {token:?}")))?;
+                            values[f].push(v);
+                        }
+                    }
+                }
+            }
+        }
+        PcdEncoding::Binary => {
+            let point_size: usize = fields.iter().map(Field::stride).sum();
+            if data.len() < point_size.saturating_mul(n) {
+                return Err(format_err("PCD binary data ends early"));
+            }
+            for record in data.chunks_exact(point_size).take(n) {
+                let mut offset = 0;
+                for (f, field) in fields.iter().enumerate() {
+                    values[f].push(field.decode(&record[offset..])?);
+                    offset += field.stride();
+                }
+            }
+        }
+        PcdEncoding::BinaryCompressed => {
+            if data.len() < 8 {
+                return Err(format_err("PCD compressed data ends early"));
+            }
+            let compressed_len = u32::from_le_bytes(data[0..4].try_into().unwrap()) as usize;
+            let raw_len = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+            let compressed = compressed_len
+                .checked_add(8)
+                .and_then(|end| data.get(8..end))
+                .ok_or_else(|| format_err("PCD compressed data ends early"))?;
+            let point_size: usize = fields.iter().map(Field::stride).sum();
+            if point_size.checked_mul(n) != Some(raw_len) {
+                return Err(format_err(format!("PCD uncompressed size {raw_len} does not match {n} points")));
+            }
+            let raw = lzf::decompress(compressed, raw_len)?;
+            let mut offset = 0;
+            for (f, field) in fields.iter().enumerate() {
+                for i in 0..n {
+                    values[f].push(field.decode(&raw[offset + i * field.stride()..])?);
+                }
+                offset += field.stride() * n;
+            }
+        }
+    }
+
+    let mut hits = Vec::with_capacity(n * 3);
+    for ((&px, &py), &pz) in values[x].iter().zip(&values[y]).zip(&values[z]) {
+        hits.extend_from_slice(&[px as f32, py as f32, pz as f32]);
+    }
+    let floats = |name: &str| column(name).map_or_else(Vec::new, |f| values[f].iter().map(|&v| v as f32).collect());
+    let ints = |name: &str| column(name).map_or_else(Vec::new, |f| values[f].iter().map(|&v| v as u32).collect());
+    Ok(ScanResult {
+        hits,
+        hit_count: n,
+        velocities: floats("velocity"),
+        rings: column("ring").map_or_else(Vec::new, |f| values[f].iter().map(|&v| v as u16).collect()),
+        width: header.width as u32,
+        height: header.height as u32,
+        intensities: floats("intensity"),
+        times: floats("time"),
+        labels: ints("label"),
+        object_ids: ints("object_id"),
+        sensor_position: header.viewpoint.0,
+        sensor_rotation: header.viewpoint.1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_scan() -> ScanResult {
+        // A 3 × 2 organized scan with one miss and Doppler output.
+        ScanResult {
+            hits: vec![
+                1.0, 2.0, 3.0,
+                -4.5, 0.25, 7.0,
+                f32::NAN, f32::NAN, f32::NAN,
+                0.1, 0.2, 0.3,
+                10.0, -10.0, 0.0,
+                5.5, 6.5, 7.5,
+            ],
+            hit_count: 6,
+            velocities: vec![0.5, -1.0, f32::NAN, 0.0, 2.0, 3.0],
+            rings: vec![0, 0, 0, 1, 1, 1],
+            width: 3,
+            height: 2,
+            intensities: vec![0.9, 0.5, 0.0, 1.0, 0.25, 0.125],
+            times: vec![0.0, 0.025, 0.05, 0.0, 0.025, 0.05],
+            labels: vec![40, 40, 0, 10, 10, 50],
+            object_ids: vec![0, 0, u32::MAX, 1, 1, 2],
+            sensor_position: Vec3::new(1.0, 2.0, 3.0),
+            sensor_rotation: Quat::from_rotation_y(0.5),
+        }
+    }
+
+    fn assert_same_floats(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x.is_nan() && y.is_nan()) || x == y, "{x} != {y}");
+        }
+    }
+
+    fn assert_round_trip(encoding: PcdEncoding) {
+        let scan = sample_scan();
+        let parsed = read_pcd(&write_pcd(&scan, encoding)).unwrap();
+        assert_eq!((parsed.width, parsed.height, parsed.hit_count), (3, 2, 6));
+        assert_same_floats(&parsed.hits, &scan.hits);
+        assert_same_floats(&parsed.velocities, &scan.velocities);
+        assert_eq!(parsed.rings, scan.rings);
+        assert_same_floats(&parsed.intensities, &scan.intensities);
+        assert_same_floats(&parsed.times, &scan.times);
+        assert_eq!(parsed.labels, scan.labels);
+        assert_eq!(parsed.object_ids, scan.object_ids);
+        assert_eq!(parsed.sensor_position, scan.sensor_position);
+        assert!(parsed.sensor_rotation.abs_diff_eq(scan.sensor_rotation, 1e-6));
+    }
+
+    #[test]
+    fn test_pcd_round_trip_ascii() {
+        assert_round_trip(PcdEncoding::Ascii);
+    }
+
+    #[test]
+    fn test_pcd_round_trip_binary() {
+        assert_round_trip(PcdEncoding::Binary);
+    }
+
+    #[test]
+    fn test_pcd_round_trip_binary_compressed() {
+        assert_round_trip(PcdEncoding::BinaryCompressed);
+    }
+
+    #[test]
+    fn test_pcd_header_lists_optional_fields() {
+        let bytes = write_pcd(&sample_scan(), PcdEncoding::Ascii);
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.contains("FIELDS x y z intensity ring time velocity label object_id\n"));
+        assert!(text.contains("SIZE 4 4 4 4 2 4 4 4 4\n"));
+        assert!(text.contains("TYPE F F F F U F F U U\n"));
+        assert!(text.contains("WIDTH 3\nHEIGHT 2\n"));
+        assert!(text.contains("VIEWPOINT 1 2 3 "));
+        assert!(text.contains("POINTS 6\nDATA ascii\n"));
+        assert!(text.contains("\nnan nan nan 0 0 0.05 nan 0 4294967295\n"));
+    }
+
+    #[test]
+    fn test_pcd_omits_absent_fields() {
+        let scan = ScanResult {
+            hits: vec![1.0, 2.0, 3.0],
+            hit_count: 1,
+            ..ScanResult::default()
+        };
+        let text = String::from_utf8(write_pcd(&scan, PcdEncoding::Ascii)).unwrap();
+        assert!(text.contains("FIELDS x y z\n"));
+        assert!(text.contains("WIDTH 1\nHEIGHT 1\n"));
+    }
+
+    #[test]
+    fn test_read_pcd_foreign_ascii_file() {
+        // PCL-style file with extra fields, a double-precision intensity and COUNT > 1.
+        let pcd = b"# .PCD v0.7\nVERSION 0.7\nFIELDS x y z intensity normal\nSIZE 4 4 4 8 4\n\
+                    TYPE F F F F F\nCOUNT 1 1 1 1 3\nWIDTH 2\nHEIGHT 1\nPOINTS 2\nDATA ascii\n\
+                    1 2 3 0.5 0 0 1\n4 5 6 0.7 0 1 0\n";
+        let scan = read_pcd(pcd).unwrap();
+        assert_eq!(scan.hits, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert!(scan.rings.is_empty() && scan.velocities.is_empty());
+    }
+
+    #[test]
+    fn test_read_pcd_rejects_malformed_input() {
+        assert!(matches!(read_pcd(b"VERSION 0.7\n"), Err(SimLidarError::Format(_))));
+        let no_xyz = b"FIELDS a\nSIZE 4\nTYPE F\nWIDTH 1\nDATA ascii\n1\n";
+        assert!(matches!(read_pcd(no_xyz), Err(SimLidarError::Format(_))));
+        let short = b"FIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nWIDTH 2\nDATA binary\n\x00\x00";
+        assert!(matches!(read_pcd(short), Err(SimLidarError::Format(_))));
+        assert!("zip".parse::<PcdEncoding>().is_err());
+    }
+
+    #[test]
+    fn test_read_pcd_rejects_oversized_headers() {
+        let huge = format!("FIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nWIDTH {}\nHEIGHT 2\nDATA ascii\n", usize::MAX);
+        assert!(matches!(read_pcd(huge.as_bytes()), Err(SimLidarError::Format(_))));
+
+        let header = b"FIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nWIDTH 1\nDATA binary_compressed\n";
+        for (compressed_len, raw_len) in [(u32::MAX, 12), (3, u32::MAX)] {
+            let mut bytes = header.to_vec();
+            bytes.extend_from_slice(&compressed_len.to_le_bytes());
+            bytes.extend_from_slice(&raw_len.to_le_bytes());
+            bytes.extend_from_slice(&[0x01, b'a', b'b']);
+            assert!(matches!(read_pcd(&bytes), Err(SimLidarError::Format(_))));
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::str::FromStr;
+
+use crate::error::SimLidarError;
+use crate::point_fields::{point_fields, ScalarKind};
+use crate::raycaster::ScanResult;
+
+/// Encoding of a PLY file body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyFormat {
+    /// One whitespace-separated line of text per element.
+    Ascii,
+    /// Packed little-endian records.
+    BinaryLittleEndian,
+}
+
+impl PlyFormat {
+    /// Name used on the PLY `format` line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        }
+    }
+}
+
+impl FromStr for PlyFormat {
+    type Err = SimLidarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(PlyFormat::Ascii),
+            "binary" | "binary_little_endian" => Ok(PlyFormat::BinaryLittleEndian),
+            _ => Err(SimLidarError::Format(format!("unknown PLY format {s:?}"))),
+        }
+    }
+}
+
+/// PLY property type name of a scalar kind.
+fn type_name(kind: ScalarKind) -> &'static str {
+    match kind {
+        ScalarKind::F32 => "float",
+        ScalarKind::U16 => "ushort",
+        ScalarKind::U32 => "uint",
+    }
+}
+
+/// Property names of the `sensor_pose` element.
+const POSE_PROPERTIES: [&str; 7] = ["x", "y", "z", "qx", "qy", "qz", "qw"];
+
+/// Serialize a scan as a PLY file.
+///
+/// * `format`       – ASCII or binary little-endian body.
+/// * `include_pose` – Also write a one-entry `sensor_pose` element holding
+///   the world-space sensor position and orientation quaternion, so the
+///   cloud can be re-registered later.
+///
+/// Every per-point attribute the scan produced becomes a `vertex` property
+/// after `x y z`: `intensity`, `ring`, `time`, `velocity` (Doppler scans),
+/// `label` and `object_id`.
+pub fn write_ply(result: &ScanResult, format: PlyFormat, include_pose: bool) -> Vec<u8> {
+    let fields = point_fields(result);
+    let n = result.hit_count;
+
+    let mut header = format!("ply\nformat {} 1.0\ncomment generated by sim-lidar-rs\n", format.as_str());
+    header.push_str(&format!("element vertex {n}\n"));
+    for field in &fields {
+        header.push_str(&format!("property {} {}\n", type_name(field.kind()), field.name()));
+    }
+    if include_pose {
+        header.push_str("element sensor_pose 1\n");
+        for name in POSE_PROPERTIES {
+            header.push_str(&format!("property float {name}\n"));
+        }
+    }
+    header.push_str("end_header\n");
+    let mut out = header.into_bytes();
+
+    let (p, q) = (result.sensor_position, result.sensor_rotation);
+    let pose = [p.x, p.y, p.z, q.x, q.y, q.z, q.w];
+    match format {
+        PlyFormat::Ascii => {
+            let mut text = String::new();
+            for i in 0..n {
+                for (k, field) in fields.iter().enumerate() {
+                    if k > 0 {
+                        text.push(' ');
+                    }
+                    field.value(result, i).write_ascii(&mut text);
+                }
+                text.push('\n');
+            }
+            if include_pose {
+                text.push_str(&pose.map(|v| v.to_string()).join(" "));
+                text.push('\n');
+            }
+            out.extend_from_slice(text.as_bytes());
+        }
+        PlyFormat::BinaryLittleEndian => {
+            for i in 0..n {
+                for field in &fields {
+                    field.value(result, i).write_le(&mut out);
+                }
+            }
+            if include_pose {
+                for v in pose {
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec3};
+
+    fn sample_scan() -> ScanResult {
+        ScanResult {
+            hits: vec![1.0, 2.0, 3.0, -4.0, 0.5, 8.0],
+            hit_count: 2,
+            rings: vec![3, 7],
+            width: 2,
+            height: 1,
+            intensities: vec![0.75, 0.25],
+            times: vec![0.0, 0.05],
+            labels: vec![40, 10],
+            object_ids: vec![0, 4],
+            sensor_position: Vec3::new(10.0, 1.5, -2.0),
+            sensor_rotation: Quat::IDENTITY,
+            ..ScanResult::default()
+        }
+    }
+
+    /// Split a PLY file into its header text and body bytes.
+    fn split(bytes: Vec<u8>) -> (String, Vec<u8>) {
+        let end = bytes.windows(11).position(|w| w == b"end_header\n").unwrap() + 11;
+        (String::from_utf8(bytes[..end].to_vec()).unwrap(), bytes[end..].to_vec())
+    }
+
+    #[test]
+    fn test_ply_ascii_lists_every_attribute() {
+        let (header, body) = split(write_ply(&sample_scan(), PlyFormat::Ascii, false));
+        assert!(header.starts_with("ply\nformat ascii 1.0\n"));
+        assert!(header.contains(
+            "element vertex 2\nproperty float x\nproperty float y\nproperty float z\n\
+             property float intensity\nproperty ushort ring\nproperty float time\n\
+             property uint label\nproperty uint object_id\nend_header\n"
+        ));
+        assert!(!header.contains("sensor_pose"));
+        assert_eq!(std::str::from_utf8(&body).unwrap(), "1 2 3 0.75 3 0 40 0\n-4 0.5 8 0.25 7 0.05 10 4\n");
+    }
+
+    #[test]
+    fn test_ply_binary_little_endian_records() {
+        let (header, body) = split(write_ply(&sample_scan(), PlyFormat::BinaryLittleEndian, false));
+        assert!(header.contains("format binary_little_endian 1.0\n"));
+        // x y z intensity (4 × f32), ring (u16), time (f32), label + object_id (2 × u32)
+        let record = 4 * 4 + 2 + 4 + 2 * 4;
+        assert_eq!(body.len(), 2 * record);
+        let second = &body[record..];
+        assert_eq!(f32::from_le_bytes(second[0..4].try_into().unwrap()), -4.0);
+        assert_eq!(u16::from_le_bytes(second[16..18].try_into().unwrap()), 7);
+        assert_eq!(u32::from_le_bytes(second[26..30].try_into().unwrap()), 4);
+    }
+
+    #[test]
+    fn test_ply_includes_sensor_pose_element() {
+        let scan = sample_scan();
+        let (header, body) = split(write_ply(&scan, PlyFormat::Ascii, true));
+        assert!(header.contains("element sensor_pose 1\nproperty float x\n"));
+        assert!(header.ends_with("property float qw\nend_header\n"));
+        assert!(std::str::from_utf8(&body).unwrap().ends_with("\n10 1.5 -2 0 0 0 1\n"));
+
+        let (_, body) = split(write_ply(&scan, PlyFormat::BinaryLittleEndian, true));
+        let pose = &body[body.len() - 7 * 4..];
+        assert_eq!(f32::from_le_bytes(pose[0..4].try_into().unwrap()), 10.0);
+        assert_eq!(f32::from_le_bytes(pose[24..28].try_into().unwrap()), 1.0);
+    }
+
+    #[test]
+    fn test_ply_format_from_str() {
+        assert_eq!("binary".parse::<PlyFormat>().unwrap(), PlyFormat::BinaryLittleEndian);
+        assert_eq!("ascii".parse::<PlyFormat>().unwrap(), PlyFormat::Ascii);
+        assert!("binary_big_endian".parse::<PlyFormat>().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+// Per-point attributes of a `ScanResult`, shared by the point cloud writers.
+
+use glam::{Mat3, Quat, Vec3};
+
+use crate::raycaster::ScanResult;
+
+/// Rotation from the simulator's Y-up sensor frame (x forward, y up, z right;
+/// see [`SensorConfig::generate_local_ray_directions`]) to the Z-up sensor
+/// frame used by ROS (REP 103) and KITTI: x forward, y left, z up.
+///
+/// [`SensorConfig::generate_local_ray_directions`]: crate::SensorConfig::generate_local_ray_directions
+pub(crate) const SIM_TO_Z_UP: Mat3 = Mat3::from_cols(Vec3::X, Vec3::Z, Vec3::NEG_Y);
+
+/// Point `i` of `result` in the Z-up frame of the sensor that took the scan.
+pub(crate) fn sensor_frame_point(result: &ScanResult, i: usize) -> Vec3 {
+    let hit = Vec3::from_slice(&result.hits[i * 3..i * 3 + 3]);
+    SIM_TO_Z_UP * (result.sensor_rotation.inverse() * (hit - result.sensor_position))
+}
+
+/// Pose of the sensor that took `result`, with both the world and the sensor
+/// frame rotated to Z-up, so it places [`sensor_frame_point`]s in a Z-up world.
+pub(crate) fn z_up_sensor_pose(result: &ScanResult) -> (Vec3, Quat) {
+    let to_z_up = Quat::from_mat3(&SIM_TO_Z_UP);
+    (SIM_TO_Z_UP * result.sensor_position, (to_z_up * result.sensor_rotation * to_z_up.inverse()).normalize())
+}
+
+/// Storage type of a per-point attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ScalarKind {
+    F32,
+    U16,
+    U32,
+}
+
+impl ScalarKind {
+    /// Size of one value in bytes.
+    pub(crate) fn size(self) -> usize {
+        match self {
+            ScalarKind::F32 | ScalarKind::U32 => 4,
+            ScalarKind::U16 => 2,
+        }
+    }
+}
+
+/// A single attribute value of one point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Scalar {
+    F32(f32),
+    U16(u16),
+    U32(u32),
+}
+
+impl Scalar {
+    pub(crate) fn write_le(self, out: &mut Vec<u8>) {
+        match self {
+            Scalar::F32(v) => out.extend_from_slice(&v.to_le_bytes()),
+            Scalar::U16(v) => out.extend_from_slice(&v.to_le_bytes()),
+            Scalar::U32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        }
+    }
+
+    /// Overwrite `out[offset..]` with the little-endian value.
+    pub(crate) fn write_le_at(self, out: &mut [u8], offset: usize) {
+        match self {
+            Scalar::F32(v) => out[offset..offset + 4].copy_from_slice(&v.to_le_bytes()),
+            Scalar::U16(v) => out[offset..offset + 2].copy_from_slice(&v.to_le_bytes()),
+            Scalar::U32(v) => out[offset..offset + 4].copy_from_slice(&v.to_le_bytes()),
+        }
+    }
+
+    /// Append the value as text.  NaN is written as lower-case `nan`, which
+    /// PCL and the common PLY readers parse.
+    pub(crate) fn write_ascii(self, out: &mut String) {
+        match self {
+            Scalar::F32(v) if v.is_nan() => out.push_str("nan"),
+            Scalar::F32(v) => out.push_str(&v.to_string()),
+            Scalar::U16(v) => out.push_str(&v.to_string()),
+            Scalar::U32(v) => out.push_str(&v.to_string()),
+        }
+    }
+}
+
+/// Per-point attribute of a [`ScanResult`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PointField {
+    X,
+    Y,
+    Z,
+    Intensity,
+    Ring,
+    Time,
+    Velocity,
+    Label,
+    ObjectId,
+}
+
+impl PointField {
+    /// Attribute name used in file headers.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            PointField::X => "x",
+            PointField::Y => "y",
+            PointField::Z => "z",
+            PointField::Intensity => "intensity",
+            PointField::Ring => "ring",
+            PointField::Time => "time",
+            PointField::Velocity => "velocity",
+            PointField::Label => "label",
+            PointField::ObjectId => "object_id",
+        }
+    }
+
+    pub(crate) fn kind(self) -> ScalarKind {
+        match self {
+            PointField::Ring => ScalarKind::U16,
+            PointField::Label | PointField::ObjectId => ScalarKind::U32,
+            _ => ScalarKind::F32,
+        }
+    }
+
+    /// Value of this attribute for point `i` of `result`.
+    pub(crate) fn value(self, result: &ScanResult, i: usize) -> Scalar {
+        match self {
+            PointField::X => Scalar::F32(result.hits[i * 3]),
+            PointField::Y => Scalar::F32(result.hits[i * 3 + 1]),
+            PointField::Z => Scalar::F32(result.hits[i * 3 + 2]),
+            PointField::Intensity => Scalar::F32(result.intensities[i]),
+            PointField::Ring => Scalar::U16(result.rings[i]),
+            PointField::Time => Scalar::F32(result.times[i]),
+            PointField::Velocity => Scalar::F32(result.velocities[i]),
+            PointField::Label => Scalar::U32(result.labels[i]),
+            PointField::ObjectId => Scalar::U32(result.object_ids[i]),
+        }
+    }
+}
+
+/// Attributes present in `result`: `x y z`, followed by every optional
+/// attribute that holds a value for every point, in a fixed order.
+pub(crate) fn point_fields(result: &ScanResult) -> Vec<PointField> {
+    let n = result.hit_count;
+    let optional = [
+        (PointField::Intensity, result.intensities.len()),
+        (PointField::Ring, result.rings.len()),
+        (PointField::Time, result.times.len()),
+        (PointField::Velocity, result.velocities.len()),
+        (PointField::Label, result.labels.len()),
+        (PointField::ObjectId, result.object_ids.len()),
+    ];
+    let mut fields = vec![PointField::X, PointField::Y, PointField::Z];
+    fields.extend(optional.into_iter().filter(|&(_, len)| len == n).map(|(field, _)| field));
+    fields
+}
@@ -0,0 +1,59 @@
+use std::ops::Mul;
+
+use glam::{Mat4, Quat, Vec3};
+
+/// World-space position and orientation of a sensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub position: Vec3,
+    /// Unit quaternion; normalised by [`Pose::new`].
+    pub rotation: Quat,
+}
+
+impl Pose {
+    /// The origin, with the sensor's default orientation.
+    pub const IDENTITY: Pose = Pose {
+        position: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+    };
+
+    /// Pose at `position` with `rotation` (normalised).
+    pub fn new(position: Vec3, rotation: Quat) -> Self {
+        Self {
+            position,
+            rotation: rotation.normalize(),
+        }
+    }
+
+    /// Pose at `position` with the default orientation.
+    pub fn from_position(position: Vec3) -> Self {
+        Self {
+            position,
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    /// Sensor-to-world transform.
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position)
+    }
+}
+
+/// Chain transforms: `vehicle * mount` is the world pose of a sensor
+/// mounted at `mount` in the frame of `vehicle`.
+impl Mul for Pose {
+    type Output = Pose;
+
+    fn mul(self, rhs: Pose) -> Pose {
+        Pose {
+            position: self.position + self.rotation * rhs.position,
+            rotation: self.rotation * rhs.rotation,
+        }
+    }
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
@@ -0,0 +1,346 @@
+use glam::{Quat, Vec3};
+
+use crate::bvh::Aabb;
+use crate::error::SimLidarError;
+
+/// Hits closer than this are ignored, so a ray leaving a surface does not
+/// hit it again.
+const EPSILON: f32 = 1e-6;
+
+fn invalid(what: &str) -> SimLidarError {
+    SimLidarError::Format(format!("primitive: {what}"))
+}
+
+/// Geometry of an analytic [`Primitive`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    /// Box with the given half extents along its local axes, rotated by
+    /// `rotation` about its center.
+    Cuboid {
+        center: Vec3,
+        half_extents: Vec3,
+        rotation: Quat,
+    },
+    /// Capped cylinder around its local y axis, rotated by `rotation` about
+    /// its center.
+    Cylinder {
+        center: Vec3,
+        radius: f32,
+        half_height: f32,
+        rotation: Quat,
+    },
+    /// Infinite plane through `point`, hit from either side.
+    Plane {
+        point: Vec3,
+        normal: Vec3,
+    },
+}
+
+/// Analytic shape raycast exactly, without tessellation.
+///
+/// Primitives sit in the [`Bvh`](crate::Bvh) next to the triangles (see
+/// [`Bvh::add_primitive`](crate::Bvh::add_primitive)) and report exact
+/// surface normals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Primitive {
+    pub shape: Shape,
+    /// Object id reported for hits on the primitive.
+    pub object_id: u32,
+}
+
+impl Primitive {
+    /// Sphere of `radius` metres around `center`.
+    pub fn sphere(center: Vec3, radius: f32) -> Result<Self, SimLidarError> {
+        if !(center.is_finite() && radius > 0.0 && radius.is_finite()) {
+            return Err(invalid("sphere needs a finite center and a positive radius"));
+        }
+        Ok(Self::new(Shape::Sphere { center, radius }))
+    }
+
+    /// Box of `half_extents` metres around `center`, rotated by `rotation`.
+    pub fn cuboid(center: Vec3, half_extents: Vec3, rotation: Quat) -> Result<Self, SimLidarError> {
+        if !(center.is_finite() && half_extents.is_finite() && half_extents.min_element() > 0.0) {
+            return Err(invalid("box needs a finite center and positive half extents"));
+        }
+        let rotation = normalized(rotation)?;
+        Ok(Self::new(Shape::Cuboid {
+            center,
+            half_extents,
+            rotation,
+        }))
+    }
+
+    /// Capped cylinder around `center`, upright (along +y) before `rotation`.
+    pub fn cylinder(center: Vec3, radius: f32, half_height: f32, rotation: Quat) -> Result<Self, SimLidarError> {
+        let sizes = Vec3::new(radius, half_height, 1.0);
+        if !(center.is_finite() && sizes.is_finite() && sizes.min_element() > 0.0) {
+            return Err(invalid("cylinder needs a finite center, a positive radius and a positive height"));
+        }
+        let rotation = normalized(rotation)?;
+        Ok(Self::new(Shape::Cylinder {
+            center,
+            radius,
+            half_height,
+            rotation,
+        }))
+    }
+
+    /// Infinite plane through `point` with the given normal, e.g. a ground
+    /// plane `Primitive::plane(Vec3::ZERO, Vec3::Y)`.
+    pub fn plane(point: Vec3, normal: Vec3) -> Result<Self, SimLidarError> {
+        let normal = normal.try_normalize().filter(|_| point.is_finite());
+        let normal = normal.ok_or_else(|| invalid("plane needs a finite point and a non-zero normal"))?;
+        Ok(Self::new(Shape::Plane { point, normal }))
+    }
+
+    /// Set the object id reported for hits.
+    pub fn with_object_id(mut self, object_id: u32) -> Self {
+        self.object_id = object_id;
+        self
+    }
+
+    fn new(shape: Shape) -> Self {
+        Self { shape, object_id: 0 }
+    }
+
+    /// World-space bounding box, or `None` for unbounded shapes (planes).
+    pub fn aabb(&self) -> Option<Aabb> {
+        match self.shape {
+            Shape::Sphere { center, radius } => Some(Aabb::new(center - radius, center + radius)),
+            Shape::Cuboid {
+                center,
+                half_extents,
+                rotation,
+            } => {
+                let extent = rotated_extent(rotation, half_extents);
+                Some(Aabb::new(center - extent, center + extent))
+            }
+            Shape::Cylinder {
+                center,
+                radius,
+                half_height,
+                rotation,
+            } => {
+                // Each cap disc extends `radius · sqrt(1 - axis_i²)` along axis i.
+                let axis = rotation * Vec3::Y;
+                let disc = radius * (Vec3::ONE - axis * axis).max(Vec3::ZERO).powf(0.5);
+                let extent = axis.abs() * half_height + disc;
+                Some(Aabb::new(center - extent, center + extent))
+            }
+            Shape::Plane { .. } => None,
+        }
+    }
+
+    /// Closest intersection within `t_max`: the distance and the unit
+    /// outward normal at the hit point.
+    pub fn ray_intersect(&self, origin: Vec3, direction: Vec3, t_max: f32) -> Option<(f32, Vec3)> {
+        let (t, normal) = match self.shape {
+            Shape::Sphere { center, radius } => {
+                let oc = origin - center;
+                let a = direction.length_squared();
+                let half_b = oc.dot(direction);
+                let c = oc.length_squared() - radius * radius;
+                let t = nearest_root(a, half_b, c)?;
+                (t, (oc + direction * t) / radius)
+            }
+            Shape::Cuboid {
+                center,
+                half_extents,
+                rotation,
+            } => {
+                let inverse = rotation.inverse();
+                let o = inverse * (origin - center);
+                let d = inverse * direction;
+                let (t, local) = ray_box(o, d, half_extents)?;
+                (t, rotation * local)
+            }
+            Shape::Cylinder {
+                center,
+                radius,
+                half_height,
+                rotation,
+            } => {
+                let inverse = rotation.inverse();
+                let o = inverse * (origin - center);
+                let d = inverse * direction;
+                let (t, local) = ray_cylinder(o, d, radius, half_height)?;
+                (t, rotation * local)
+            }
+            Shape::Plane { point, normal } => {
+                let denom = normal.dot(direction);
+                if denom.abs() < 1e-9 {
+                    return None;
+                }
+                ((point - origin).dot(normal) / denom, normal)
+            }
+        };
+        (t > EPSILON && t <= t_max).then_some((t, normal))
+    }
+}
+
+fn normalized(rotation: Quat) -> Result<Quat, SimLidarError> {
+    let length = rotation.length();
+    if !(length.is_finite() && length > 0.0) {
+        return Err(invalid("rotation must be a finite, non-zero quaternion"));
+    }
+    Ok(rotation / length)
+}
+
+/// Half extents of the world-space box enclosing a rotated box.
+fn rotated_extent(rotation: Quat, half_extents: Vec3) -> Vec3 {
+    let m = glam::Mat3::from_quat(rotation);
+    m.x_axis.abs() * half_extents.x + m.y_axis.abs() * half_extents.y + m.z_axis.abs() * half_extents.z
+}
+
+/// Smallest root of `a·t² + 2·half_b·t + c` beyond [`EPSILON`].
+fn nearest_root(a: f32, half_b: f32, c: f32) -> Option<f32> {
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 || a == 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    let near = (-half_b - sqrt) / a;
+    if near > EPSILON {
+        return Some(near);
+    }
+    Some((-half_b + sqrt) / a)
+}
+
+/// Ray against the axis-aligned box `[-half, half]`: distance and face normal.
+fn ray_box(origin: Vec3, direction: Vec3, half: Vec3) -> Option<(f32, Vec3)> {
+    let inv = Vec3::ONE / direction;
+    let t1 = (-half - origin) * inv;
+    let t2 = (half - origin) * inv;
+    let near = t1.min(t2);
+    let far = t1.max(t2);
+    let t_near = near.max_element();
+    let t_far = far.min_element();
+    if t_near > t_far || t_far <= EPSILON {
+        return None;
+    }
+    // Entering face, or the exit face when starting inside.
+    let (t, bounds) = if t_near > EPSILON { (t_near, near) } else { (t_far, far) };
+    let axis = (0..3).find(|&i| bounds[i] == t).unwrap_or(0);
+    let mut normal = Vec3::ZERO;
+    normal[axis] = (origin[axis] + direction[axis] * t).signum();
+    Some((t, normal))
+}
+
+/// Ray against the capped cylinder of `radius` around the y axis, spanning
+/// `[-half_height, half_height]`: distance and outward normal.
+fn ray_cylinder(origin: Vec3, direction: Vec3, radius: f32, half_height: f32) -> Option<(f32, Vec3)> {
+    let mut closest: Option<(f32, Vec3)> = None;
+    let mut consider = |t: f32, normal: Vec3| {
+        if t > EPSILON && closest.is_none_or(|(best, _)| t < best) {
+            closest = Some((t, normal));
+        }
+    };
+    // Side: x² + z² = r².
+    let a = direction.x * direction.x + direction.z * direction.z;
+    let half_b = origin.x * direction.x + origin.z * direction.z;
+    let c = origin.x * origin.x + origin.z * origin.z - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if a > 0.0 && discriminant >= 0.0 {
+        let sqrt = discriminant.sqrt();
+        for t in [(-half_b - sqrt) / a, (-half_b + sqrt) / a] {
+            let p = origin + direction * t;
+            if p.y.abs() <= half_height {
+                consider(t, Vec3::new(p.x, 0.0, p.z) / radius);
+            }
+        }
+    }
+    // Caps: y = ±h inside the disc.
+    if direction.y != 0.0 {
+        for cap in [-half_height, half_height] {
+            let t = (cap - origin.y) / direction.y;
+            let p = origin + direction * t;
+            if p.x * p.x + p.z * p.z <= radius * radius {
+                consider(t, Vec3::new(0.0, cap.signum(), 0.0));
+            }
+        }
+    }
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-5
+    }
+
+    #[test]
+    fn test_primitive_sphere_exact_hit_and_normal() {
+        let sphere = Primitive::sphere(Vec3::new(0.0, 0.0, 5.0), 1.0).unwrap();
+        let (t, normal) = sphere.ray_intersect(Vec3::ZERO, Vec3::Z, 100.0).unwrap();
+        assert!((t - 4.0).abs() < 1e-5);
+        assert!(close(normal, Vec3::NEG_Z));
+        // Off-centre: the normal points from the centre to the hit point.
+        let dir = Vec3::new(0.1, 0.0, 1.0).normalize();
+        let (t, normal) = sphere.ray_intersect(Vec3::ZERO, dir, 100.0).unwrap();
+        assert!(close(normal, dir * t - Vec3::new(0.0, 0.0, 5.0)));
+        assert!(sphere.ray_intersect(Vec3::ZERO, Vec3::Z, 3.9).is_none());
+        assert!(sphere.ray_intersect(Vec3::ZERO, Vec3::X, 100.0).is_none());
+        // From inside the ray hits the far side.
+        let (t, _) = sphere.ray_intersect(Vec3::new(0.0, 0.0, 5.0), Vec3::Y, 100.0).unwrap();
+        assert!((t - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_primitive_rotated_cuboid() {
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        let cuboid = Primitive::cuboid(Vec3::new(10.0, 0.0, 0.0), Vec3::splat(1.0), rotation).unwrap();
+        // Edge-on: the corner of the rotated box faces the ray at √2 from the centre.
+        let (t, _) = cuboid.ray_intersect(Vec3::new(0.0, 0.0, 0.0), Vec3::X, 100.0).unwrap();
+        assert!((t - (10.0 - 2f32.sqrt())).abs() < 1e-4, "{t}");
+        let (t, normal) = cuboid.ray_intersect(Vec3::new(10.0, 5.0, 0.0), Vec3::NEG_Y, 100.0).unwrap();
+        assert!((t - 4.0).abs() < 1e-5);
+        assert!(close(normal, Vec3::Y));
+        let aabb = cuboid.aabb().unwrap();
+        assert!(close(aabb.max, Vec3::new(10.0 + 2f32.sqrt(), 1.0, 2f32.sqrt())));
+    }
+
+    #[test]
+    fn test_primitive_cylinder_side_and_caps() {
+        // Horizontal pole along x: rotate the y axis onto x.
+        let rotation = Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2);
+        let pole = Primitive::cylinder(Vec3::ZERO, 0.5, 2.0, rotation).unwrap();
+        let (t, normal) = pole.ray_intersect(Vec3::new(1.0, 5.0, 0.0), Vec3::NEG_Y, 100.0).unwrap();
+        assert!((t - 4.5).abs() < 1e-5);
+        assert!(close(normal, Vec3::Y));
+        let (t, normal) = pole.ray_intersect(Vec3::new(5.0, 0.1, 0.0), Vec3::NEG_X, 100.0).unwrap();
+        assert!((t - 3.0).abs() < 1e-5);
+        assert!(close(normal, Vec3::X));
+        assert!(pole.ray_intersect(Vec3::new(2.5, 5.0, 0.0), Vec3::NEG_Y, 100.0).is_none());
+        let aabb = pole.aabb().unwrap();
+        assert!(close(aabb.min, Vec3::new(-2.0, -0.5, -0.5)), "{aabb:?}");
+    }
+
+    #[test]
+    fn test_primitive_plane_is_unbounded() {
+        let ground = Primitive::plane(Vec3::new(0.0, -1.5, 0.0), Vec3::new(0.0, 2.0, 0.0)).unwrap().with_object_id(3);
+        assert!(ground.aabb().is_none());
+        assert_eq!(ground.object_id, 3);
+        let dir = Vec3::new(1000.0, -1.0, 0.0).normalize();
+        let (t, normal) = ground.ray_intersect(Vec3::ZERO, dir, f32::INFINITY).unwrap();
+        assert!((dir * t).y + 1.5 < 1e-3);
+        assert!(close(normal, Vec3::Y));
+        assert!(ground.ray_intersect(Vec3::ZERO, Vec3::X, 100.0).is_none());
+        // Hit from below too.
+        assert!(ground.ray_intersect(Vec3::new(0.0, -5.0, 0.0), Vec3::Y, 100.0).is_some());
+    }
+
+    #[test]
+    fn test_primitive_rejects_degenerate_shapes() {
+        assert!(Primitive::sphere(Vec3::ZERO, 0.0).is_err());
+        assert!(Primitive::cuboid(Vec3::ZERO, Vec3::new(1.0, 0.0, 1.0), Quat::IDENTITY).is_err());
+        assert!(Primitive::cylinder(Vec3::ZERO, 1.0, f32::NAN, Quat::IDENTITY).is_err());
+        assert!(Primitive::cylinder(Vec3::ZERO, 1.0, 1.0, Quat::from_xyzw(0.0, 0.0, 0.0, 0.0)).is_err());
+        assert!(Primitive::plane(Vec3::ZERO, Vec3::ZERO).is_err());
+    }
+}
@@ -1,18 +1,138 @@
 use glam::{Quat, Vec3};
-use rand::SeedableRng;
-use rand::rngs::StdRng;
+use rand::RngCore;
 use rand_distr::{Distribution, Normal};
 
-use crate::bvh::Bvh;
+use crate::bvh::{Bvh, Intersection, PACKET_WIDTH};
+use crate::error::{ConfigIssue, SimLidarError};
+use crate::pose::Pose;
 use crate::sensor::SensorConfig;
 
 /// Output of a single scan.
+///
+/// A `ScanResult` can be reused across scans with [`scan_into`], which clears
+/// and refills its buffers without releasing their capacity.
+#[derive(Clone, Debug, Default)]
 pub struct ScanResult {
     /// Flat `[x, y, z, x, y, z, ...]` buffer of hit world-space coordinates.
-    /// Only valid hits (within min/max range) are included.
+    /// Only valid hits (within min/max range) are included, unless
+    /// [`SensorConfig::organized`] is set, in which case every ray produces a
+    /// point and misses are `NaN`.
     pub hits: Vec<f32>,
-    /// Number of valid hits.
+    /// Number of points in `hits` (valid hits, plus `NaN` misses in organized
+    /// mode).
     pub hit_count: usize,
+    /// Radial velocity of each hit in m/s, parallel to `hits`.  Positive values
+    /// mean the surface is moving away from the sensor.  Empty unless
+    /// [`SensorConfig::doppler`] is enabled.
+    pub velocities: Vec<f32>,
+    /// Laser channel (ring) of each point, parallel to `hits`.  Ring `0` is
+    /// the lowest beam (`vertical_fov_lower`).
+    pub rings: Vec<u16>,
+    /// Points per row: `horizontal_resolution` for organized scans, otherwise
+    /// `hit_count`.
+    pub width: u32,
+    /// Number of rows: `vertical_channels` for organized scans, otherwise 1.
+    pub height: u32,
+    /// Return intensity of each point in `[0, 1]`, parallel to `hits`:
+    /// the cosine of the angle between the ray and the surface normal
+    /// (Lambertian reflectance).  `0` for misses.
+    pub intensities: Vec<f32>,
+    /// Time of each point in seconds since the start of the scan, parallel
+    /// to `hits`, derived from the azimuth column and
+    /// [`SensorConfig::scan_frequency_hz`].
+    pub times: Vec<f32>,
+    /// Semantic label of each point, parallel to `hits`, taken from
+    /// [`Bvh::object_labels`].  `0` for unlabeled objects and misses.
+    pub labels: Vec<u32>,
+    /// Object id of the surface hit by each point, parallel to `hits`.
+    /// `u32::MAX` for misses.
+    pub object_ids: Vec<u32>,
+    /// World-space sensor position the scan was taken from.
+    pub sensor_position: Vec3,
+    /// World-space sensor orientation the scan was taken with.
+    pub sensor_rotation: Quat,
+}
+
+/// Linear velocities of the sensor and of the scene objects for one scan.
+///
+/// Only used when [`SensorConfig::doppler`] is enabled.  The default value
+/// describes a static sensor in a static scene.
+#[derive(Clone, Debug, Default)]
+pub struct Motion {
+    /// World-space velocity of the sensor in m/s.
+    pub sensor_velocity: Vec3,
+    /// World-space velocity of each object in m/s, indexed by the object ids
+    /// stored in [`Bvh::object_ids`].  Objects without an entry are static.
+    pub object_velocities: Vec<Vec3>,
+}
+
+impl ScanResult {
+    /// Create an empty result whose buffers can hold `total_rays` hits without
+    /// reallocating.
+    pub fn with_capacity(total_rays: usize) -> Self {
+        Self {
+            hits: Vec::with_capacity(total_rays * 3),
+            velocities: Vec::with_capacity(total_rays),
+            rings: Vec::with_capacity(total_rays),
+            intensities: Vec::with_capacity(total_rays),
+            times: Vec::with_capacity(total_rays),
+            labels: Vec::with_capacity(total_rays),
+            object_ids: Vec::with_capacity(total_rays),
+            ..Self::default()
+        }
+    }
+
+    /// Remove all hits, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.hits.clear();
+        self.hit_count = 0;
+        self.velocities.clear();
+        self.rings.clear();
+        self.width = 0;
+        self.height = 0;
+        self.intensities.clear();
+        self.times.clear();
+        self.labels.clear();
+        self.object_ids.clear();
+        self.sensor_position = Vec3::ZERO;
+        self.sensor_rotation = Quat::IDENTITY;
+    }
+
+    /// The pose the scan was taken from.
+    pub fn pose(&self) -> Pose {
+        Pose {
+            position: self.sensor_position,
+            rotation: self.sensor_rotation,
+        }
+    }
+
+    /// World-space point of each entry in `hits` (`NaN` for organized misses).
+    pub fn points(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.hits.chunks_exact(3).map(Vec3::from_slice)
+    }
+
+    /// Reserve room for `total_rays` more points in every per-point buffer.
+    pub(crate) fn reserve(&mut self, total_rays: usize, doppler: bool) {
+        self.hits.reserve(total_rays * 3);
+        self.rings.reserve(total_rays);
+        self.intensities.reserve(total_rays);
+        self.times.reserve(total_rays);
+        self.labels.reserve(total_rays);
+        self.object_ids.reserve(total_rays);
+        if doppler {
+            self.velocities.reserve(total_rays);
+        }
+    }
+}
+
+impl Motion {
+    /// Velocity of the object with the given id (zero if unknown).
+    fn object_velocity(&self, object_id: u32) -> Vec3 {
+        self.object_velocities
+            .get(object_id as usize)
+            .copied()
+            .unwrap_or(Vec3::ZERO)
+    }
 }
 
 /// Execute a single LiDAR scan using a pre-built BVH.
@@ -21,47 +141,268 @@ pub struct ScanResult {
 /// * `config`   – Sensor parameters.
 /// * `position` – World-space sensor origin.
 /// * `rotation` – Sensor orientation as a unit quaternion.
-pub fn scan(bvh: &Bvh, config: &SensorConfig, position: Vec3, rotation: Quat) -> ScanResult {
-    let directions = config.generate_ray_directions(rotation);
-    let total = directions.len();
-    let mut hits: Vec<f32> = Vec::with_capacity(total * 3);
-    let mut hit_count = 0usize;
-
-    // Set up optional noise RNG
-    let use_noise = config.noise_stddev > 0.0;
-    let mut rng: Option<StdRng> = if use_noise {
-        Some(StdRng::from_entropy())
+///
+/// Returns [`SimLidarError::InvalidConfig`] if a noise standard deviation is
+/// not a finite, non-negative number.
+///
+/// The free scan functions regenerate the ray direction table on every call;
+/// [`Sensor`](crate::Sensor) caches it and reuses its output buffers.
+#[deprecated(note = "use `Sensor::scan`, which caches the ray direction table")]
+#[allow(deprecated)]
+pub fn scan(bvh: &Bvh, config: &SensorConfig, position: Vec3, rotation: Quat) -> Result<ScanResult, SimLidarError> {
+    scan_with_motion(bvh, config, position, rotation, &Motion::default())
+}
+
+/// Execute a single LiDAR scan, computing radial velocities from `motion`.
+///
+/// Identical to [`scan`] except that, when [`SensorConfig::doppler`] is
+/// enabled, each hit also records the relative velocity between the hit
+/// surface and the sensor projected onto the ray direction.
+#[deprecated(note = "use `Sensor::scan` with `Sensor::motion_mut`, which caches the ray direction table")]
+#[allow(deprecated)]
+pub fn scan_with_motion(
+    bvh: &Bvh,
+    config: &SensorConfig,
+    position: Vec3,
+    rotation: Quat,
+    motion: &Motion,
+) -> Result<ScanResult, SimLidarError> {
+    let mut result = ScanResult::default();
+    scan_into(bvh, config, position, rotation, motion, &mut result)?;
+    Ok(result)
+}
+
+/// Execute a single LiDAR scan, writing the output into an existing
+/// [`ScanResult`].
+///
+/// `out` is cleared first.  Its buffers are reserved up front for a full scan
+/// (`config.total_rays()` hits), so once a `ScanResult` has been used with a
+/// given configuration, later scans never reallocate it.  On error `out` is
+/// left empty.  Noise is drawn from a fresh random seed; use
+/// [`scan_into_seeded`] for reproducible noise.
+#[deprecated(note = "use `Sensor::scan`, which caches the ray direction table")]
+#[allow(deprecated)]
+pub fn scan_into(
+    bvh: &Bvh,
+    config: &SensorConfig,
+    position: Vec3,
+    rotation: Quat,
+    motion: &Motion,
+    out: &mut ScanResult,
+) -> Result<(), SimLidarError> {
+    let seed = if config.is_noisy() { rand::random() } else { 0 };
+    scan_into_seeded(bvh, config, position, rotation, motion, seed, out)
+}
+
+/// Like [`scan_into`], drawing range and velocity noise from `seed`.
+///
+/// Each ray's noise depends only on `seed` and the ray index, so a scan is
+/// reproducible, including with the `parallel` feature regardless of the
+/// number of threads.
+#[deprecated(note = "use `Sensor::scan` with `Sensor::set_noise_seed`, which caches the ray direction table")]
+pub fn scan_into_seeded(
+    bvh: &Bvh,
+    config: &SensorConfig,
+    position: Vec3,
+    rotation: Quat,
+    motion: &Motion,
+    seed: u64,
+    out: &mut ScanResult,
+) -> Result<(), SimLidarError> {
+    let directions = config.generate_local_ray_directions();
+    scan_directions_into(bvh, config, &directions, &Pose { position, rotation }, motion, seed, out)
+}
+
+/// [`scan_into_seeded`] with a precomputed table of sensor-local ray
+/// directions (from [`SensorConfig::generate_local_ray_directions`]), rotated
+/// into the world frame as the rays are cast.  Without the `parallel`
+/// feature nothing is allocated besides the growth of `out`.
+pub(crate) fn scan_directions_into(
+    bvh: &Bvh,
+    config: &SensorConfig,
+    directions: &[Vec3],
+    pose: &Pose,
+    motion: &Motion,
+    seed: u64,
+    out: &mut ScanResult,
+) -> Result<(), SimLidarError> {
+    let (position, rotation) = (pose.position, pose.rotation);
+    out.clear();
+    out.reserve(directions.len(), config.doppler);
+    out.sensor_position = position;
+    out.sensor_rotation = rotation;
+
+    // Any non-zero stddev (including invalid negative or NaN values) goes
+    // through `noise_distribution` so bad configs are reported instead of
+    // ignored.
+    let noise_dist: Option<Normal<f32>> = if config.noise_stddev != 0.0 {
+        Some(noise_distribution("noise_stddev", config.noise_stddev)?)
     } else {
         None
     };
-    let noise_dist: Option<Normal<f32>> = if use_noise {
-        Some(Normal::new(0.0, config.noise_stddev).expect("valid stddev"))
+    let velocity_noise_dist: Option<Normal<f32>> = if config.doppler && config.velocity_noise_stddev != 0.0 {
+        Some(noise_distribution("velocity_noise_stddev", config.velocity_noise_stddev)?)
     } else {
         None
     };
 
-    for dir in &directions {
-        if let Some(mut t) = bvh.cast_ray(position, *dir, config.max_range) {
-            if t < config.min_range {
-                continue;
-            }
-            // Apply Gaussian noise to the range measurement if configured
-            if let (Some(ref mut rng), Some(ref dist)) = (rng.as_mut(), noise_dist.as_ref()) {
-                let noise: f32 = dist.sample(rng as &mut StdRng);
-                t = (t + noise).max(0.0);
+    let sample = |i: usize, dir: Vec3, hit: Option<Intersection>| -> Option<RaySample> {
+        let hit = hit.filter(|hit| hit.distance >= config.min_range)?;
+        let mut rng = RayRng::new(seed, i);
+        let mut t = hit.distance;
+        // Apply Gaussian noise to the range measurement if configured
+        if let Some(dist) = noise_dist.as_ref() {
+            let noise: f32 = dist.sample(&mut rng);
+            t = (t + noise).max(0.0);
+        }
+        let mut velocity = 0.0;
+        if config.doppler {
+            let object_velocity = motion.object_velocity(hit.object_id);
+            velocity = (object_velocity - motion.sensor_velocity).dot(dir);
+            if let Some(dist) = velocity_noise_dist.as_ref() {
+                velocity += dist.sample(&mut rng);
             }
-            let hit = position + *dir * t;
-            hits.push(hit.x);
-            hits.push(hit.y);
-            hits.push(hit.z);
-            hit_count += 1;
         }
+        Some(RaySample {
+            point: position + dir * t,
+            intensity: hit.normal.dot(dir).abs(),
+            label: bvh.object_label(hit.object_id),
+            object_id: hit.object_id,
+            velocity,
+        })
+    };
+
+    let columns = config.horizontal_resolution.max(1) as usize;
+    let column_period = 1.0 / (columns as f32 * config.scan_frequency_hz);
+    let mut push = |i: usize, sample: Option<RaySample>| {
+        let ring = (i / columns) as u16;
+        let time = (i % columns) as f32 * column_period;
+        let sample = match sample {
+            Some(sample) => sample,
+            None if config.organized => RaySample {
+                point: Vec3::NAN,
+                intensity: 0.0,
+                label: 0,
+                object_id: u32::MAX,
+                velocity: f32::NAN,
+            },
+            None => return,
+        };
+        out.hits.extend_from_slice(&sample.point.to_array());
+        out.rings.push(ring);
+        out.intensities.push(sample.intensity);
+        out.times.push(time);
+        out.labels.push(sample.label);
+        out.object_ids.push(sample.object_id);
+        if config.doppler {
+            out.velocities.push(sample.velocity);
+        }
+        out.hit_count += 1;
+    };
+
+    // Neighbouring rays of a channel are cast together as one packet.
+    // Lanes past the end of a short final packet are `None` and skipped.
+    let cast_packet = |packet: usize, local: &[Vec3]| -> [Option<RaySample>; PACKET_WIDTH] {
+        let mut dirs = [Vec3::ZERO; PACKET_WIDTH];
+        for (dir, local) in dirs.iter_mut().zip(local) {
+            *dir = rotation * *local;
+        }
+        let dirs = &dirs[..local.len()];
+        let mut hits = bvh.cast_ray_packet(position, dirs, config.max_range);
+        std::array::from_fn(|lane| sample(packet * PACKET_WIDTH + lane, *dirs.get(lane)?, hits[lane].take()))
+    };
+
+    // Packets are cast in parallel, then appended in ray order.
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        let samples: Vec<Option<RaySample>> = directions
+            .par_chunks(PACKET_WIDTH)
+            .enumerate()
+            .flat_map_iter(|(packet, dirs)| cast_packet(packet, dirs).into_iter().take(dirs.len()))
+            .collect();
+        for (i, sample) in samples.into_iter().enumerate() {
+            push(i, sample);
+        }
+    }
+    #[cfg(not(feature = "parallel"))]
+    for (packet, dirs) in directions.chunks(PACKET_WIDTH).enumerate() {
+        for (lane, sample) in cast_packet(packet, dirs).into_iter().take(dirs.len()).enumerate() {
+            push(packet * PACKET_WIDTH + lane, sample);
+        }
+    }
+
+    if config.organized {
+        out.width = config.horizontal_resolution;
+        out.height = config.vertical_channels;
+    } else {
+        out.width = out.hit_count as u32;
+        out.height = 1;
+    }
+    Ok(())
+}
+
+/// The measurement of a single ray that hit a surface.
+struct RaySample {
+    point: Vec3,
+    intensity: f32,
+    label: u32,
+    object_id: u32,
+    /// Radial velocity; only used with Doppler output.
+    velocity: f32,
+}
+
+/// SplitMix64 finalizer, used to derive independent per-ray seeds.
+pub(crate) fn mix_seed(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Small SplitMix64 generator for one ray's noise, seeded from the scan
+/// seed and the ray index, so noise does not depend on the order in which
+/// rays are cast.
+struct RayRng(u64);
+
+impl RayRng {
+    fn new(seed: u64, ray: usize) -> Self {
+        Self(seed ^ mix_seed(ray as u64))
+    }
+}
+
+impl RngCore for RayRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix_seed(self.0)
     }
 
-    ScanResult { hits, hit_count }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Zero-mean Gaussian with the given standard deviation, or an
+/// [`SimLidarError::InvalidConfig`] naming `field` if it is not usable.
+fn noise_distribution(field: &'static str, stddev: f32) -> Result<Normal<f32>, SimLidarError> {
+    Normal::new(0.0, stddev).map_err(|_| {
+        SimLidarError::InvalidConfig(vec![ConfigIssue::new(field, format!("must be finite and >= 0, got {stddev}"))])
+    })
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
     use crate::bvh::Bvh;
@@ -76,15 +417,15 @@ mod tests {
             -10.0, 0.0,  10.0,
         ];
         let indices: Vec<u32> = vec![0, 1, 2, 0, 2, 3];
-        Bvh::build(&vertices, &indices)
+        Bvh::build(&vertices, &indices).unwrap()
     }
 
     #[test]
     fn test_scan_hits_ground() {
         let bvh = ground_plane_bvh();
         // Single downward-pointing ray
-        let config = SensorConfig::new(1, 1, -89.9, -89.9, 0.1, 50.0, 0.0);
-        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY);
+        let config = SensorConfig::new(1, 1, -89.9, -89.9, 0.1, 50.0, 0.0).unwrap();
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
         assert!(result.hit_count > 0, "Expected at least one ground hit");
     }
 
@@ -92,16 +433,234 @@ mod tests {
     fn test_scan_max_range_filters_hits() {
         let bvh = ground_plane_bvh();
         // Sensor 5m above, max range 3m — the ground is out of range
-        let config = SensorConfig::new(1, 1, -89.9, -89.9, 0.1, 3.0, 0.0);
-        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY);
+        let config = SensorConfig::new(1, 1, -89.9, -89.9, 0.1, 3.0, 0.0).unwrap();
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
         assert_eq!(result.hit_count, 0, "Ground is beyond max range, no hits expected");
     }
 
     #[test]
     fn test_scan_output_buffer_length() {
         let bvh = ground_plane_bvh();
-        let config = SensorConfig::new(36, 1, -89.0, -89.0, 0.1, 100.0, 0.0);
-        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY);
+        let config = SensorConfig::new(36, 1, -89.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
         assert_eq!(result.hits.len(), result.hit_count * 3);
     }
+
+    // ── Doppler / radial velocity ──────────────────────────────────────────
+
+    fn doppler_config() -> SensorConfig {
+        // Single downward-pointing ray
+        let mut config = SensorConfig::new(1, 1, -90.0, -90.0, 0.1, 50.0, 0.0).unwrap();
+        config.doppler = true;
+        config
+    }
+
+    #[test]
+    fn test_scan_without_doppler_has_no_velocities() {
+        let bvh = ground_plane_bvh();
+        let config = SensorConfig::new(36, 1, -89.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
+        assert!(result.hit_count > 0);
+        assert!(result.velocities.is_empty());
+    }
+
+    #[test]
+    fn test_scan_doppler_static_scene_is_zero() {
+        let bvh = ground_plane_bvh();
+        let result = scan(&bvh, &doppler_config(), Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
+        assert_eq!(result.velocities.len(), result.hit_count);
+        assert!(result.velocities[0].abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_scan_doppler_sensor_approaching_ground() {
+        let bvh = ground_plane_bvh();
+        // Sensor descending at 2 m/s towards the ground: range is shrinking.
+        let motion = Motion {
+            sensor_velocity: Vec3::new(0.0, -2.0, 0.0),
+            object_velocities: Vec::new(),
+        };
+        let result = scan_with_motion(&bvh, &doppler_config(), Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY, &motion).unwrap();
+        assert_eq!(result.hit_count, 1);
+        assert!((result.velocities[0] + 2.0).abs() < 1e-4, "Expected -2 m/s, got {}", result.velocities[0]);
+    }
+
+    #[test]
+    fn test_scan_doppler_uses_object_velocity() {
+        let vertices: Vec<f32> = vec![
+            -10.0, 0.0, -10.0,
+             10.0, 0.0, -10.0,
+             10.0, 0.0,  10.0,
+            -10.0, 0.0,  10.0,
+        ];
+        let indices: Vec<u32> = vec![0, 1, 2, 0, 2, 3];
+        let bvh = Bvh::build_with_object_ids(&vertices, &indices, &[1, 1]).unwrap();
+        // Object 1 sinks at 3 m/s while the sensor is static; tangential
+        // motion must not contribute.
+        let motion = Motion {
+            sensor_velocity: Vec3::ZERO,
+            object_velocities: vec![Vec3::ZERO, Vec3::new(5.0, -3.0, 0.0)],
+        };
+        let result = scan_with_motion(&bvh, &doppler_config(), Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY, &motion).unwrap();
+        assert_eq!(result.hit_count, 1);
+        assert!((result.velocities[0] - 3.0).abs() < 1e-3, "Expected +3 m/s, got {}", result.velocities[0]);
+    }
+
+    #[test]
+    fn test_scan_seeded_noise_is_reproducible() {
+        let bvh = ground_plane_bvh();
+        let mut config = SensorConfig::new(64, 4, -40.0, -60.0, 0.1, 50.0, 0.05).unwrap();
+        config.doppler = true;
+        config.velocity_noise_stddev = 0.1;
+        let scan = |seed| {
+            let mut out = ScanResult::default();
+            scan_into_seeded(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY, &Motion::default(), seed, &mut out).unwrap();
+            out
+        };
+        let (a, b, c) = (scan(7), scan(7), scan(8));
+        assert!(a.hit_count > 200);
+        assert_eq!(a.hits, b.hits);
+        assert_eq!(a.velocities, b.velocities);
+        assert_ne!(a.hits, c.hits);
+        assert!(a.velocities.iter().any(|&v| v != 0.0));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_scan_parallel_independent_of_thread_count() {
+        let bvh = ground_plane_bvh();
+        let config = SensorConfig::new(256, 8, -30.0, -60.0, 0.1, 50.0, 0.05).unwrap();
+        let scan = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                let mut out = ScanResult::default();
+                scan_into_seeded(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY, &Motion::default(), 42, &mut out).unwrap();
+                out
+            })
+        };
+        let (one, four) = (scan(1), scan(4));
+        assert!(one.hit_count > 1000);
+        assert_eq!(one.hits, four.hits);
+        assert_eq!(one.rings, four.rings);
+    }
+
+    #[test]
+    fn test_scan_nan_noise_is_invalid_config() {
+        let bvh = ground_plane_bvh();
+        // Fields are public, so a config can be invalidated after construction.
+        let mut config = SensorConfig::new(1, 1, -89.9, -89.9, 0.1, 50.0, 0.0).unwrap();
+        config.noise_stddev = f32::NAN;
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY);
+        assert!(matches!(result, Err(SimLidarError::InvalidConfig(_))));
+    }
+
+    // ── Buffer reuse ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_scan_into_reuses_allocation() {
+        let bvh = ground_plane_bvh();
+        let config = SensorConfig::new(36, 4, -60.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        let position = Vec3::new(0.0, 5.0, 0.0);
+        let mut out = ScanResult::default();
+        scan_into(&bvh, &config, position, Quat::IDENTITY, &Motion::default(), &mut out).unwrap();
+        let first_count = out.hit_count;
+        let ptr = out.hits.as_ptr();
+        let capacity = out.hits.capacity();
+        assert!(capacity >= config.total_rays() as usize * 3);
+
+        scan_into(&bvh, &config, position, Quat::IDENTITY, &Motion::default(), &mut out).unwrap();
+        assert_eq!(out.hit_count, first_count, "Result must be cleared between scans");
+        assert_eq!(out.hits.len(), out.hit_count * 3);
+        assert_eq!(out.hits.as_ptr(), ptr, "Buffer must not be reallocated");
+        assert_eq!(out.hits.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_scan_into_matches_scan() {
+        let bvh = ground_plane_bvh();
+        let config = SensorConfig::new(36, 2, -45.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        let position = Vec3::new(0.0, 5.0, 0.0);
+        let expected = scan(&bvh, &config, position, Quat::IDENTITY).unwrap();
+        let mut out = ScanResult::with_capacity(config.total_rays() as usize);
+        scan_into(&bvh, &config, position, Quat::IDENTITY, &Motion::default(), &mut out).unwrap();
+        assert_eq!(out.hits, expected.hits);
+    }
+
+    // ── Rings / organized output ───────────────────────────────────────────
+
+    #[test]
+    fn test_scan_rings_follow_channels() {
+        let bvh = ground_plane_bvh();
+        let config = SensorConfig::new(8, 3, -60.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
+        assert_eq!(result.rings.len(), result.hit_count);
+        assert_eq!(result.rings, [[0u16; 8], [1; 8], [2; 8]].concat());
+        assert_eq!((result.width, result.height), (result.hit_count as u32, 1));
+    }
+
+    #[test]
+    fn test_scan_organized_keeps_misses_as_nan() {
+        let bvh = ground_plane_bvh();
+        // Upper channel points at the sky and misses, lower channel hits the ground.
+        let mut config = SensorConfig::new(4, 2, 30.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        config.organized = true;
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
+        assert_eq!((result.width, result.height), (4, 2));
+        assert_eq!(result.hit_count, 8);
+        assert_eq!(result.hits.len(), 8 * 3);
+        assert!(result.hits[..4 * 3].iter().all(|v| v.is_finite()));
+        assert!(result.hits[4 * 3..].iter().all(|v| v.is_nan()));
+        assert_eq!(result.rings, vec![0, 0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(result.intensities[4..], [0.0; 4]);
+        assert_eq!(result.object_ids[4..], [u32::MAX; 4]);
+    }
+
+    // ── Per-point attributes ───────────────────────────────────────────────
+
+    #[test]
+    fn test_scan_intensity_follows_incidence_angle() {
+        let bvh = ground_plane_bvh();
+        // Straight down hits the ground head-on; 60° below the horizon hits at 30° off the normal.
+        let config = SensorConfig::new(4, 2, -60.0, -90.0, 0.1, 100.0, 0.0).unwrap();
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
+        assert_eq!(result.intensities.len(), 8);
+        assert!(result.intensities[..4].iter().all(|&i| (i - 1.0).abs() < 1e-5));
+        let expected = 60f32.to_radians().sin();
+        assert!(result.intensities[4..].iter().all(|&i| (i - expected).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_scan_times_follow_azimuth_columns() {
+        let bvh = ground_plane_bvh();
+        let mut config = SensorConfig::new(4, 2, -60.0, -89.0, 0.1, 100.0, 0.0).unwrap();
+        config.scan_frequency_hz = 20.0;
+        let result = scan(&bvh, &config, Vec3::new(0.0, 5.0, 0.0), Quat::IDENTITY).unwrap();
+        // 20 Hz sweep split into 4 columns: 12.5 ms per column, same for every ring.
+        let expected = [0.0, 0.0125, 0.025, 0.0375];
+        for (t, e) in result.times.iter().zip(expected.iter().cycle()) {
+            assert!((t - e).abs() < 1e-6, "{t} != {e}");
+        }
+    }
+
+    #[test]
+    fn test_scan_labels_come_from_object_labels() {
+        let vertices: Vec<f32> = vec![
+            -10.0, 0.0, -10.0,
+             10.0, 0.0, -10.0,
+             10.0, 0.0,  10.0,
+            -10.0, 0.0,  10.0,
+        ];
+        let indices: Vec<u32> = vec![0, 1, 2, 0, 2, 3];
+        let mut bvh = Bvh::build_with_object_ids(&vertices, &indices, &[2, 2]).unwrap();
+        let config = doppler_config();
+        let position = Vec3::new(0.0, 5.0, 0.0);
+
+        let result = scan(&bvh, &config, position, Quat::IDENTITY).unwrap();
+        assert_eq!((result.labels[0], result.object_ids[0]), (0, 2), "Unlabeled objects report label 0");
+
+        bvh.set_object_labels(&[0, 0, 40]);
+        let result = scan(&bvh, &config, position, Quat::IDENTITY).unwrap();
+        assert_eq!(result.labels, vec![40]);
+        assert_eq!(result.sensor_position, position);
+    }
 }
@@ -0,0 +1,211 @@
+use crate::error::SimLidarError;
+use crate::pose::Pose;
+use crate::raycaster::ScanResult;
+use crate::scene::Scene;
+use crate::sensor::{Sensor, SensorConfig};
+
+/// A [`Sensor`] on a [`Rig`], with its mounting transform relative to the
+/// vehicle base frame.
+pub struct MountedSensor {
+    sensor: Sensor,
+    mount: Pose,
+}
+
+impl MountedSensor {
+    /// The sensor, holding its configuration and last scan.
+    pub fn sensor(&self) -> &Sensor {
+        &self.sensor
+    }
+
+    /// Mutable access to the sensor, e.g. to change its configuration,
+    /// motion or noise seed.
+    pub fn sensor_mut(&mut self) -> &mut Sensor {
+        &mut self.sensor
+    }
+
+    /// Sensor pose in the vehicle base frame.
+    pub fn mount(&self) -> &Pose {
+        &self.mount
+    }
+
+    /// Move the sensor on the vehicle.
+    pub fn set_mount(&mut self, mount: Pose) {
+        self.mount = mount;
+    }
+
+    /// The result of the last rig scan, in world coordinates.
+    pub fn last_scan(&self) -> &ScanResult {
+        self.sensor.last_scan()
+    }
+}
+
+/// The clouds of every sensor of a [`Rig`] merged into one unorganized
+/// cloud.  Organized misses are dropped.
+#[derive(Clone, Debug, Default)]
+pub struct MergedScan {
+    /// Concatenated points in sensor id order, in world coordinates.  Rings
+    /// and times are those of the originating sensor; velocities are present
+    /// if any sensor has Doppler output, `NaN` for the others.  The sensor
+    /// pose fields hold the vehicle pose.
+    pub scan: ScanResult,
+    /// Id of the sensor that produced each point, parallel to `scan.hits`.
+    pub sensor_ids: Vec<u32>,
+}
+
+/// Several sensors mounted on one vehicle, scanning a shared [`Scene`].
+///
+/// ```
+/// use glam::{Quat, Vec3};
+/// use sim_lidar_rs::{Pose, Primitive, Rig, Scene, SensorConfig};
+///
+/// let mut scene = Scene::new();
+/// scene.add_primitive(Primitive::plane(Vec3::ZERO, Vec3::Y)?);
+/// let mut rig = Rig::new();
+/// let roof = rig.add_sensor(SensorConfig::vlp16(), Pose::from_position(Vec3::new(0.0, 2.0, 0.0)))?;
+/// rig.add_sensor(SensorConfig::vlp16(), Pose::new(Vec3::new(2.0, 0.5, 1.0), Quat::from_rotation_y(-0.8)))?;
+///
+/// let vehicle = Pose::from_position(Vec3::new(10.0, 0.0, 5.0));
+/// let merged = rig.scan_merged(&scene, &vehicle)?;
+/// assert_eq!(merged.sensor_ids.len(), merged.scan.hit_count);
+/// assert!(rig.sensor(roof).unwrap().last_scan().hit_count > 0);
+/// # Ok::<(), sim_lidar_rs::SimLidarError>(())
+/// ```
+#[derive(Default)]
+pub struct Rig {
+    sensors: Vec<MountedSensor>,
+    merged: MergedScan,
+}
+
+impl Rig {
+    /// A rig without sensors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mount a sensor at `mount` (in the vehicle base frame) and return its
+    /// id.  Fails with [`SimLidarError::InvalidConfig`] if `config` is
+    /// invalid.
+    pub fn add_sensor(&mut self, config: SensorConfig, mount: Pose) -> Result<u32, SimLidarError> {
+        let sensor = Sensor::new(config)?;
+        self.sensors.push(MountedSensor { sensor, mount });
+        Ok(self.sensors.len() as u32 - 1)
+    }
+
+    /// The mounted sensors, indexed by sensor id.
+    pub fn sensors(&self) -> &[MountedSensor] {
+        &self.sensors
+    }
+
+    /// The sensor with the given id.
+    pub fn sensor(&self, id: u32) -> Option<&MountedSensor> {
+        self.sensors.get(id as usize)
+    }
+
+    /// Mutable access to the sensor with the given id.
+    pub fn sensor_mut(&mut self, id: u32) -> Option<&mut MountedSensor> {
+        self.sensors.get_mut(id as usize)
+    }
+
+    /// Scan `scene` with every sensor from the vehicle at `vehicle`, and
+    /// return the sensors with their new per-sensor clouds.
+    ///
+    /// Fails like [`Sensor::scan`]; sensors before the failing one keep their
+    /// new scan.
+    pub fn scan(&mut self, scene: &Scene, vehicle: &Pose) -> Result<&[MountedSensor], SimLidarError> {
+        for mounted in &mut self.sensors {
+            mounted.sensor.scan(scene, &(*vehicle * mounted.mount))?;
+        }
+        Ok(&self.sensors)
+    }
+
+    /// Like [`Rig::scan`], merging the per-sensor clouds into one cloud
+    /// tagged with sensor ids.  The merged buffers are reused across calls.
+    pub fn scan_merged(&mut self, scene: &Scene, vehicle: &Pose) -> Result<&MergedScan, SimLidarError> {
+        self.scan(scene, vehicle)?;
+        let MergedScan { scan: out, sensor_ids } = &mut self.merged;
+        out.clear();
+        sensor_ids.clear();
+        out.sensor_position = vehicle.position;
+        out.sensor_rotation = vehicle.rotation;
+        let doppler = self.sensors.iter().any(|mounted| mounted.sensor.config().doppler);
+        for (id, mounted) in self.sensors.iter().enumerate() {
+            let scan = mounted.sensor.last_scan();
+            for (i, point) in scan.hits.chunks_exact(3).enumerate() {
+                if point[0].is_nan() {
+                    continue;
+                }
+                out.hits.extend_from_slice(point);
+                out.rings.push(scan.rings[i]);
+                out.intensities.push(scan.intensities[i]);
+                out.times.push(scan.times[i]);
+                out.labels.push(scan.labels[i]);
+                out.object_ids.push(scan.object_ids[i]);
+                if doppler {
+                    out.velocities.push(scan.velocities.get(i).copied().unwrap_or(f32::NAN));
+                }
+                sensor_ids.push(id as u32);
+            }
+        }
+        out.hit_count = sensor_ids.len();
+        out.width = out.hit_count as u32;
+        out.height = 1;
+        Ok(&self.merged)
+    }
+
+    /// The result of the last [`Rig::scan_merged`].
+    pub fn last_merged_scan(&self) -> &MergedScan {
+        &self.merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Primitive;
+    use glam::{Quat, Vec3};
+
+    fn ring_config() -> SensorConfig {
+        SensorConfig::new(90, 1, 0.0, 0.0, 0.1, 100.0, 0.0).unwrap()
+    }
+
+    #[test]
+    fn test_rig_applies_mounts_and_merges() {
+        // The sensors sit inside a sphere of radius 20 centred on the vehicle.
+        let mut scene = Scene::new();
+        let vehicle = Pose::new(Vec3::new(5.0, 1.0, -3.0), Quat::from_rotation_y(1.2));
+        scene.add_primitive(Primitive::sphere(vehicle.position, 20.0).unwrap());
+        let mut rig = Rig::new();
+        let mount = Pose::new(Vec3::new(1.0, 2.0, 0.5), Quat::from_rotation_x(0.4));
+        assert_eq!(rig.add_sensor(ring_config(), Pose::IDENTITY).unwrap(), 0);
+        assert_eq!(rig.add_sensor(ring_config(), mount).unwrap(), 1);
+        let mut organized = ring_config();
+        organized.organized = true;
+        organized.max_range = 5.0;
+        assert_eq!(rig.add_sensor(organized, Pose::IDENTITY).unwrap(), 2);
+        let mut invalid = ring_config();
+        invalid.horizontal_resolution = 0;
+        assert!(rig.add_sensor(invalid, Pose::IDENTITY).is_err());
+        assert_eq!(rig.sensors().len(), 3);
+
+        let sensors = rig.scan(&scene, &vehicle).unwrap();
+        assert_eq!(sensors[1].last_scan().pose(), vehicle * mount);
+        assert_eq!(sensors[2].last_scan().hit_count, 90, "Organized misses");
+
+        rig.scan_merged(&scene, &vehicle).unwrap();
+        let merged = rig.last_merged_scan();
+        assert_eq!(merged.scan.hit_count, 180, "Misses are dropped");
+        assert_eq!(merged.sensor_ids[..90], [0; 90]);
+        assert_eq!(merged.sensor_ids[90..], [1; 90]);
+        assert_eq!(merged.scan.hits[270..], rig.sensor(1).unwrap().last_scan().hits[..]);
+        assert!(merged.scan.points().all(|p| (p.distance(vehicle.position) - 20.0).abs() < 1e-3));
+        assert_eq!(merged.scan.pose(), vehicle);
+        assert!(merged.scan.velocities.is_empty());
+    }
+
+    #[test]
+    fn test_rig_requires_geometry() {
+        let mut rig = Rig::new();
+        rig.add_sensor(ring_config(), Pose::IDENTITY).unwrap();
+        assert!(matches!(rig.scan_merged(&Scene::new(), &Pose::IDENTITY), Err(SimLidarError::MissingGeometry)));
+    }
+}
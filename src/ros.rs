@@ -0,0 +1,490 @@
+use std::str::FromStr;
+
+use glam::{Quat, Vec3};
+
+use crate::error::SimLidarError;
+use crate::point_fields::{sensor_frame_point, Scalar};
+use crate::raycaster::ScanResult;
+
+/// `sensor_msgs/PointField` datatype constants.
+const UINT16: u8 = 4;
+const UINT32: u8 = 6;
+const FLOAT32: u8 = 7;
+
+/// Per-point value that can be written into a `PointCloud2` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointCloud2Channel {
+    /// Sensor-frame coordinates (x forward, y left, z up), `FLOAT32`.
+    X,
+    Y,
+    Z,
+    /// Return intensity in `[0, 1]`, `FLOAT32`.
+    Intensity,
+    /// Laser channel, `UINT16`.
+    Ring,
+    /// Seconds since the scan start, `FLOAT32` (Velodyne driver convention).
+    Time,
+    /// Nanoseconds since the scan start, `UINT32` (Ouster driver `t`).
+    TimeNs,
+    /// Intensity scaled to `0..=255`, `UINT16` (Ouster driver `reflectivity`).
+    Reflectivity,
+    /// Ambient light, always `0`, `UINT16` (Ouster driver `ambient`).
+    Ambient,
+    /// Range from the sensor in millimetres, `UINT32` (Ouster driver `range`).
+    RangeMm,
+}
+
+impl PointCloud2Channel {
+    /// `sensor_msgs/PointField` datatype of the channel.
+    pub(crate) fn datatype(self) -> u8 {
+        match self {
+            PointCloud2Channel::Ring | PointCloud2Channel::Reflectivity | PointCloud2Channel::Ambient => UINT16,
+            PointCloud2Channel::TimeNs | PointCloud2Channel::RangeMm => UINT32,
+            _ => FLOAT32,
+        }
+    }
+
+    fn size(self) -> u32 {
+        match self.datatype() {
+            UINT16 => 2,
+            _ => 4,
+        }
+    }
+
+    /// Field name used by [`PointCloud2Layout::packed`].
+    fn default_name(self) -> &'static str {
+        match self {
+            PointCloud2Channel::X => "x",
+            PointCloud2Channel::Y => "y",
+            PointCloud2Channel::Z => "z",
+            PointCloud2Channel::Intensity => "intensity",
+            PointCloud2Channel::Ring => "ring",
+            PointCloud2Channel::Time => "time",
+            PointCloud2Channel::TimeNs => "t",
+            PointCloud2Channel::Reflectivity => "reflectivity",
+            PointCloud2Channel::Ambient => "ambient",
+            PointCloud2Channel::RangeMm => "range",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let channel = match name {
+            "x" => PointCloud2Channel::X,
+            "y" => PointCloud2Channel::Y,
+            "z" => PointCloud2Channel::Z,
+            "intensity" => PointCloud2Channel::Intensity,
+            "ring" => PointCloud2Channel::Ring,
+            "time" => PointCloud2Channel::Time,
+            "t" => PointCloud2Channel::TimeNs,
+            "reflectivity" => PointCloud2Channel::Reflectivity,
+            "ambient" => PointCloud2Channel::Ambient,
+            "range" => PointCloud2Channel::RangeMm,
+            _ => return None,
+        };
+        Some(channel)
+    }
+}
+
+/// One `sensor_msgs/PointField` of a [`PointCloud2Layout`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloud2Field {
+    pub name: String,
+    /// Byte offset of the field within a point record.
+    pub offset: u32,
+    pub channel: PointCloud2Channel,
+}
+
+/// Field layout of a `PointCloud2` point record.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloud2Layout {
+    pub fields: Vec<PointCloud2Field>,
+    /// Size of one point record in bytes (may include padding).
+    pub point_step: u32,
+}
+
+impl PointCloud2Layout {
+    /// Fields packed back to back, in order, with their default names.
+    pub fn packed(channels: &[PointCloud2Channel]) -> Self {
+        let mut offset = 0;
+        let fields = channels
+            .iter()
+            .map(|&channel| {
+                let field = PointCloud2Field {
+                    name: channel.default_name().to_string(),
+                    offset,
+                    channel,
+                };
+                offset += channel.size();
+                field
+            })
+            .collect();
+        Self {
+            fields,
+            point_step: offset,
+        }
+    }
+
+    /// `x y z intensity ring time`, packed into 22 bytes, as published by the
+    /// ROS 2 `velodyne_pointcloud` driver.
+    pub fn velodyne() -> Self {
+        use PointCloud2Channel::*;
+        Self::packed(&[X, Y, Z, Intensity, Ring, Time])
+    }
+
+    /// The 48-byte `ouster_ros::Point` record published by the Ouster ROS
+    /// driver: `x y z` (padded to 16), `intensity`, `t`, `reflectivity`,
+    /// `ring`, `ambient`, `range`.
+    pub fn ouster() -> Self {
+        use PointCloud2Channel::*;
+        let field = |name: &str, offset, channel| PointCloud2Field {
+            name: name.to_string(),
+            offset,
+            channel,
+        };
+        Self {
+            fields: vec![
+                field("x", 0, X),
+                field("y", 4, Y),
+                field("z", 8, Z),
+                field("intensity", 16, Intensity),
+                field("t", 20, TimeNs),
+                field("reflectivity", 24, Reflectivity),
+                field("ring", 26, Ring),
+                field("ambient", 28, Ambient),
+                field("range", 32, RangeMm),
+            ],
+            point_step: 48,
+        }
+    }
+
+    /// Check that the layout has at least one field and that every field
+    /// fits within `point_step`.  Fails with [`SimLidarError::Format`].
+    pub fn validate(&self) -> Result<(), SimLidarError> {
+        if self.fields.is_empty() || self.point_step == 0 {
+            return Err(SimLidarError::Format("PointCloud2 layout has no fields".to_string()));
+        }
+        for field in &self.fields {
+            if field.offset.checked_add(field.channel.size()).is_none_or(|end| end > self.point_step) {
+                return Err(SimLidarError::Format(format!(
+                    "PointCloud2 field {:?} at offset {} does not fit in a {}-byte point",
+                    field.name, field.offset, self.point_step
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PointCloud2Layout {
+    type Err = SimLidarError;
+
+    /// `"velodyne"`, `"ouster"`, or a comma-separated list of field names
+    /// (e.g. `"x,y,z,intensity"`) to pack in order.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "velodyne" => Ok(Self::velodyne()),
+            "ouster" => Ok(Self::ouster()),
+            _ => {
+                let channels = s
+                    .split(',')
+                    .map(|name| {
+                        PointCloud2Channel::from_name(name.trim())
+                            .ok_or_else(|| SimLidarError::Format(format!("unknown PointCloud2 field {name:?}")))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::packed(&channels))
+            }
+        }
+    }
+}
+
+/// Little-endian CDR encoder.  Alignment is relative to the end of the
+/// 4-byte encapsulation header, as required by the ROS 2 middleware.
+pub(crate) struct CdrWriter {
+    pub(crate) buf: Vec<u8>,
+}
+
+impl CdrWriter {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let mut buf = Vec::with_capacity(capacity + 4);
+        buf.extend_from_slice(&[0x00, 0x01, 0x00, 0x00]); // CDR_LE
+        Self { buf }
+    }
+
+    pub(crate) fn align(&mut self, n: usize) {
+        while !(self.buf.len() - 4).is_multiple_of(n) {
+            self.buf.push(0);
+        }
+    }
+
+    pub(crate) fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub(crate) fn u32(&mut self, v: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn i32(&mut self, v: i32) {
+        self.align(4);
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn f64(&mut self, v: f64) {
+        self.align(8);
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn string(&mut self, s: &str) {
+        self.u32(s.len() as u32 + 1);
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+    }
+
+    pub(crate) fn bytes(&mut self, data: &[u8]) {
+        self.u32(data.len() as u32);
+        self.buf.extend_from_slice(data);
+    }
+}
+
+/// Point records of `result` laid out by `layout`, in the sensor frame.
+/// Returns the record bytes, the `(width, height)` grid and whether every
+/// point is finite.  Fails if `layout` is invalid.
+pub(crate) fn pack_points(result: &ScanResult, layout: &PointCloud2Layout) -> Result<(Vec<u8>, u32, u32, bool), SimLidarError> {
+    layout.validate()?;
+    let n = result.hit_count;
+    let (width, height) = if result.width as usize * result.height as usize == n {
+        (result.width, result.height)
+    } else {
+        (n as u32, 1)
+    };
+    let point_step = layout.point_step as usize;
+
+    let mut data = vec![0u8; n * point_step];
+    let mut is_dense = true;
+    for (i, record) in data.chunks_exact_mut(point_step).enumerate() {
+        let p = sensor_frame_point(result, i);
+        is_dense &= p.is_finite();
+        let intensity = result.intensities.get(i).copied().unwrap_or(0.0);
+        let time = result.times.get(i).copied().unwrap_or(0.0);
+        for field in &layout.fields {
+            let value = match field.channel {
+                PointCloud2Channel::X => Scalar::F32(p.x),
+                PointCloud2Channel::Y => Scalar::F32(p.y),
+                PointCloud2Channel::Z => Scalar::F32(p.z),
+                PointCloud2Channel::Intensity => Scalar::F32(intensity),
+                PointCloud2Channel::Ring => Scalar::U16(result.rings.get(i).copied().unwrap_or(0)),
+                PointCloud2Channel::Time => Scalar::F32(time),
+                PointCloud2Channel::TimeNs => Scalar::U32((time as f64 * 1e9).round() as u32),
+                PointCloud2Channel::Reflectivity => Scalar::U16((intensity.clamp(0.0, 1.0) * 255.0).round() as u16),
+                PointCloud2Channel::Ambient => Scalar::U16(0),
+                PointCloud2Channel::RangeMm if p.is_finite() => Scalar::U32((p.length() * 1000.0).round() as u32),
+                PointCloud2Channel::RangeMm => Scalar::U32(0),
+            };
+            value.write_le_at(record, field.offset as usize);
+        }
+    }
+    Ok((data, width, height, is_dense))
+}
+
+/// Split a time in seconds into whole seconds and nanoseconds.
+pub(crate) fn split_stamp(stamp: f64) -> (i32, u32) {
+    let sec = stamp.floor();
+    let nanosec = ((stamp - sec) * 1e9).round().min(999_999_999.0) as u32;
+    (sec as i32, nanosec)
+}
+
+/// Encode a scan as a CDR-serialized ROS 2 `sensor_msgs/msg/PointCloud2`,
+/// ready to publish on a topic (e.g. through rosbridge or a DDS bridge).
+///
+/// * `layout`   – Point record layout, e.g. [`PointCloud2Layout::velodyne`].
+/// * `frame_id` – `header.frame_id` of the message.
+/// * `stamp`    – `header.stamp` in seconds (scan start time).
+///
+/// Points are expressed in the sensor frame (x forward, y left, z up).
+/// Organized scans keep their `height × width` grid and set
+/// `is_dense = false` when they contain misses.
+///
+/// Fails with [`SimLidarError::Format`] if `layout` is invalid (see
+/// [`PointCloud2Layout::validate`]).
+pub fn encode_point_cloud2(
+    result: &ScanResult,
+    layout: &PointCloud2Layout,
+    frame_id: &str,
+    stamp: f64,
+) -> Result<Vec<u8>, SimLidarError> {
+    let (data, width, height, is_dense) = pack_points(result, layout)?;
+    let (sec, nanosec) = split_stamp(stamp);
+    let mut cdr = CdrWriter::new(data.len() + 64 + layout.fields.len() * 24 + frame_id.len());
+    // std_msgs/Header
+    cdr.i32(sec);
+    cdr.u32(nanosec);
+    cdr.string(frame_id);
+    cdr.u32(height);
+    cdr.u32(width);
+    // sensor_msgs/PointField[]
+    cdr.u32(layout.fields.len() as u32);
+    for field in &layout.fields {
+        cdr.string(&field.name);
+        cdr.u32(field.offset);
+        cdr.u8(field.channel.datatype());
+        cdr.u32(1);
+    }
+    cdr.u8(0); // is_bigendian
+    cdr.u32(layout.point_step);
+    cdr.u32(layout.point_step * width);
+    cdr.bytes(&data);
+    cdr.u8(is_dense as u8);
+    Ok(cdr.buf)
+}
+
+/// Encode a CDR-serialized ROS 2 `tf2_msgs/msg/TFMessage` holding a single
+/// transform from `parent_frame_id` to `child_frame_id`.
+pub(crate) fn encode_tf_message(
+    parent_frame_id: &str,
+    child_frame_id: &str,
+    stamp: f64,
+    translation: Vec3,
+    rotation: Quat,
+) -> Vec<u8> {
+    let (sec, nanosec) = split_stamp(stamp);
+    let mut cdr = CdrWriter::new(128 + parent_frame_id.len() + child_frame_id.len());
+    cdr.u32(1);
+    // geometry_msgs/TransformStamped
+    cdr.i32(sec);
+    cdr.u32(nanosec);
+    cdr.string(parent_frame_id);
+    cdr.string(child_frame_id);
+    for v in translation.to_array() {
+        cdr.f64(v as f64);
+    }
+    for v in rotation.to_array() {
+        cdr.f64(v as f64);
+    }
+    cdr.buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal CDR reader mirroring [`CdrWriter`].
+    struct CdrReader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> CdrReader<'a> {
+        fn new(buf: &'a [u8]) -> Self {
+            assert_eq!(&buf[..4], &[0, 1, 0, 0]);
+            Self { buf, pos: 4 }
+        }
+
+        fn u8(&mut self) -> u8 {
+            self.pos += 1;
+            self.buf[self.pos - 1]
+        }
+
+        fn u32(&mut self) -> u32 {
+            self.pos += (4 - (self.pos - 4) % 4) % 4;
+            self.pos += 4;
+            u32::from_le_bytes(self.buf[self.pos - 4..self.pos].try_into().unwrap())
+        }
+
+        fn string(&mut self) -> String {
+            let len = self.u32() as usize;
+            self.pos += len;
+            String::from_utf8(self.buf[self.pos - len..self.pos - 1].to_vec()).unwrap()
+        }
+
+        fn bytes(&mut self) -> &'a [u8] {
+            let len = self.u32() as usize;
+            self.pos += len;
+            &self.buf[self.pos - len..self.pos]
+        }
+    }
+
+    fn sample_scan() -> ScanResult {
+        ScanResult {
+            // Forward 3 m, and a miss.
+            hits: vec![3.0, 5.0, 0.0, f32::NAN, f32::NAN, f32::NAN],
+            hit_count: 2,
+            rings: vec![5, 6],
+            width: 2,
+            height: 1,
+            intensities: vec![1.0, 0.0],
+            times: vec![0.025, 0.05],
+            sensor_position: Vec3::new(0.0, 5.0, 0.0),
+            sensor_rotation: Quat::IDENTITY,
+            ..ScanResult::default()
+        }
+    }
+
+    fn f32_at(record: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(record[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_point_cloud2_message_layout() {
+        let bytes = encode_point_cloud2(&sample_scan(), &PointCloud2Layout::velodyne(), "velodyne", 12.5).unwrap();
+        let mut r = CdrReader::new(&bytes);
+        assert_eq!((r.u32(), r.u32()), (12, 500_000_000));
+        assert_eq!(r.string(), "velodyne");
+        assert_eq!((r.u32(), r.u32()), (1, 2));
+        let field_count = r.u32();
+        let fields: Vec<(String, u32, u8, u32)> =
+            (0..field_count).map(|_| (r.string(), r.u32(), r.u8(), r.u32())).collect();
+        let names: Vec<&str> = fields.iter().map(|f| f.0.as_str()).collect();
+        assert_eq!(names, ["x", "y", "z", "intensity", "ring", "time"]);
+        assert_eq!(fields[4], ("ring".to_string(), 16, UINT16, 1));
+        assert_eq!(fields[5], ("time".to_string(), 18, FLOAT32, 1));
+        assert_eq!(r.u8(), 0, "is_bigendian");
+        assert_eq!((r.u32(), r.u32()), (22, 44));
+        let data = r.bytes();
+        assert_eq!(data.len(), 44);
+        assert_eq!(r.u8(), 0, "Organized scan with a miss is not dense");
+        assert_eq!(r.pos, bytes.len());
+
+        assert_eq!([f32_at(data, 0), f32_at(data, 4), f32_at(data, 8)], [3.0, 0.0, 0.0]);
+        assert_eq!(u16::from_le_bytes([data[16], data[17]]), 5);
+        assert_eq!(f32_at(data, 18), 0.025);
+        assert!(f32_at(&data[22..], 0).is_nan());
+    }
+
+    #[test]
+    fn test_point_cloud2_ouster_layout() {
+        let layout = PointCloud2Layout::ouster();
+        let bytes = encode_point_cloud2(&sample_scan(), &layout, "os_lidar", 0.0).unwrap();
+        // The point data is the last `width * point_step` bytes before `is_dense`.
+        let data = &bytes[bytes.len() - 1 - 2 * 48..bytes.len() - 1];
+        assert_eq!(u32::from_le_bytes(data[20..24].try_into().unwrap()), 25_000_000, "t is in nanoseconds");
+        assert_eq!(u16::from_le_bytes([data[24], data[25]]), 255);
+        assert_eq!(u16::from_le_bytes([data[26], data[27]]), 5);
+        assert_eq!(u32::from_le_bytes(data[32..36].try_into().unwrap()), 3000, "range is in millimetres");
+    }
+
+    #[test]
+    fn test_point_cloud2_layout_from_str() {
+        assert_eq!("velodyne".parse::<PointCloud2Layout>().unwrap(), PointCloud2Layout::velodyne());
+        let custom: PointCloud2Layout = "x, y, z, ring".parse().unwrap();
+        assert_eq!(custom.point_step, 14);
+        assert_eq!(custom.fields[3].offset, 12);
+        assert!("x,y,colour".parse::<PointCloud2Layout>().is_err());
+    }
+
+    #[test]
+    fn test_point_cloud2_rejects_invalid_layouts() {
+        let empty = PointCloud2Layout::packed(&[]);
+        let mut overflowing = PointCloud2Layout::velodyne();
+        overflowing.fields[5].offset = 20;
+        let mut wrapping = PointCloud2Layout::velodyne();
+        wrapping.fields[0].offset = u32::MAX;
+        for layout in [empty, overflowing, wrapping] {
+            assert!(layout.validate().is_err());
+            let err = encode_point_cloud2(&sample_scan(), &layout, "velodyne", 0.0).unwrap_err();
+            assert!(matches!(err, SimLidarError::Format(_)), "{err}");
+        }
+        PointCloud2Layout::ouster().validate().unwrap();
+    }
+}
@@ -0,0 +1,271 @@
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::bvh::Bvh;
+use crate::error::SimLidarError;
+use crate::heightfield::Heightfield;
+use crate::mesh::Mesh;
+use crate::primitive::Primitive;
+
+/// Environment geometry scanned by a [`Sensor`](crate::Sensor): a triangle
+/// mesh in a BVH, plus heightfields and analytic primitives.
+///
+/// ```
+/// use glam::Vec3;
+/// use sim_lidar_rs::{Pose, Primitive, Scene, Sensor, SensorConfig};
+///
+/// let mut scene = Scene::new();
+/// scene.add_primitive(Primitive::plane(Vec3::new(0.0, -1.5, 0.0), Vec3::Y)?);
+/// let mut sensor = Sensor::new(SensorConfig::vlp16())?;
+/// let scan = sensor.scan(&scene, &Pose::IDENTITY)?;
+/// assert!(scan.hit_count > 0);
+/// # Ok::<(), sim_lidar_rs::SimLidarError>(())
+/// ```
+#[derive(Default)]
+pub struct Scene {
+    /// `None` until geometry is loaded or added.
+    bvh: Option<Bvh>,
+    /// Object and label names of the last mesh loaded with [`Scene::load_mesh`].
+    object_names: Vec<String>,
+    label_names: Vec<String>,
+}
+
+impl Scene {
+    /// An empty scene.  Scanning it fails with
+    /// [`SimLidarError::MissingGeometry`] until geometry is added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A scene holding `mesh`, with its object ids, labels and names.
+    pub fn from_mesh(mesh: &Mesh) -> Result<Self, SimLidarError> {
+        let mut scene = Self::new();
+        scene.load_mesh(mesh)?;
+        Ok(scene)
+    }
+
+    /// Replace the triangle geometry, keeping object labels, heightfields and
+    /// primitives.  `vertices` and `indices` follow [`Bvh::build`].  On error
+    /// the previous geometry is kept.
+    pub fn load_geometry(&mut self, vertices: &[f32], indices: &[u32]) -> Result<(), SimLidarError> {
+        self.replace_bvh(Bvh::build(vertices, indices)?);
+        Ok(())
+    }
+
+    /// Like [`Scene::load_geometry`], with one object id per triangle.
+    pub fn load_geometry_with_object_ids(
+        &mut self,
+        vertices: &[f32],
+        indices: &[u32],
+        object_ids: &[u32],
+    ) -> Result<(), SimLidarError> {
+        self.replace_bvh(Bvh::build_with_object_ids(vertices, indices, object_ids)?);
+        Ok(())
+    }
+
+    /// Replace the triangle geometry with a mesh, with its object ids, labels
+    /// and names.  Heightfields and primitives are kept.  On error the
+    /// previous geometry is kept.
+    pub fn load_mesh(&mut self, mesh: &Mesh) -> Result<(), SimLidarError> {
+        let mut bvh = mesh.build_bvh()?;
+        if let Some(mut old) = self.bvh.take() {
+            bvh.take_analytic_geometry(&mut old);
+        }
+        self.bvh = Some(bvh);
+        self.object_names = mesh.object_names.clone();
+        self.label_names = mesh.label_names.clone();
+        Ok(())
+    }
+
+    /// Assign a semantic label to each object id (`labels[id]`).  Fails with
+    /// [`SimLidarError::MissingGeometry`] if the scene is empty; labels are
+    /// kept across later [`Scene::load_geometry`] calls.
+    pub fn set_object_labels(&mut self, labels: &[u32]) -> Result<(), SimLidarError> {
+        let bvh = self.bvh.as_mut().ok_or(SimLidarError::MissingGeometry)?;
+        bvh.set_object_labels(labels);
+        Ok(())
+    }
+
+    /// Add terrain next to the triangle geometry.
+    pub fn add_heightfield(&mut self, heightfield: Heightfield) {
+        self.bvh.get_or_insert_with(Bvh::empty).add_heightfield(heightfield);
+    }
+
+    /// Remove every heightfield.
+    pub fn clear_heightfields(&mut self) {
+        if let Some(bvh) = self.bvh.as_mut() {
+            bvh.heightfields.clear();
+        }
+    }
+
+    /// Add an analytic primitive next to the triangle geometry.
+    pub fn add_primitive(&mut self, primitive: Primitive) {
+        self.bvh.get_or_insert_with(Bvh::empty).add_primitive(primitive);
+    }
+
+    /// Remove every primitive.
+    pub fn clear_primitives(&mut self) {
+        if let Some(bvh) = self.bvh.as_mut() {
+            bvh.clear_primitives();
+        }
+    }
+
+    /// Name of each object, indexed by object id, for a mesh loaded with
+    /// [`Scene::load_mesh`] (empty otherwise).
+    pub fn object_names(&self) -> &[String] {
+        &self.object_names
+    }
+
+    /// Name of each semantic label, indexed by label, for a mesh loaded with
+    /// [`Scene::load_mesh`] (empty otherwise).  Label `0` is unlabeled.
+    pub fn label_names(&self) -> &[String] {
+        &self.label_names
+    }
+
+    /// The acceleration structure, or `None` if nothing has been loaded.
+    pub fn bvh(&self) -> Option<&Bvh> {
+        self.bvh.as_ref()
+    }
+
+    /// Install newly built triangles, keeping the current object labels,
+    /// heightfields and primitives.
+    fn replace_bvh(&mut self, mut bvh: Bvh) {
+        if let Some(mut old) = self.bvh.take() {
+            bvh.object_labels = std::mem::take(&mut old.object_labels);
+            bvh.take_analytic_geometry(&mut old);
+        }
+        self.bvh = Some(bvh);
+        self.object_names.clear();
+        self.label_names.clear();
+    }
+}
+
+/// A reference-counted handle to a [`Scene`], so several sensors or
+/// simulators share one BVH.  Clones refer to the same scene, and changes
+/// made through any of them are seen by all.
+///
+/// ```
+/// use glam::Vec3;
+/// use sim_lidar_rs::{Pose, Primitive, Scene, SceneHandle, Sensor, SensorConfig};
+///
+/// let scene = SceneHandle::new(Scene::new());
+/// let attached = scene.clone();
+/// scene.write().add_primitive(Primitive::sphere(Vec3::ZERO, 10.0)?);
+/// let mut sensor = Sensor::new(SensorConfig::vlp16())?;
+/// assert!(sensor.scan(&attached.read(), &Pose::IDENTITY)?.hit_count > 0);
+/// # Ok::<(), sim_lidar_rs::SimLidarError>(())
+/// ```
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Default)]
+pub struct SceneHandle(Arc<RwLock<Scene>>);
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SceneHandle {
+    /// A handle to a new, empty scene.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn empty() -> SceneHandle {
+        Self::default()
+    }
+}
+
+impl SceneHandle {
+    /// A handle owning `scene`.
+    pub fn new(scene: Scene) -> Self {
+        Self(Arc::new(RwLock::new(scene)))
+    }
+
+    /// Lock the scene for scanning.
+    pub fn read(&self) -> RwLockReadGuard<'_, Scene> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the scene for changes.
+    pub fn write(&self) -> RwLockWriteGuard<'_, Scene> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether both handles refer to the same scene.
+    pub fn ptr_eq(&self, other: &SceneHandle) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pose, Sensor, SensorConfig};
+    use glam::{Quat, Vec3};
+
+    fn ring_config() -> SensorConfig {
+        SensorConfig::new(360, 1, 0.0, 0.0, 0.1, 100.0, 0.0).unwrap()
+    }
+
+    #[test]
+    fn test_scene_scan_with_native_api() {
+        // A cylinder of radius 5 around the sensor, seen from inside.
+        let mut scene = Scene::new();
+        let mut sensor = Sensor::new(ring_config()).unwrap();
+        assert!(matches!(sensor.scan(&scene, &Pose::IDENTITY), Err(SimLidarError::MissingGeometry)));
+
+        scene.add_primitive(Primitive::cylinder(Vec3::ZERO, 5.0, 10.0, Quat::IDENTITY).unwrap().with_object_id(2));
+        scene.set_object_labels(&[0, 0, 7]).unwrap();
+        let pose = Pose::new(Vec3::new(1.0, 0.0, 0.0), Quat::from_rotation_y(0.3));
+        let scan = sensor.scan(&scene, &pose).unwrap();
+        assert_eq!(scan.hit_count, 360);
+        assert_eq!(scan.pose(), pose);
+        assert!(scan.points().all(|p| (Vec3::new(p.x, 0.0, p.z).length() - 5.0).abs() < 1e-3));
+        assert!(scan.labels.iter().all(|&l| l == 7));
+    }
+
+    #[test]
+    fn test_sensor_noise_seed_repeats_scan_sequence() {
+        let mut scene = Scene::new();
+        scene.add_primitive(Primitive::sphere(Vec3::ZERO, 20.0).unwrap());
+        let mut config = ring_config();
+        config.noise_stddev = 0.1;
+        let mut sensor = Sensor::new(config).unwrap();
+        let mut sequence = || {
+            sensor.set_noise_seed(Some(3));
+            let first = sensor.scan(&scene, &Pose::IDENTITY).unwrap().hits.clone();
+            let second = sensor.scan(&scene, &Pose::IDENTITY).unwrap().hits.clone();
+            (first, second)
+        };
+        let (a1, a2) = sequence();
+        let (b1, b2) = sequence();
+        assert_eq!((&a1, &a2), (&b1, &b2));
+        assert_ne!(a1, a2, "Consecutive scans get different noise");
+    }
+
+    #[test]
+    fn test_scene_handle_shares_updates() {
+        let scene = SceneHandle::empty();
+        let attached = scene.clone();
+        assert!(attached.ptr_eq(&scene) && !attached.ptr_eq(&SceneHandle::empty()));
+        let mut first = Sensor::new(ring_config()).unwrap();
+        let mut second = Sensor::new(ring_config()).unwrap();
+        assert!(first.scan(&attached.read(), &Pose::IDENTITY).is_err());
+
+        scene.write().add_primitive(Primitive::sphere(Vec3::ZERO, 10.0).unwrap());
+        assert_eq!(first.scan(&attached.read(), &Pose::IDENTITY).unwrap().hit_count, 360);
+        scene.write().clear_primitives();
+        scene.write().add_primitive(Primitive::sphere(Vec3::ZERO, 0.05).unwrap());
+        assert_eq!(second.scan(&scene.read(), &Pose::IDENTITY).unwrap().hit_count, 0, "Below min_range");
+        assert_eq!(first.scan(&attached.read(), &Pose::IDENTITY).unwrap().hit_count, 0);
+    }
+
+    #[test]
+    fn test_scene_keeps_analytic_geometry_across_loads() {
+        let mut scene = Scene::new();
+        scene.add_primitive(Primitive::plane(Vec3::new(0.0, -2.0, 0.0), Vec3::Y).unwrap());
+        scene.load_geometry(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0], &[0, 1, 2]).unwrap();
+        let mut mesh = Mesh::new();
+        mesh.add_object("wall", 0);
+        scene.load_mesh(&mesh).unwrap();
+        assert_eq!(scene.object_names(), ["wall"]);
+        assert_eq!(scene.bvh().unwrap().primitives().len(), 1);
+        assert!(scene.load_geometry(&[], &[0]).is_err());
+        assert_eq!(scene.object_names(), ["wall"], "Failed loads keep the scene");
+    }
+}
@@ -1,12 +1,23 @@
 use glam::Vec3;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+use crate::error::{ConfigIssue, SimLidarError};
+use crate::pose::Pose;
+use crate::raycaster::{self, Motion, ScanResult};
+use crate::scene::Scene;
+use crate::trajectory::{ScanMetadata, Trajectory, TrajectoryScan};
+
 /// Type alias for [`SensorConfig`]. Refers to the same sensor configuration struct.
 pub type LidarConfig = SensorConfig;
 
 /// Sensor configuration mirroring real-world LiDARs (e.g., Velodyne VLP-16, Ouster).
-#[wasm_bindgen]
+///
+/// With the `serde` feature enabled the configuration can be (de)serialized;
+/// optional fields may be omitted and take their [`SensorConfig::new`] defaults.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SensorConfig {
     /// Number of rays per full horizontal sweep (360°).
     pub horizontal_resolution: u32,
@@ -22,12 +33,44 @@ pub struct SensorConfig {
     pub max_range: f32,
     /// Standard deviation of Gaussian noise added to each hit distance (0 = no noise).
     pub noise_stddev: f32,
+    /// Report a radial (Doppler) velocity for every hit, as FMCW sensors do.
+    /// Disabled by default.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub doppler: bool,
+    /// Standard deviation of Gaussian noise added to each radial velocity in
+    /// m/s (0 = no noise).  Only used when `doppler` is enabled.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub velocity_noise_stddev: f32,
+    /// Emit one point per ray, laid out as a `vertical_channels` ×
+    /// `horizontal_resolution` grid, with `NaN` coordinates for misses.
+    /// Disabled by default (only valid hits are returned).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub organized: bool,
+    /// Rotation rate in Hz (full sweeps per second), used to timestamp each
+    /// point relative to the start of the scan.  Defaults to 10 Hz.
+    #[cfg_attr(feature = "serde", serde(default = "default_scan_frequency_hz"))]
+    pub scan_frequency_hz: f32,
+}
+
+/// Largest number of rays per scan accepted by [`SensorConfig::validate`]
+/// (about 200 MB of hit coordinates).
+pub const MAX_RAYS_PER_SCAN: u32 = 1 << 24;
+
+/// Default rotation rate of a spinning LiDAR.
+const DEFAULT_SCAN_FREQUENCY_HZ: f32 = 10.0;
+
+#[cfg(feature = "serde")]
+fn default_scan_frequency_hz() -> f32 {
+    DEFAULT_SCAN_FREQUENCY_HZ
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl SensorConfig {
     /// Create a new sensor configuration.
-    #[wasm_bindgen(constructor)]
+    ///
+    /// Throws an `InvalidConfig` error listing every invalid field (see
+    /// [`SensorConfig::validate`]).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(
         horizontal_resolution: u32,
         vertical_channels: u32,
@@ -36,8 +79,8 @@ impl SensorConfig {
         min_range: f32,
         max_range: f32,
         noise_stddev: f32,
-    ) -> SensorConfig {
-        SensorConfig {
+    ) -> Result<SensorConfig, SimLidarError> {
+        let config = SensorConfig::from_parts(
             horizontal_resolution,
             vertical_channels,
             vertical_fov_upper,
@@ -45,31 +88,135 @@ impl SensorConfig {
             min_range,
             max_range,
             noise_stddev,
-        }
+        );
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check every field and throw an `InvalidConfig` error listing all
+    /// invalid ones.  Useful after mutating fields directly from JavaScript.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = validate))]
+    pub fn validate_js(&self) -> Result<(), SimLidarError> {
+        self.validate()
     }
 
     /// Returns a preset matching the Velodyne VLP-16.
     pub fn vlp16() -> SensorConfig {
-        SensorConfig::new(1800, 16, 15.0, -15.0, 0.1, 100.0, 0.0)
+        SensorConfig::from_parts(1800, 16, 15.0, -15.0, 0.1, 100.0, 0.0)
     }
 
     /// Returns a preset matching the Ouster OS1-32.
     pub fn ouster_os1_32() -> SensorConfig {
-        SensorConfig::new(1024, 32, 22.5, -22.5, 0.1, 120.0, 0.0)
+        SensorConfig::from_parts(1024, 32, 22.5, -22.5, 0.1, 120.0, 0.0)
     }
 
     /// Returns a preset matching the Ouster OS1-64.
     pub fn ouster_os1_64() -> SensorConfig {
-        SensorConfig::new(2048, 64, 22.5, -22.5, 0.1, 120.0, 0.0)
+        SensorConfig::from_parts(2048, 64, 22.5, -22.5, 0.1, 120.0, 0.0)
     }
 
-    /// Total number of rays fired per scan.
+    /// Total number of rays fired per scan (saturating for invalid
+    /// configurations; [`SensorConfig::validate`] caps it at
+    /// [`MAX_RAYS_PER_SCAN`]).
     pub fn total_rays(&self) -> u32 {
-        self.horizontal_resolution * self.vertical_channels
+        self.horizontal_resolution.saturating_mul(self.vertical_channels)
     }
 }
 
 impl SensorConfig {
+    /// Whether scans draw any range or velocity noise.
+    pub(crate) fn is_noisy(&self) -> bool {
+        self.noise_stddev != 0.0 || (self.doppler && self.velocity_noise_stddev != 0.0)
+    }
+
+    /// Build a configuration without validating it.  Only used for presets
+    /// and by [`SensorConfig::new`], which validates the result.
+    fn from_parts(
+        horizontal_resolution: u32,
+        vertical_channels: u32,
+        vertical_fov_upper: f32,
+        vertical_fov_lower: f32,
+        min_range: f32,
+        max_range: f32,
+        noise_stddev: f32,
+    ) -> SensorConfig {
+        SensorConfig {
+            horizontal_resolution,
+            vertical_channels,
+            vertical_fov_upper,
+            vertical_fov_lower,
+            min_range,
+            max_range,
+            noise_stddev,
+            doppler: false,
+            velocity_noise_stddev: 0.0,
+            organized: false,
+            scan_frequency_hz: DEFAULT_SCAN_FREQUENCY_HZ,
+        }
+    }
+
+    /// Check every field and return an [`SimLidarError::InvalidConfig`]
+    /// listing all invalid ones, or `Ok(())` if the configuration is usable.
+    pub fn validate(&self) -> Result<(), SimLidarError> {
+        let mut issues = Vec::new();
+
+        if self.horizontal_resolution == 0 {
+            issues.push(ConfigIssue::new("horizontal_resolution", "must be > 0"));
+        }
+        if self.vertical_channels == 0 {
+            issues.push(ConfigIssue::new("vertical_channels", "must be > 0"));
+        }
+        match self.horizontal_resolution.checked_mul(self.vertical_channels) {
+            Some(total) if total <= MAX_RAYS_PER_SCAN => {}
+            _ => issues.push(ConfigIssue::new(
+                "horizontal_resolution",
+                format!(
+                    "horizontal_resolution × vertical_channels must be <= {MAX_RAYS_PER_SCAN}, got {} × {}",
+                    self.horizontal_resolution, self.vertical_channels
+                ),
+            )),
+        }
+
+        let upper_ok = check_angle(&mut issues, "vertical_fov_upper", self.vertical_fov_upper);
+        let lower_ok = check_angle(&mut issues, "vertical_fov_lower", self.vertical_fov_lower);
+        if upper_ok && lower_ok && self.vertical_fov_lower > self.vertical_fov_upper {
+            issues.push(ConfigIssue::new(
+                "vertical_fov_lower",
+                format!(
+                    "must be <= vertical_fov_upper ({}), got {}",
+                    self.vertical_fov_upper, self.vertical_fov_lower
+                ),
+            ));
+        }
+
+        let min_ok = check_non_negative(&mut issues, "min_range", self.min_range);
+        let max_ok = check_non_negative(&mut issues, "max_range", self.max_range);
+        if max_ok && self.max_range == 0.0 {
+            issues.push(ConfigIssue::new("max_range", "must be > 0"));
+        }
+        if min_ok && max_ok && self.min_range > self.max_range {
+            issues.push(ConfigIssue::new(
+                "min_range",
+                format!("must be <= max_range ({}), got {}", self.max_range, self.min_range),
+            ));
+        }
+
+        check_non_negative(&mut issues, "noise_stddev", self.noise_stddev);
+        check_non_negative(&mut issues, "velocity_noise_stddev", self.velocity_noise_stddev);
+        if !(self.scan_frequency_hz.is_finite() && self.scan_frequency_hz > 0.0) {
+            issues.push(ConfigIssue::new(
+                "scan_frequency_hz",
+                format!("must be finite and > 0, got {}", self.scan_frequency_hz),
+            ));
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(SimLidarError::InvalidConfig(issues))
+        }
+    }
+
     /// Generate all sensor-local ray directions for a full scan.
     ///
     /// Returns unit vectors in the sensor's own coordinate frame, with no
@@ -78,7 +225,7 @@ impl SensorConfig {
     ///
     /// [`generate_ray_directions`]: SensorConfig::generate_ray_directions
     pub fn generate_local_ray_directions(&self) -> Vec<Vec3> {
-        let total = (self.horizontal_resolution * self.vertical_channels) as usize;
+        let total = self.total_rays() as usize;
         let mut directions = Vec::with_capacity(total);
 
         let v_step = if self.vertical_channels > 1 {
@@ -123,13 +270,368 @@ impl SensorConfig {
     }
 }
 
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SensorConfig {
+    /// Parse and validate a configuration from a JSON document.
+    ///
+    /// Throws a `ConfigParse` error for malformed JSON or an `InvalidConfig`
+    /// error if the parsed values are invalid.
+    pub fn from_json(json: &str) -> Result<SensorConfig, SimLidarError> {
+        let config: SensorConfig = serde_json::from_str(json).map_err(|e| SimLidarError::ConfigParse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Serialize this configuration as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, SimLidarError> {
+        serde_json::to_string_pretty(self).map_err(|e| SimLidarError::ConfigSerialize(e.to_string()))
+    }
+}
+
+#[cfg(feature = "toml")]
+impl SensorConfig {
+    /// Parse and validate a configuration from a TOML document.
+    pub fn from_toml(toml: &str) -> Result<SensorConfig, SimLidarError> {
+        let config: SensorConfig = toml::from_str(toml).map_err(|e| SimLidarError::ConfigParse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Serialize this configuration as TOML.
+    pub fn to_toml(&self) -> Result<String, SimLidarError> {
+        toml::to_string_pretty(self).map_err(|e| SimLidarError::ConfigSerialize(e.to_string()))
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl SensorConfig {
+    /// Parse and validate a configuration from a YAML document.
+    pub fn from_yaml(yaml: &str) -> Result<SensorConfig, SimLidarError> {
+        let config: SensorConfig = serde_yaml::from_str(yaml).map_err(|e| SimLidarError::ConfigParse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Serialize this configuration as YAML.
+    pub fn to_yaml(&self) -> Result<String, SimLidarError> {
+        serde_yaml::to_string(self).map_err(|e| SimLidarError::ConfigSerialize(e.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SensorConfig {
+    /// Load and validate a configuration file, choosing the format from the
+    /// file extension (`.json`, `.toml`, `.yaml` / `.yml`).
+    ///
+    /// TOML and YAML require the `toml` and `yaml` features respectively.
+    /// Fails with [`SimLidarError::Format`] naming the extension if it is not
+    /// supported, before touching the file.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<SensorConfig, SimLidarError> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)?;
+        let text = std::fs::read_to_string(path)?;
+        match format {
+            ConfigFormat::Json => SensorConfig::from_json(&text),
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => SensorConfig::from_toml(&text),
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => SensorConfig::from_yaml(&text),
+        }
+    }
+
+    /// Save this configuration to a file, choosing the format from the file
+    /// extension and failing on an unsupported one as in
+    /// [`SensorConfig::load`].
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), SimLidarError> {
+        let path = path.as_ref();
+        let text = match ConfigFormat::from_path(path)? {
+            ConfigFormat::Json => self.to_json()?,
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => self.to_toml()?,
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => self.to_yaml()?,
+        };
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Serialization formats supported by [`SensorConfig::load`] / [`SensorConfig::save`].
+#[cfg(feature = "serde")]
+enum ConfigFormat {
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+#[cfg(feature = "serde")]
+impl ConfigFormat {
+    fn from_path(path: &std::path::Path) -> Result<ConfigFormat, SimLidarError> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match ext.to_ascii_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            #[cfg(feature = "toml")]
+            "toml" => Ok(ConfigFormat::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            _ => Err(SimLidarError::Format(format!("unsupported config file extension {ext:?}"))),
+        }
+    }
+}
+
+/// A LiDAR sensor: its configuration, its motion for Doppler output, and the
+/// output buffers re-used by every scan.
+///
+/// Buffers are reserved for a full scan when the sensor is created, so
+/// scanning does not allocate.  See [`Scene`] for an example.
+pub struct Sensor {
+    config: SensorConfig,
+    motion: Motion,
+    output: ScanResult,
+    /// Sensor-local ray directions of `config`, rotated by the pose of each
+    /// scan while casting.
+    directions: Vec<Vec3>,
+    /// Seed for reproducible noise, and the number of scans taken with it.
+    noise_seed: Option<u64>,
+    seeded_scans: u64,
+}
+
+impl Sensor {
+    /// Create a sensor, failing with [`SimLidarError::InvalidConfig`] if
+    /// `config` is invalid.
+    pub fn new(config: SensorConfig) -> Result<Self, SimLidarError> {
+        config.validate()?;
+        let output = ScanResult::with_capacity(config.total_rays() as usize);
+        Ok(Self {
+            directions: config.generate_local_ray_directions(),
+            config,
+            motion: Motion::default(),
+            output,
+            noise_seed: None,
+            seeded_scans: 0,
+        })
+    }
+
+    /// The sensor configuration.
+    pub fn config(&self) -> &SensorConfig {
+        &self.config
+    }
+
+    /// Replace the configuration, growing the output buffers if it fires
+    /// more rays and recomputing the ray direction table.  An invalid
+    /// `config` is rejected and the current one kept.
+    pub fn set_config(&mut self, config: SensorConfig) -> Result<(), SimLidarError> {
+        config.validate()?;
+        self.output.clear();
+        self.output.reserve(config.total_rays() as usize, true);
+        self.directions = config.generate_local_ray_directions();
+        self.config = config;
+        Ok(())
+    }
+
+    /// Sensor and object velocities used for Doppler output.
+    pub fn motion(&self) -> &Motion {
+        &self.motion
+    }
+
+    /// Mutable access to the velocities used for Doppler output.
+    pub fn motion_mut(&mut self) -> &mut Motion {
+        &mut self.motion
+    }
+
+    /// Make noise reproducible: the `n`-th scan after this call draws its
+    /// noise from `seed` and `n`, whatever the thread count.  `None` draws a
+    /// fresh random seed for every scan (the default).
+    pub fn set_noise_seed(&mut self, seed: Option<u64>) {
+        self.noise_seed = seed;
+        self.seeded_scans = 0;
+    }
+
+    /// Scan `scene` from `pose`, replacing the previous result.  The ray
+    /// directions are computed once per configuration, so repeated scans
+    /// only write into the reused output buffers.
+    ///
+    /// Fails with [`SimLidarError::MissingGeometry`] if the scene is empty, or
+    /// [`SimLidarError::InvalidConfig`] if the noise settings are unusable.
+    pub fn scan(&mut self, scene: &Scene, pose: &Pose) -> Result<&ScanResult, SimLidarError> {
+        let bvh = scene.bvh().ok_or(SimLidarError::MissingGeometry)?;
+        let seed = match self.noise_seed {
+            Some(seed) => {
+                self.seeded_scans += 1;
+                raycaster::mix_seed(seed ^ raycaster::mix_seed(self.seeded_scans - 1))
+            }
+            None if self.config.is_noisy() => rand::random(),
+            None => 0,
+        };
+        raycaster::scan_directions_into(bvh, &self.config, &self.directions, pose, &self.motion, seed, &mut self.output)?;
+        Ok(&self.output)
+    }
+
+    /// The result of the last scan (empty before the first one).
+    pub fn last_scan(&self) -> &ScanResult {
+        &self.output
+    }
+
+    /// Scan `scene` along `trajectory` at `rate_hz` scans per second (see
+    /// [`Trajectory::scan_times`]) and return every scan with its metadata.
+    ///
+    /// Fails like [`Sensor::scan`], or with [`SimLidarError::InvalidArgument`]
+    /// for a bad `rate_hz`.
+    pub fn scan_trajectory(&mut self, scene: &Scene, trajectory: &Trajectory, rate_hz: f64) -> Result<Vec<TrajectoryScan>, SimLidarError> {
+        let mut scans = Vec::new();
+        self.scan_trajectory_with(scene, trajectory, rate_hz, |metadata, scan| {
+            scans.push(TrajectoryScan {
+                metadata: *metadata,
+                scan: scan.clone(),
+            });
+            Ok(())
+        })?;
+        Ok(scans)
+    }
+
+    /// Like [`Sensor::scan_trajectory`], streaming each scan to `sink` as it
+    /// is taken instead of keeping it, e.g. into a writer:
+    ///
+    /// ```no_run
+    /// # use sim_lidar_rs::{McapOptions, McapWriter, Scene, Sensor, Trajectory};
+    /// # fn record(sensor: &mut Sensor, scene: &Scene, trajectory: &Trajectory) -> Result<(), sim_lidar_rs::SimLidarError> {
+    /// let mut mcap = McapWriter::new(std::fs::File::create("drive.mcap")?, McapOptions::default())?;
+    /// sensor.scan_trajectory_with(scene, trajectory, 10.0, |metadata, scan| mcap.write_scan(scan, metadata.timestamp))?;
+    /// mcap.finish()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Returns the number of scans; an error from `sink` stops the batch.
+    pub fn scan_trajectory_with<F>(&mut self, scene: &Scene, trajectory: &Trajectory, rate_hz: f64, mut sink: F) -> Result<usize, SimLidarError>
+    where
+        F: FnMut(&ScanMetadata, &ScanResult) -> Result<(), SimLidarError>,
+    {
+        let mut count = 0;
+        for (index, timestamp) in trajectory.scan_times(rate_hz)?.enumerate() {
+            let pose = trajectory.pose_at(timestamp).ok_or_else(|| {
+                SimLidarError::Format(format!("trajectory: no pose at {timestamp}"))
+            })?;
+            let scan = self.scan(scene, &pose)?;
+            sink(&ScanMetadata { index, timestamp, pose }, scan)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Record an issue unless `value` is a finite elevation angle in [-90°, 90°].
+fn check_angle(issues: &mut Vec<ConfigIssue>, field: &'static str, value: f32) -> bool {
+    if !value.is_finite() || !(-90.0..=90.0).contains(&value) {
+        issues.push(ConfigIssue::new(field, format!("must be within [-90, 90] degrees, got {value}")));
+        return false;
+    }
+    true
+}
+
+/// Record an issue unless `value` is finite and non-negative.
+fn check_non_negative(issues: &mut Vec<ConfigIssue>, field: &'static str, value: f32) -> bool {
+    if !value.is_finite() || value < 0.0 {
+        issues.push(ConfigIssue::new(field, format!("must be finite and >= 0, got {value}")));
+        return false;
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    #[allow(deprecated)]
+    fn test_sensor_cached_directions_match_free_scan() {
+        use crate::{Pose, Primitive, Scene};
+        use glam::Quat;
+        let mut scene = Scene::new();
+        scene.add_primitive(Primitive::cuboid(Vec3::ZERO, Vec3::new(8.0, 3.0, 6.0), Quat::IDENTITY).unwrap());
+        let pose = Pose::new(Vec3::new(1.0, 0.5, -2.0), Quat::from_euler(glam::EulerRot::YXZ, 0.7, 0.2, -0.1));
+        let ring = SensorConfig::new(360, 1, 0.0, 0.0, 0.1, 100.0, 0.0).unwrap();
+        let mut sensor = Sensor::new(ring.clone()).unwrap();
+        let mut expected = ScanResult::default();
+        for config in [SensorConfig::vlp16(), ring] {
+            sensor.set_config(config.clone()).unwrap();
+            crate::scan_into(scene.bvh().unwrap(), &config, pose.position, pose.rotation, &Motion::default(), &mut expected).unwrap();
+            let scan = sensor.scan(&scene, &pose).unwrap();
+            assert_eq!(scan.hits, expected.hits);
+            assert_eq!(scan.intensities, expected.intensities);
+        }
+
+        // Later scans reuse the output buffers.
+        let buffer = sensor.last_scan().hits.as_ptr();
+        sensor.scan(&scene, &Pose::IDENTITY).unwrap();
+        assert_eq!(sensor.last_scan().hits.as_ptr(), buffer);
+    }
+
+    #[test]
+    fn test_sensor_set_config_reserves_every_buffer() {
+        let mut sensor = Sensor::new(SensorConfig::new(8, 1, 0.0, 0.0, 0.1, 100.0, 0.0).unwrap()).unwrap();
+        let config = SensorConfig::vlp16();
+        let total = config.total_rays() as usize;
+        sensor.set_config(config).unwrap();
+        let out = sensor.last_scan();
+        assert!(out.hits.capacity() >= total * 3);
+        for capacity in [
+            out.velocities.capacity(),
+            out.rings.capacity(),
+            out.intensities.capacity(),
+            out.times.capacity(),
+            out.labels.capacity(),
+            out.object_ids.capacity(),
+        ] {
+            assert!(capacity >= total);
+        }
+    }
+
+    #[test]
+    fn test_sensor_scans_trajectory() {
+        use crate::{Pose, Primitive, Scene, Trajectory};
+        use glam::Quat;
+        let mut scene = Scene::new();
+        scene.add_primitive(Primitive::sphere(Vec3::ZERO, 30.0).unwrap());
+        let mut trajectory = Trajectory::new();
+        trajectory.push(0.0, Pose::IDENTITY).unwrap();
+        trajectory.push(1.0, Pose::new(Vec3::new(10.0, 0.0, 0.0), Quat::from_rotation_y(0.5))).unwrap();
+        let mut sensor = Sensor::new(SensorConfig::new(360, 1, 0.0, 0.0, 0.1, 100.0, 0.0).unwrap()).unwrap();
+
+        let scans = sensor.scan_trajectory(&scene, &trajectory, 4.0).unwrap();
+        assert_eq!(scans.len(), 5);
+        for (i, frame) in scans.iter().enumerate() {
+            assert_eq!(frame.metadata.index, i);
+            assert_eq!(frame.metadata.timestamp, i as f64 * 0.25);
+            assert_eq!(frame.scan.pose(), frame.metadata.pose);
+            assert_eq!(frame.scan.hit_count, 360);
+        }
+        assert!((scans[2].metadata.pose.position - Vec3::new(5.0, 0.0, 0.0)).length() < 1e-5);
+
+        let mut streamed = Vec::new();
+        let count = sensor
+            .scan_trajectory_with(&scene, &trajectory, 4.0, |metadata, scan| {
+                streamed.push((metadata.timestamp, scan.hits.clone()));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!((count, streamed[4].0), (5, 1.0));
+        assert_eq!(streamed[4].1, scans[4].scan.hits);
+
+        let mut calls = 0;
+        let stop = sensor.scan_trajectory_with(&scene, &trajectory, 4.0, |_, _| {
+            calls += 1;
+            Err(SimLidarError::Io("disk full".into()))
+        });
+        assert!(matches!(stop, Err(SimLidarError::Io(_))) && calls == 1);
+    }
+
     #[test]
     fn test_sensor_config_total_rays() {
-        let cfg = SensorConfig::new(1800, 16, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg = SensorConfig::new(1800, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         assert_eq!(cfg.total_rays(), 1800 * 16);
     }
 
@@ -146,14 +648,14 @@ mod tests {
 
     #[test]
     fn test_generate_ray_directions_count() {
-        let cfg = SensorConfig::new(360, 8, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg = SensorConfig::new(360, 8, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         let dirs = cfg.generate_ray_directions(glam::Quat::IDENTITY);
         assert_eq!(dirs.len(), (360 * 8) as usize);
     }
 
     #[test]
     fn test_generate_ray_directions_unit_length() {
-        let cfg = SensorConfig::new(36, 4, 10.0, -10.0, 0.1, 50.0, 0.0);
+        let cfg = SensorConfig::new(36, 4, 10.0, -10.0, 0.1, 50.0, 0.0).unwrap();
         let dirs = cfg.generate_ray_directions(glam::Quat::IDENTITY);
         for d in &dirs {
             let len = d.length();
@@ -166,7 +668,7 @@ mod tests {
     /// `LidarConfig` is a type alias for `SensorConfig`; use it interchangeably.
     #[test]
     fn test_lidar_config_alias() {
-        let cfg: LidarConfig = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg: LidarConfig = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         assert_eq!(cfg.vertical_channels, 16);
         assert_eq!(cfg.horizontal_resolution, 360);
     }
@@ -175,7 +677,7 @@ mod tests {
     /// direction vectors.
     #[test]
     fn test_generate_local_ray_directions_16ch_360deg_count() {
-        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         let dirs = cfg.generate_local_ray_directions();
         assert_eq!(dirs.len(), 360 * 16, "Expected 5760 local ray directions");
     }
@@ -184,7 +686,7 @@ mod tests {
     /// and point toward +X (X > 0).
     #[test]
     fn test_generate_local_ray_0deg_horizontal() {
-        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         let dirs = cfg.generate_local_ray_directions();
         // Channel v=0, azimuth h=0 → index 0
         let dir = dirs[0];
@@ -196,7 +698,7 @@ mod tests {
     /// component equal to sin(vertical_fov_upper).
     #[test]
     fn test_generate_local_ray_max_vertical_fov() {
-        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         let dirs = cfg.generate_local_ray_directions();
         // Channel v=15 (fov_upper = 15°), azimuth h=0 → index 15 * 360 + 0
         let dir = dirs[15 * 360];
@@ -214,7 +716,7 @@ mod tests {
     /// direction produced by `generate_local_ray_directions`.
     #[test]
     fn test_generate_ray_directions_applies_rotation() {
-        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0);
+        let cfg = LidarConfig::new(360, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap();
         // 180° rotation around Y-axis flips X → -X and Z → -Z
         let rot = glam::Quat::from_rotation_y(std::f32::consts::PI);
         let local_dirs = cfg.generate_local_ray_directions();
@@ -235,4 +737,150 @@ mod tests {
             );
         }
     }
+
+    // ── Validation ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_presets_are_valid() {
+        for cfg in [SensorConfig::vlp16(), SensorConfig::ouster_os1_32(), SensorConfig::ouster_os1_64()] {
+            assert_eq!(cfg.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_config() {
+        let err = SensorConfig::new(0, 16, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap_err();
+        assert_eq!(
+            err,
+            SimLidarError::InvalidConfig(vec![ConfigIssue::new("horizontal_resolution", "must be > 0")])
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_too_many_rays() {
+        let err = SensorConfig::new(1 << 20, 1 << 13, 15.0, -15.0, 0.1, 100.0, 0.0).unwrap_err();
+        assert!(matches!(&err, SimLidarError::InvalidConfig(issues) if issues.len() == 1 && issues[0].field == "horizontal_resolution"));
+        assert!(SensorConfig::new(1 << 12, 1 << 13, 15.0, -15.0, 0.1, 100.0, 0.0).is_err(), "Above the cap");
+        assert!(SensorConfig::new(1 << 12, 1 << 12, 15.0, -15.0, 0.1, 100.0, 0.0).is_ok());
+        let mut cfg = SensorConfig::vlp16();
+        cfg.horizontal_resolution = u32::MAX;
+        assert_eq!(cfg.total_rays(), u32::MAX, "Saturates instead of panicking");
+    }
+
+    #[test]
+    fn test_validate_reports_every_invalid_field() {
+        let mut cfg = SensorConfig::vlp16();
+        cfg.horizontal_resolution = 0;
+        cfg.vertical_fov_lower = 20.0;
+        cfg.min_range = 200.0;
+        cfg.noise_stddev = -0.5;
+        cfg.velocity_noise_stddev = f32::NAN;
+        cfg.scan_frequency_hz = 0.0;
+        let Err(SimLidarError::InvalidConfig(issues)) = cfg.validate() else {
+            panic!("expected InvalidConfig");
+        };
+        let fields: Vec<&str> = issues.iter().map(|i| i.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "horizontal_resolution",
+                "vertical_fov_lower",
+                "min_range",
+                "noise_stddev",
+                "velocity_noise_stddev",
+                "scan_frequency_hz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_out_of_range_fov_and_zero_max_range() {
+        let mut cfg = SensorConfig::vlp16();
+        cfg.vertical_fov_upper = 95.0;
+        cfg.min_range = 0.0;
+        cfg.max_range = 0.0;
+        let Err(SimLidarError::InvalidConfig(issues)) = cfg.validate() else {
+            panic!("expected InvalidConfig");
+        };
+        let fields: Vec<&str> = issues.iter().map(|i| i.field).collect();
+        assert_eq!(fields, vec!["vertical_fov_upper", "max_range"]);
+    }
+
+    // ── Serialization ──────────────────────────────────────────────────────
+
+    #[cfg(feature = "serde")]
+    fn doppler_os1_64() -> SensorConfig {
+        let mut cfg = SensorConfig::ouster_os1_64();
+        cfg.noise_stddev = 0.02;
+        cfg.doppler = true;
+        cfg.velocity_noise_stddev = 0.05;
+        cfg.organized = true;
+        cfg.scan_frequency_hz = 20.0;
+        cfg
+    }
+
+    #[cfg(feature = "serde")]
+    fn assert_same(a: &SensorConfig, b: &SensorConfig) {
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let cfg = doppler_os1_64();
+        let parsed = SensorConfig::from_json(&cfg.to_json().unwrap()).unwrap();
+        assert_same(&cfg, &parsed);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_json_defaults_optional_fields() {
+        let json = r#"{
+            "horizontal_resolution": 1800, "vertical_channels": 16,
+            "vertical_fov_upper": 15.0, "vertical_fov_lower": -15.0,
+            "min_range": 0.1, "max_range": 100.0, "noise_stddev": 0.0
+        }"#;
+        assert_same(&SensorConfig::from_json(json).unwrap(), &SensorConfig::vlp16());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_json_validates_and_reports_parse_errors() {
+        let mut cfg = SensorConfig::vlp16();
+        cfg.min_range = 500.0;
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert!(matches!(SensorConfig::from_json(&json), Err(SimLidarError::InvalidConfig(_))));
+        assert!(matches!(SensorConfig::from_json("{"), Err(SimLidarError::ConfigParse(_))));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_round_trip() {
+        let cfg = doppler_os1_64();
+        let parsed = SensorConfig::from_toml(&cfg.to_toml().unwrap()).unwrap();
+        assert_same(&cfg, &parsed);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_round_trip() {
+        let cfg = doppler_os1_64();
+        let parsed = SensorConfig::from_yaml(&cfg.to_yaml().unwrap()).unwrap();
+        assert_same(&cfg, &parsed);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_save_and_load_by_extension() {
+        let path = std::env::temp_dir().join(format!("sim_lidar_cfg_{}.json", std::process::id()));
+        let cfg = doppler_os1_64();
+        cfg.save(&path).unwrap();
+        let loaded = SensorConfig::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_same(&cfg, &loaded);
+        for result in [cfg.save("config.ini"), SensorConfig::load("missing.ini").map(drop)] {
+            assert!(matches!(result, Err(SimLidarError::Format(reason)) if reason.contains("\"ini\"")));
+        }
+        assert!(matches!(SensorConfig::load("missing.json"), Err(SimLidarError::Io(_))));
+    }
 }
@@ -0,0 +1,243 @@
+//! Four-lane `f32` vectors for ray packet traversal: SSE2 on x86_64, SIMD128
+//! on Wasm built with `+simd128`, plain arrays elsewhere.
+//!
+//! `min` and `max` follow [`f32::min`] and [`f32::max`] (a `NaN` operand
+//! yields the other one), so packet results match the scalar code exactly.
+
+#[cfg(target_arch = "x86_64")]
+mod imp {
+    use std::arch::x86_64::*;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(__m128);
+
+    impl F32x4 {
+        pub fn splat(v: f32) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { Self(_mm_set1_ps(v)) }
+        }
+
+        pub fn from_array(v: [f32; 4]) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline; `v` holds 4 floats.
+            unsafe { Self(_mm_loadu_ps(v.as_ptr())) }
+        }
+
+        pub fn to_array(self) -> [f32; 4] {
+            let mut v = [0.0; 4];
+            // SAFETY: SSE2 is part of the x86_64 baseline; `v` holds 4 floats.
+            unsafe { _mm_storeu_ps(v.as_mut_ptr(), self.0) };
+            v
+        }
+
+        pub fn add(self, rhs: Self) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { Self(_mm_add_ps(self.0, rhs.0)) }
+        }
+
+        pub fn sub(self, rhs: Self) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { Self(_mm_sub_ps(self.0, rhs.0)) }
+        }
+
+        pub fn mul(self, rhs: Self) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { Self(_mm_mul_ps(self.0, rhs.0)) }
+        }
+
+        pub fn div(self, rhs: Self) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { Self(_mm_div_ps(self.0, rhs.0)) }
+        }
+
+        pub fn min(self, rhs: Self) -> Self {
+            // `minps` returns its second operand when either is NaN; keep
+            // `self` where `rhs` is NaN instead.
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe {
+                let min = _mm_min_ps(self.0, rhs.0);
+                let rhs_nan = _mm_cmpunord_ps(rhs.0, rhs.0);
+                Self(_mm_or_ps(_mm_and_ps(rhs_nan, self.0), _mm_andnot_ps(rhs_nan, min)))
+            }
+        }
+
+        pub fn max(self, rhs: Self) -> Self {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe {
+                let max = _mm_max_ps(self.0, rhs.0);
+                let rhs_nan = _mm_cmpunord_ps(rhs.0, rhs.0);
+                Self(_mm_or_ps(_mm_and_ps(rhs_nan, self.0), _mm_andnot_ps(rhs_nan, max)))
+            }
+        }
+
+        /// Bit `i` is set where lane `i` of `self <= rhs` (false for NaN).
+        pub fn le(self, rhs: Self) -> u32 {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { _mm_movemask_ps(_mm_cmple_ps(self.0, rhs.0)) as u32 }
+        }
+
+        /// Bit `i` is set where lane `i` of `self < rhs` (false for NaN).
+        pub fn lt(self, rhs: Self) -> u32 {
+            // SAFETY: SSE2 is part of the x86_64 baseline.
+            unsafe { _mm_movemask_ps(_mm_cmplt_ps(self.0, rhs.0)) as u32 }
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod imp {
+    use std::arch::wasm32::*;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(v128);
+
+    impl F32x4 {
+        pub fn splat(v: f32) -> Self {
+            Self(f32x4_splat(v))
+        }
+
+        pub fn from_array(v: [f32; 4]) -> Self {
+            Self(f32x4(v[0], v[1], v[2], v[3]))
+        }
+
+        pub fn to_array(self) -> [f32; 4] {
+            [
+                f32x4_extract_lane::<0>(self.0),
+                f32x4_extract_lane::<1>(self.0),
+                f32x4_extract_lane::<2>(self.0),
+                f32x4_extract_lane::<3>(self.0),
+            ]
+        }
+
+        pub fn add(self, rhs: Self) -> Self {
+            Self(f32x4_add(self.0, rhs.0))
+        }
+
+        pub fn sub(self, rhs: Self) -> Self {
+            Self(f32x4_sub(self.0, rhs.0))
+        }
+
+        pub fn mul(self, rhs: Self) -> Self {
+            Self(f32x4_mul(self.0, rhs.0))
+        }
+
+        pub fn div(self, rhs: Self) -> Self {
+            Self(f32x4_div(self.0, rhs.0))
+        }
+
+        pub fn min(self, rhs: Self) -> Self {
+            // `pmin` keeps `self` unless `rhs < self`, so only a NaN `self`
+            // needs fixing up.
+            let min = f32x4_pmin(self.0, rhs.0);
+            Self(v128_bitselect(rhs.0, min, f32x4_ne(self.0, self.0)))
+        }
+
+        pub fn max(self, rhs: Self) -> Self {
+            let max = f32x4_pmax(self.0, rhs.0);
+            Self(v128_bitselect(rhs.0, max, f32x4_ne(self.0, self.0)))
+        }
+
+        /// Bit `i` is set where lane `i` of `self <= rhs` (false for NaN).
+        pub fn le(self, rhs: Self) -> u32 {
+            i32x4_bitmask(f32x4_le(self.0, rhs.0)) as u32
+        }
+
+        /// Bit `i` is set where lane `i` of `self < rhs` (false for NaN).
+        pub fn lt(self, rhs: Self) -> u32 {
+            i32x4_bitmask(f32x4_lt(self.0, rhs.0)) as u32
+        }
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", all(target_arch = "wasm32", target_feature = "simd128"))))]
+mod imp {
+    #[derive(Clone, Copy)]
+    pub struct F32x4([f32; 4]);
+
+    impl F32x4 {
+        pub fn splat(v: f32) -> Self {
+            Self([v; 4])
+        }
+
+        pub fn from_array(v: [f32; 4]) -> Self {
+            Self(v)
+        }
+
+        pub fn to_array(self) -> [f32; 4] {
+            self.0
+        }
+
+        pub fn add(self, rhs: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+        }
+
+        pub fn sub(self, rhs: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] - rhs.0[i]))
+        }
+
+        pub fn mul(self, rhs: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] * rhs.0[i]))
+        }
+
+        pub fn div(self, rhs: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i] / rhs.0[i]))
+        }
+
+        pub fn min(self, rhs: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i].min(rhs.0[i])))
+        }
+
+        pub fn max(self, rhs: Self) -> Self {
+            Self(std::array::from_fn(|i| self.0[i].max(rhs.0[i])))
+        }
+
+        /// Bit `i` is set where lane `i` of `self <= rhs` (false for NaN).
+        pub fn le(self, rhs: Self) -> u32 {
+            (0..4).filter(|&i| self.0[i] <= rhs.0[i]).fold(0, |mask, i| mask | 1 << i)
+        }
+
+        /// Bit `i` is set where lane `i` of `self < rhs` (false for NaN).
+        pub fn lt(self, rhs: Self) -> u32 {
+            (0..4).filter(|&i| self.0[i] < rhs.0[i]).fold(0, |mask, i| mask | 1 << i)
+        }
+    }
+}
+
+pub(crate) use imp::F32x4;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simd_min_max_match_scalar_nan_semantics() {
+        let a = [1.0, f32::NAN, 3.0, f32::NAN];
+        let b = [2.0, 5.0, f32::NAN, f32::NAN];
+        let (va, vb) = (F32x4::from_array(a), F32x4::from_array(b));
+        for (op, scalar) in [(F32x4::min as fn(F32x4, F32x4) -> F32x4, f32::min as fn(f32, f32) -> f32), (F32x4::max, f32::max)] {
+            let v = op(va, vb);
+            for i in 0..4 {
+                // Compare lane `i` through `le` both ways; NaN compares false.
+                let expected = scalar(a[i], b[i]);
+                let lane = F32x4::splat(expected);
+                let equal = v.le(lane) & lane.le(v) & (1 << i) != 0;
+                assert_eq!(equal, !expected.is_nan(), "lane {i}");
+            }
+        }
+        assert_eq!(F32x4::from_array([1.0, 2.0, f32::NAN, 4.0]).le(F32x4::splat(2.0)), 0b0011);
+        assert_eq!(F32x4::splat(2.0).mul(F32x4::from_array([1.0, 2.0, 3.0, 4.0])).le(F32x4::splat(6.0)), 0b0111);
+    }
+
+    #[test]
+    fn test_simd_arithmetic_matches_scalar() {
+        let a = [1.5, -2.0, 0.1, -0.5];
+        let b = [0.3, 4.0, 0.0, 1.0];
+        let (va, vb) = (F32x4::from_array(a), F32x4::from_array(b));
+        let bits = |v: [f32; 4]| v.map(f32::to_bits);
+        assert_eq!(bits(va.add(vb).to_array()), bits(std::array::from_fn(|i| a[i] + b[i])));
+        assert_eq!(bits(va.sub(vb).to_array()), bits(std::array::from_fn(|i| a[i] - b[i])));
+        assert_eq!(bits(va.div(vb).to_array()), bits(std::array::from_fn(|i| a[i] / b[i])));
+        assert_eq!(va.lt(vb), 0b1010);
+        assert_eq!(va.lt(va), 0);
+        assert_eq!(F32x4::splat(f32::NAN).lt(vb) | va.lt(F32x4::splat(f32::NAN)), 0);
+    }
+}
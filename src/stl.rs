@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+use crate::error::SimLidarError;
+use crate::mesh::Mesh;
+
+const BINARY_HEADER: usize = 80;
+const BINARY_TRIANGLE: usize = 50;
+
+/// Options for [`parse_stl`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StlOptions {
+    /// Factor applied to every coordinate, e.g. `0.001` for millimetres to
+    /// metres.
+    pub scale: f32,
+    /// Merge vertices closer than this distance (in scaled units), so the
+    /// triangle soup of an STL file becomes an indexed mesh.  `Some(0.0)`
+    /// merges only identical positions; `None` keeps every vertex.
+    pub weld_tolerance: Option<f32>,
+}
+
+impl Default for StlOptions {
+    /// Unit scale, with identical vertices merged.
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            weld_tolerance: Some(0.0),
+        }
+    }
+}
+
+/// Parse a binary or ASCII STL file into a [`Mesh`].
+///
+/// The encoding is detected from the file: a file whose size matches the
+/// binary triangle count is binary, even if its header starts with `solid`.
+/// Each ASCII `solid` becomes an object named after it; a binary file is a
+/// single unnamed object.  STL carries no semantic information, so every
+/// object is unlabeled.  Facet normals are ignored, and triangles that
+/// collapse when their vertices are welded are dropped.
+pub fn parse_stl(bytes: &[u8], options: &StlOptions) -> Result<Mesh, SimLidarError> {
+    let mut welder = Welder::new(options);
+    if is_binary(bytes) {
+        welder.mesh.add_object("", 0);
+        for facet in bytes[BINARY_HEADER + 4..].chunks_exact(BINARY_TRIANGLE) {
+            let mut corners = [Vec3::ZERO; 3];
+            for (k, corner) in corners.iter_mut().enumerate() {
+                let at = 12 + k * 12;
+                let f = |o: usize| f32::from_le_bytes(facet[at + o..at + o + 4].try_into().unwrap());
+                *corner = Vec3::new(f(0), f(4), f(8));
+            }
+            welder.add_triangle(corners, 0)?;
+        }
+    } else {
+        parse_ascii(bytes, &mut welder)?;
+    }
+    Ok(welder.mesh)
+}
+
+/// Whether `bytes` has the exact size of a binary STL file.
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < BINARY_HEADER + 4 {
+        return false;
+    }
+    let count = u32::from_le_bytes(bytes[BINARY_HEADER..BINARY_HEADER + 4].try_into().unwrap()) as usize;
+    count.checked_mul(BINARY_TRIANGLE).and_then(|n| n.checked_add(BINARY_HEADER + 4)) == Some(bytes.len())
+}
+
+fn parse_ascii(bytes: &[u8], welder: &mut Welder) -> Result<(), SimLidarError> {
+    let text = String::from_utf8_lossy(bytes);
+    if !text.trim_start().starts_with("solid") {
+        return Err(SimLidarError::Format("STL: neither binary nor ASCII".to_string()));
+    }
+    let mut object = None;
+    let mut polygon = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let malformed = |what: &str| SimLidarError::Format(format!("STL line {}: {what}", line_no + 1));
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("solid") => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                object = Some(welder.mesh.add_object(&name, 0));
+            }
+            Some("outer") => polygon.clear(),
+            Some("vertex") => {
+                let mut v = [0.0f32; 3];
+                for c in &mut v {
+                    *c = tokens
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| malformed("expected three vertex coordinates"))?;
+                }
+                polygon.push(Vec3::from(v));
+            }
+            Some("endloop") => {
+                let id = object.ok_or_else(|| malformed("facet outside of a solid"))?;
+                if polygon.len() < 3 {
+                    return Err(malformed("facet needs at least three vertices"));
+                }
+                for k in 1..polygon.len() - 1 {
+                    welder.add_triangle([polygon[0], polygon[k], polygon[k + 1]], id)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Builds an indexed mesh from triangle corners, merging nearby vertices.
+struct Welder {
+    mesh: Mesh,
+    scale: f32,
+    /// Grid cell size for welding (`0` for exact matches), if enabled.
+    tolerance: Option<f32>,
+    cells: HashMap<[i64; 3], Vec<u32>>,
+}
+
+impl Welder {
+    fn new(options: &StlOptions) -> Self {
+        Self {
+            mesh: Mesh::new(),
+            scale: options.scale,
+            tolerance: options.weld_tolerance.map(|t| t.max(0.0)),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn add_triangle(&mut self, corners: [Vec3; 3], object_id: u32) -> Result<(), SimLidarError> {
+        let mut tri = [0u32; 3];
+        for (index, corner) in tri.iter_mut().zip(corners) {
+            let p = corner * self.scale;
+            if !p.is_finite() {
+                return Err(SimLidarError::Format("STL: vertex is not finite".to_string()));
+            }
+            *index = self.vertex(p);
+        }
+        if tri[0] != tri[1] && tri[1] != tri[2] && tri[0] != tri[2] {
+            self.mesh.indices.extend_from_slice(&tri);
+            self.mesh.object_ids.push(object_id);
+        }
+        Ok(())
+    }
+
+    /// Index of the vertex at `p`, reusing a welded one if possible.
+    fn vertex(&mut self, p: Vec3) -> u32 {
+        let Some(tolerance) = self.tolerance else { return self.push(p) };
+        if tolerance == 0.0 {
+            let key = p.to_array().map(|c| (c + 0.0).to_bits() as i64);
+            if let Some(&index) = self.cells.get(&key).and_then(|v| v.first()) {
+                return index;
+            }
+            let index = self.push(p);
+            self.cells.insert(key, vec![index]);
+            return index;
+        }
+        // Search the neighbouring cells for a vertex within the tolerance.
+        // Cell coordinates saturate for tiny tolerances; points sharing a
+        // saturated cell are still compared by exact distance.
+        let cell = (p / tolerance).floor();
+        let cell = [cell.x as i64, cell.y as i64, cell.z as i64];
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let key = [cell[0].saturating_add(dx), cell[1].saturating_add(dy), cell[2].saturating_add(dz)];
+                    for &index in self.cells.get(&key).map_or(&[][..], Vec::as_slice) {
+                        let v = &self.mesh.vertices[index as usize * 3..index as usize * 3 + 3];
+                        if Vec3::from_slice(v).distance(p) <= tolerance {
+                            return index;
+                        }
+                    }
+                }
+            }
+        }
+        let index = self.push(p);
+        self.cells.entry(cell).or_default().push(index);
+        index
+    }
+
+    fn push(&mut self, p: Vec3) -> u32 {
+        self.mesh.vertices.extend_from_slice(&p.to_array());
+        (self.mesh.vertex_count() - 1) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit square in the z = 0 plane as two facets, in millimetres.
+    const SQUARE_ASCII: &str = "solid plate
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1000 0 0
+      vertex 1000 1000 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1000 1000 0
+      vertex 0 1000 0
+    endloop
+  endfacet
+endsolid plate
+solid target
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 5000
+      vertex 1000 0 5000
+      vertex 0 1000 5000
+    endloop
+  endfacet
+endsolid target
+";
+
+    fn binary(triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        // Binary files commonly start with "solid" too.
+        let mut out = b"solid exported by CAD".to_vec();
+        out.resize(BINARY_HEADER, 0);
+        out.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for tri in triangles {
+            out.extend_from_slice(&[0u8; 12]);
+            for v in tri.iter().flatten() {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            out.extend_from_slice(&[0u8; 2]);
+        }
+        out
+    }
+
+    #[test]
+    fn test_stl_ascii_welds_and_scales() {
+        let options = StlOptions {
+            scale: 0.001,
+            ..StlOptions::default()
+        };
+        let mesh = parse_stl(SQUARE_ASCII.as_bytes(), &options).unwrap();
+        assert_eq!(mesh.vertex_count(), 7, "Shared corners of the plate are welded");
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6]);
+        assert_eq!(&mesh.vertices[3..6], &[1.0, 0.0, 0.0]);
+        assert_eq!(mesh.object_names, vec!["plate", "target"]);
+        assert_eq!(mesh.object_ids, vec![0, 0, 1]);
+        assert_eq!(mesh.object_labels, vec![0, 0]);
+        mesh.build_bvh().unwrap();
+    }
+
+    #[test]
+    fn test_stl_binary_with_solid_header() {
+        let square = [[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]], [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]]];
+        let bytes = binary(&square);
+        let mesh = parse_stl(&bytes, &StlOptions::default()).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.object_names, vec![""]);
+
+        let unwelded = StlOptions {
+            weld_tolerance: None,
+            ..StlOptions::default()
+        };
+        assert_eq!(parse_stl(&bytes, &unwelded).unwrap().vertex_count(), 6);
+    }
+
+    #[test]
+    fn test_stl_weld_tolerance_drops_collapsed_triangles() {
+        let sliver = [[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]], [[0.0, 0.0, 0.0], [1.0005, 0.0, 0.0], [0.0, 0.0, 0.0004]]];
+        let options = StlOptions {
+            weld_tolerance: Some(0.001),
+            ..StlOptions::default()
+        };
+        let mesh = parse_stl(&binary(&sliver), &options).unwrap();
+        assert_eq!(mesh.vertex_count(), 3);
+        assert_eq!(mesh.triangle_count(), 1);
+    }
+
+    #[test]
+    fn test_stl_tiny_weld_tolerance_saturates_cells() {
+        let far = [[[1e30, -1e30, 0.0], [-1e30, 1e30, 1.0], [0.0, 1.0, 1e30]], [[1e30, -1e30, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]]];
+        let options = StlOptions {
+            weld_tolerance: Some(1e-20),
+            ..StlOptions::default()
+        };
+        let mesh = parse_stl(&binary(&far), &options).unwrap();
+        assert_eq!(mesh.vertex_count(), 5, "Only the shared corner is welded");
+        assert_eq!(mesh.triangle_count(), 2);
+    }
+
+    #[test]
+    fn test_stl_rejects_malformed_input() {
+        assert!(parse_stl(b"not an stl", &StlOptions::default()).is_err());
+        let bad = "solid x\nfacet normal 0 0 1\nouter loop\nvertex 0 0\nendloop\nendsolid\n";
+        let err = parse_stl(bad.as_bytes(), &StlOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("line 4"), "{err}");
+        let nan = binary(&[[[f32::NAN, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]]);
+        assert!(parse_stl(&nan, &StlOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use glam::{Quat, Vec3};
+
+use crate::error::SimLidarError;
+use crate::pose::Pose;
+use crate::raycaster::ScanResult;
+
+/// Upper bound on the number of scans [`Trajectory::scan_times`] yields, so a
+/// huge rate cannot request an effectively unbounded batch.
+pub const MAX_SCANS_PER_TRAJECTORY: usize = 1 << 20;
+
+/// Timestamped sensor poses, interpolated linearly in position and by SLERP
+/// in rotation, for batch scanning with [`Sensor::scan_trajectory`].
+///
+/// [`Sensor::scan_trajectory`]: crate::Sensor::scan_trajectory
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default)]
+pub struct Trajectory {
+    /// Keyframes in strictly increasing time order.
+    keyframes: Vec<(f64, Pose)>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Trajectory {
+    /// An empty trajectory.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Trajectory {
+        Self::default()
+    }
+
+    /// Append the pose at `(x, y, z)` with rotation `(qx, qy, qz, qw)` at
+    /// `time` seconds.  Times must be finite and strictly increasing.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_pose(&mut self, time: f64, x: f32, y: f32, z: f32, qx: f32, qy: f32, qz: f32, qw: f32) -> Result<(), SimLidarError> {
+        self.push(time, Pose::new(Vec3::new(x, y, z), Quat::from_xyzw(qx, qy, qz, qw)))
+    }
+
+    /// Number of keyframes.
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    /// Whether the trajectory has no keyframes.
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+}
+
+impl Trajectory {
+    /// Append `pose` at `time` seconds.  Fails with a
+    /// [`SimLidarError::Format`] error, keeping the trajectory, if `time` is
+    /// not finite, not after the last keyframe, or `pose` is not finite.
+    pub fn push(&mut self, time: f64, pose: Pose) -> Result<(), SimLidarError> {
+        if !time.is_finite() {
+            return Err(SimLidarError::Format(format!("trajectory: time must be finite, got {time}")));
+        }
+        if let Some(&(last, _)) = self.keyframes.last() {
+            if time <= last {
+                return Err(SimLidarError::Format(format!("trajectory: time {time} is not after {last}")));
+            }
+        }
+        if !pose.position.is_finite() || !pose.rotation.is_finite() {
+            return Err(SimLidarError::Format(format!("trajectory: pose at {time} is not finite")));
+        }
+        self.keyframes.push((time, pose));
+        Ok(())
+    }
+
+    /// The keyframes, as `(time, pose)` pairs in time order.
+    pub fn keyframes(&self) -> &[(f64, Pose)] {
+        &self.keyframes
+    }
+
+    /// Interpolated pose at `time`, or `None` outside the trajectory.
+    pub fn pose_at(&self, time: f64) -> Option<Pose> {
+        let (first, last) = (self.keyframes.first()?, self.keyframes.last()?);
+        if !(first.0..=last.0).contains(&time) {
+            return None;
+        }
+        let next = self.keyframes.partition_point(|&(t, _)| t < time);
+        if self.keyframes[next].0 == time {
+            return Some(self.keyframes[next].1);
+        }
+        let ((t0, a), (t1, b)) = (self.keyframes[next - 1], self.keyframes[next]);
+        let s = ((time - t0) / (t1 - t0)) as f32;
+        Some(Pose {
+            position: a.position.lerp(b.position, s),
+            rotation: a.rotation.slerp(b.rotation, s),
+        })
+    }
+
+    /// Scan timestamps at `rate_hz` from the first to the last keyframe,
+    /// inclusive.  Fails with [`SimLidarError::InvalidArgument`] unless
+    /// `rate_hz` is finite and positive and yields at most
+    /// [`MAX_SCANS_PER_TRAJECTORY`] scans.
+    pub fn scan_times(&self, rate_hz: f64) -> Result<impl Iterator<Item = f64>, SimLidarError> {
+        if !rate_hz.is_finite() || rate_hz <= 0.0 {
+            return Err(SimLidarError::InvalidArgument {
+                argument: "rate_hz",
+                message: format!("must be finite and > 0, got {rate_hz}"),
+            });
+        }
+        let (start, end) = match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(&(start, _)), Some(&(end, _))) => (start, end),
+            _ => (0.0, -1.0),
+        };
+        // Each time is derived from its index so long runs do not drift, and
+        // clamped so rounding never steps past the last keyframe.  The count
+        // is checked as a float so huge rates cannot saturate the cast.
+        let count = if end < start { 0.0 } else { ((end - start) * rate_hz + 1e-9).floor() + 1.0 };
+        if count > MAX_SCANS_PER_TRAJECTORY as f64 {
+            return Err(SimLidarError::InvalidArgument {
+                argument: "rate_hz",
+                message: format!("{rate_hz} Hz over {} s exceeds {MAX_SCANS_PER_TRAJECTORY} scans", end - start),
+            });
+        }
+        let count = count as usize;
+        Ok((0..count).map(move |i| (start + i as f64 / rate_hz).min(end)))
+    }
+}
+
+/// Per-scan metadata of a trajectory scan.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScanMetadata {
+    /// Position of the scan in the sequence, from `0`.
+    pub index: usize,
+    /// Scan start time in seconds, on the trajectory's clock.
+    pub timestamp: f64,
+    /// Interpolated sensor pose the scan was taken from.
+    pub pose: Pose,
+}
+
+/// One scan of a trajectory, with its metadata.
+#[derive(Clone, Debug)]
+pub struct TrajectoryScan {
+    pub metadata: ScanMetadata,
+    pub scan: ScanResult,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn straight_line() -> Trajectory {
+        let mut trajectory = Trajectory::new();
+        trajectory.push(1.0, Pose::IDENTITY).unwrap();
+        trajectory
+            .push(2.0, Pose::new(Vec3::new(10.0, 0.0, 0.0), Quat::from_rotation_y(1.0)))
+            .unwrap();
+        trajectory
+    }
+
+    #[test]
+    fn test_trajectory_interpolates_with_slerp() {
+        let trajectory = straight_line();
+        let pose = trajectory.pose_at(1.25).unwrap();
+        assert!((pose.position - Vec3::new(2.5, 0.0, 0.0)).length() < 1e-5);
+        assert!(pose.rotation.angle_between(Quat::from_rotation_y(0.25)) < 1e-5);
+        assert_eq!(trajectory.pose_at(2.0).unwrap().position, Vec3::new(10.0, 0.0, 0.0));
+        assert_eq!(trajectory.pose_at(1.0), Some(Pose::IDENTITY));
+        assert!(trajectory.pose_at(0.5).is_none() && trajectory.pose_at(2.5).is_none());
+    }
+
+    #[test]
+    fn test_trajectory_rejects_bad_keyframes() {
+        let mut trajectory = straight_line();
+        assert!(matches!(trajectory.push(2.0, Pose::IDENTITY), Err(SimLidarError::Format(_))));
+        assert!(trajectory.push(f64::NAN, Pose::IDENTITY).is_err());
+        assert!(trajectory.push(3.0, Pose::from_position(Vec3::NAN)).is_err());
+        assert_eq!(trajectory.len(), 2);
+        assert!(matches!(
+            trajectory.scan_times(0.0),
+            Err(SimLidarError::InvalidArgument { argument: "rate_hz", .. })
+        ));
+    }
+
+    #[test]
+    fn test_trajectory_scan_times() {
+        let times: Vec<f64> = straight_line().scan_times(10.0).unwrap().collect();
+        assert_eq!(times.len(), 11);
+        assert_eq!((times[0], times[10]), (1.0, 2.0));
+        assert_eq!(straight_line().scan_times(3.0).unwrap().count(), 4);
+        assert_eq!(Trajectory::new().scan_times(10.0).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_trajectory_scan_times_caps_scan_count() {
+        let limit = (MAX_SCANS_PER_TRAJECTORY - 1) as f64;
+        assert_eq!(straight_line().scan_times(limit).unwrap().count(), MAX_SCANS_PER_TRAJECTORY);
+        assert!(matches!(
+            straight_line().scan_times(limit + 1.0),
+            Err(SimLidarError::InvalidArgument { argument: "rate_hz", .. })
+        ));
+        assert!(straight_line().scan_times(f64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_trajectory_scan_times_stay_within_keyframes() {
+        // 0.1 + 2 / 10 rounds to 0.30000000000000004.
+        let mut trajectory = Trajectory::new();
+        trajectory.push(0.1, Pose::IDENTITY).unwrap();
+        trajectory.push(0.3, Pose::from_position(Vec3::X)).unwrap();
+        let times: Vec<f64> = trajectory.scan_times(10.0).unwrap().collect();
+        assert_eq!(times.len(), 3);
+        assert_eq!(times[2], 0.3);
+        assert!(times.iter().all(|&t| trajectory.pose_at(t).is_some()));
+    }
+}
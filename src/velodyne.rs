@@ -0,0 +1,316 @@
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+use glam::Vec3;
+
+use crate::error::{ConfigIssue, SimLidarError};
+use crate::pcap::{PcapWriter, UdpEndpoints};
+use crate::raycaster::ScanResult;
+
+/// Size of a Velodyne data packet (UDP payload) in bytes.
+pub const VELODYNE_PACKET_SIZE: usize = 1206;
+const BLOCKS_PER_PACKET: usize = 12;
+const RECORDS_PER_BLOCK: usize = 32;
+const BLOCK_SIZE: usize = 4 + RECORDS_PER_BLOCK * 3;
+/// Return mode byte: strongest return.
+const RETURN_MODE_STRONGEST: u8 = 0x37;
+
+/// Factory network settings of Velodyne sensors: data packets are broadcast
+/// from `192.168.1.201` to port 2368.
+pub const VELODYNE_ENDPOINTS: UdpEndpoints = UdpEndpoints {
+    source_mac: [0x60, 0x76, 0x88, 0x00, 0x00, 0x01],
+    source_ip: Ipv4Addr::new(192, 168, 1, 201),
+    source_port: 2368,
+    destination_ip: Ipv4Addr::BROADCAST,
+    destination_port: 2368,
+};
+
+/// Elevation (degrees) of each VLP-16 laser, by laser id (firing order).
+const VLP16_ELEVATIONS: [f32; 16] = [
+    -15.0, 1.0, -13.0, 3.0, -11.0, 5.0, -9.0, 7.0, -7.0, 9.0, -5.0, 11.0, -3.0, 13.0, -1.0, 15.0,
+];
+
+/// Elevation (degrees) of each VLP-32C laser, by laser id (firing order).
+const VLP32C_ELEVATIONS: [f32; 32] = [
+    -25.0, -1.0, -1.667, -15.639, -11.31, 0.0, -0.667, -8.843, -7.254, 0.333, -0.333, -6.148, -5.333, 1.333, 0.667,
+    -4.0, -4.667, 1.667, 1.0, -3.667, -3.333, 3.333, 2.333, -2.667, -3.0, 7.0, 4.667, -2.333, -2.0, 15.0, 10.333,
+    -1.333,
+];
+
+/// Velodyne sensor model whose packet format to emit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VelodyneModel {
+    Vlp16,
+    Vlp32c,
+}
+
+impl VelodyneModel {
+    /// Elevation of each laser, indexed by laser id.
+    pub fn laser_elevations(self) -> &'static [f32] {
+        match self {
+            VelodyneModel::Vlp16 => &VLP16_ELEVATIONS,
+            VelodyneModel::Vlp32c => &VLP32C_ELEVATIONS,
+        }
+    }
+
+    /// Number of lasers (and therefore of rings in the scan).
+    pub fn channels(self) -> usize {
+        self.laser_elevations().len()
+    }
+
+    /// Ring (ascending elevation rank, as in [`ScanResult::rings`]) fired by
+    /// each laser id.
+    pub fn laser_rings(self) -> Vec<usize> {
+        let elevations = self.laser_elevations();
+        elevations
+            .iter()
+            .map(|e| elevations.iter().filter(|other| *other < e).count())
+            .collect()
+    }
+
+    /// Size of one distance step in metres.
+    pub fn distance_resolution(self) -> f32 {
+        match self {
+            VelodyneModel::Vlp16 => 0.002,
+            VelodyneModel::Vlp32c => 0.004,
+        }
+    }
+
+    /// Factory byte identifying the model.
+    fn product_id(self) -> u8 {
+        match self {
+            VelodyneModel::Vlp16 => 0x22,
+            VelodyneModel::Vlp32c => 0x28,
+        }
+    }
+
+    /// Firing sequences (azimuth columns) stored in one data block: the
+    /// VLP-16 packs two 16-laser sequences into each 32-record block.
+    fn columns_per_block(self) -> usize {
+        RECORDS_PER_BLOCK / self.channels()
+    }
+}
+
+impl FromStr for VelodyneModel {
+    type Err = SimLidarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "vlp16" | "vlp-16" => Ok(VelodyneModel::Vlp16),
+            "vlp32c" | "vlp-32c" => Ok(VelodyneModel::Vlp32c),
+            _ => Err(SimLidarError::Format(format!("unknown Velodyne model {s:?}"))),
+        }
+    }
+}
+
+/// One encoded Velodyne data packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VelodynePacket {
+    /// Time of the first firing in the packet, in seconds.
+    pub timestamp: f64,
+    /// The 1206-byte UDP payload.
+    pub data: Vec<u8>,
+}
+
+/// Encode an organized scan as Velodyne data packets.
+///
+/// * `result`     – Scan taken with `organized` enabled and as many
+///   `vertical_channels` as the model has lasers (e.g. [`SensorConfig::vlp16`]).
+/// * `model`      – Packet format to emit.
+/// * `scan_start` – Time of the first firing, in seconds (Unix time).
+///
+/// Each azimuth column of the scan becomes one firing sequence.  Rings are
+/// mapped to laser ids by ascending elevation, so the driver places each
+/// return on the real laser of the same rank; use a scan whose channel
+/// elevations match the model for geometrically exact output.  Misses and
+/// out-of-range returns are encoded as distance `0`, reflectivity is the
+/// point intensity scaled to `0..=100`, and packet timestamps (microseconds
+/// past the hour) come from the per-point times of the scan.  The last packet
+/// is padded with empty firings.
+///
+/// [`SensorConfig::vlp16`]: crate::SensorConfig::vlp16
+pub fn encode_velodyne_packets(
+    result: &ScanResult,
+    model: VelodyneModel,
+    scan_start: f64,
+) -> Result<Vec<VelodynePacket>, SimLidarError> {
+    let channels = model.channels();
+    let width = result.width as usize;
+    if width == 0 || result.height as usize != channels || width * channels != result.hit_count {
+        return Err(SimLidarError::InvalidConfig(vec![ConfigIssue::new(
+            "organized",
+            format!(
+                "Velodyne packets need an organized scan with {channels} channels, got {} × {}",
+                result.height, result.width
+            ),
+        )]));
+    }
+
+    let laser_rings = model.laser_rings();
+    let resolution = model.distance_resolution();
+    let column_period = if width > 1 && result.times.len() > 1 {
+        (result.times[1] - result.times[0]) as f64
+    } else {
+        0.0
+    };
+    let column_time = |c: usize| match result.times.get(c) {
+        Some(&t) if c < width => t as f64,
+        _ => c as f64 * column_period,
+    };
+
+    let columns_per_packet = BLOCKS_PER_PACKET * model.columns_per_block();
+    let packet_count = width.div_ceil(columns_per_packet);
+    let mut packets = Vec::with_capacity(packet_count);
+    for p in 0..packet_count {
+        let mut data = Vec::with_capacity(VELODYNE_PACKET_SIZE);
+        for b in 0..BLOCKS_PER_PACKET {
+            let first_column = (p * BLOCKS_PER_PACKET + b) * model.columns_per_block();
+            let azimuth = (first_column as f64 * 36_000.0 / width as f64).round() as u32 % 36_000;
+            data.extend_from_slice(&[0xff, 0xee]);
+            data.extend_from_slice(&(azimuth as u16).to_le_bytes());
+            for sequence in 0..model.columns_per_block() {
+                let column = first_column + sequence;
+                for &ring in &laser_rings {
+                    let (distance, reflectivity) = if column < width {
+                        encode_return(result, ring * width + column, resolution)
+                    } else {
+                        (0, 0)
+                    };
+                    data.extend_from_slice(&distance.to_le_bytes());
+                    data.push(reflectivity);
+                }
+            }
+        }
+        let timestamp = scan_start + column_time(p * columns_per_packet);
+        let micros_past_hour = ((timestamp * 1e6).round() as u64 % 3_600_000_000) as u32;
+        data.extend_from_slice(&micros_past_hour.to_le_bytes());
+        data.push(RETURN_MODE_STRONGEST);
+        data.push(model.product_id());
+        debug_assert_eq!(data.len(), VELODYNE_PACKET_SIZE);
+        debug_assert_eq!(BLOCK_SIZE * BLOCKS_PER_PACKET + 6, VELODYNE_PACKET_SIZE);
+        packets.push(VelodynePacket { timestamp, data });
+    }
+    Ok(packets)
+}
+
+/// Distance (in `resolution` steps) and reflectivity of point `i`.
+fn encode_return(result: &ScanResult, i: usize, resolution: f32) -> (u16, u8) {
+    let hit = Vec3::from_slice(&result.hits[i * 3..i * 3 + 3]);
+    let distance = (hit - result.sensor_position).length() / resolution;
+    if !distance.is_finite() || distance > u16::MAX as f32 {
+        return (0, 0);
+    }
+    let intensity = result.intensities.get(i).copied().unwrap_or(0.0);
+    (distance.round() as u16, (intensity.clamp(0.0, 1.0) * 100.0).round() as u8)
+}
+
+/// Write packets into a pcap capture as broadcast from a factory-configured
+/// sensor ([`VELODYNE_ENDPOINTS`]).
+pub fn write_velodyne_pcap<W: Write>(out: W, packets: &[VelodynePacket]) -> Result<W, SimLidarError> {
+    let mut pcap = PcapWriter::new(out)?;
+    for packet in packets {
+        pcap.write_udp(packet.timestamp, &VELODYNE_ENDPOINTS, &packet.data)?;
+    }
+    pcap.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    /// Organized scan where every point of ring `r`, column `c` lies at
+    /// distance `1 + r + c / 100` metres in front of the sensor.
+    fn organized_scan(channels: usize, width: usize) -> ScanResult {
+        let mut result = ScanResult {
+            width: width as u32,
+            height: channels as u32,
+            hit_count: channels * width,
+            sensor_position: Vec3::new(0.0, 2.0, 0.0),
+            sensor_rotation: Quat::IDENTITY,
+            ..ScanResult::default()
+        };
+        for r in 0..channels {
+            for c in 0..width {
+                let d = 1.0 + r as f32 + c as f32 / 100.0;
+                result.hits.extend_from_slice(&[d, 2.0, 0.0]);
+                result.rings.push(r as u16);
+                result.intensities.push(0.5);
+                result.times.push(c as f32 * 1e-4);
+            }
+        }
+        result
+    }
+
+    fn u16_at(data: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([data[at], data[at + 1]])
+    }
+
+    #[test]
+    fn test_vlp16_laser_rings_interleave() {
+        let rings = VelodyneModel::Vlp16.laser_rings();
+        assert_eq!(rings, vec![0, 8, 1, 9, 2, 10, 3, 11, 4, 12, 5, 13, 6, 14, 7, 15]);
+        let rings = VelodyneModel::Vlp32c.laser_rings();
+        assert_eq!((rings[0], rings[29]), (0, 31));
+    }
+
+    #[test]
+    fn test_vlp16_packet_layout() {
+        let scan = organized_scan(16, 30);
+        let packets = encode_velodyne_packets(&scan, VelodyneModel::Vlp16, 7200.5).unwrap();
+        // 24 firing sequences per packet: 30 columns need two packets.
+        assert_eq!(packets.len(), 2);
+        let data = &packets[0].data;
+        assert_eq!(data.len(), VELODYNE_PACKET_SIZE);
+        assert_eq!(&data[0..2], &[0xff, 0xee]);
+        // Block 1 starts at column 2: azimuth 2 × 12° = 24.00°.
+        assert_eq!(u16_at(data, BLOCK_SIZE + 2), 2400);
+        // Laser 1 (ring 8) of the first firing: 9 m in 2 mm steps.
+        assert_eq!(u16_at(data, 4 + 3), 4500);
+        assert_eq!(data[4 + 3 + 2], 50);
+        // Second firing sequence of block 0 is column 1: laser 0 (ring 0) at 1.01 m.
+        assert_eq!(u16_at(data, 4 + 16 * 3), 505);
+        assert_eq!(u32::from_le_bytes(data[1200..1204].try_into().unwrap()), 500_000);
+        assert_eq!((data[1204], data[1205]), (0x37, 0x22));
+
+        // Second packet starts at column 24 and pads the missing firings.
+        let second = &packets[1];
+        assert!((second.timestamp - (7200.5 + 24.0 * 1e-4)).abs() < 1e-6);
+        let last_block = &second.data[11 * BLOCK_SIZE..12 * BLOCK_SIZE];
+        assert!(last_block[4..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_vlp32c_one_firing_per_block_and_misses() {
+        let mut scan = organized_scan(32, 12);
+        // Ring 0 (laser 0), column 3 is a miss.
+        scan.hits[3 * 3..3 * 3 + 3].copy_from_slice(&[f32::NAN; 3]);
+        let packets = encode_velodyne_packets(&scan, VelodyneModel::Vlp32c, 0.0).unwrap();
+        assert_eq!(packets.len(), 1);
+        let data = &packets[0].data;
+        assert_eq!(u16_at(data, 3 * BLOCK_SIZE + 2), 9000);
+        assert_eq!(u16_at(data, 3 * BLOCK_SIZE + 4), 0, "Misses encode distance 0");
+        assert_eq!(u16_at(data, 4), 250, "1 m in 4 mm steps");
+        assert_eq!(data[1205], 0x28);
+    }
+
+    #[test]
+    fn test_velodyne_rejects_unorganized_scan() {
+        let mut scan = organized_scan(16, 4);
+        scan.height = 1;
+        scan.width = 64;
+        let err = encode_velodyne_packets(&scan, VelodyneModel::Vlp16, 0.0).unwrap_err();
+        assert!(matches!(err, SimLidarError::InvalidConfig(_)));
+        assert!(encode_velodyne_packets(&organized_scan(16, 4), VelodyneModel::Vlp32c, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_velodyne_pcap_frames() {
+        let packets = encode_velodyne_packets(&organized_scan(16, 48), VelodyneModel::Vlp16, 0.0).unwrap();
+        let bytes = write_velodyne_pcap(Vec::new(), &packets).unwrap();
+        assert_eq!(bytes.len(), 24 + 2 * (16 + 42 + VELODYNE_PACKET_SIZE));
+        // UDP destination port of the first frame.
+        assert_eq!(u16::from_be_bytes([bytes[24 + 16 + 36], bytes[24 + 16 + 37]]), 2368);
+    }
+}
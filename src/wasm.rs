@@ -0,0 +1,1027 @@
+//! JavaScript bindings: thin wasm-bindgen wrappers around the native
+//! [`Scene`] and [`Sensor`] API.
+
+use std::io::Cursor;
+
+use glam::{Quat, Vec3};
+use js_sys::Float32Array;
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "gltf")]
+use crate::parse_gltf;
+use crate::{
+    encode_ouster_packets, encode_point_cloud2, encode_velodyne_packets, kitti_labels, kitti_velodyne_bin, ouster_metadata_json,
+    parse_obj, parse_stl, read_pcd, write_ouster_pcap, write_pcd, write_ply, write_velodyne_pcap, Heightfield, KittiSequence,
+    LasOptions, LasWriter, McapOptions, McapWriter, ObjGrouping, OusterProfile, PcdEncoding, PlyFormat, PointCloud2Layout, Pose,
+    Primitive, Rig, ScanResult, Scene, SceneHandle, Sensor, SensorConfig, SimLidarError, StlOptions, Trajectory, TrajectoryScan,
+    VelodyneModel,
+};
+
+/// Start a pool of `num_threads` Web Workers that casts the rays of every
+/// scan, e.g. `await initThreadPool(navigator.hardwareConcurrency)`.  Call it
+/// once, before the first scan.  Only exported by threaded builds (the
+/// `parallel` feature on a nightly `+atomics,+bulk-memory` build), which need
+/// a cross-origin isolated page for `SharedArrayBuffer`.
+#[cfg(all(feature = "parallel", target_feature = "atomics"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+/// The main LiDAR simulator.  Holds a handle to the pre-built BVH for the
+/// environment geometry and exposes scanning methods to JavaScript via
+/// wasm-bindgen.
+#[wasm_bindgen]
+pub struct LidarSimulator {
+    scene: SceneHandle,
+    /// Owns the scan output re-used across scans to avoid repeated allocation.
+    sensor: Sensor,
+}
+
+#[wasm_bindgen]
+impl LidarSimulator {
+    /// Create a new simulator.
+    ///
+    /// * `vertices` – Flat `Float32Array` of vertex positions `[x,y,z, ...]`.
+    /// * `indices`  – Flat `Uint32Array` of triangle indices.
+    /// * `config`   – Sensor configuration.
+    ///
+    /// Throws if the geometry is malformed or the configuration is invalid
+    /// (see [`SimLidarError`]).
+    #[wasm_bindgen(constructor)]
+    pub fn new(vertices: &[f32], indices: &[u32], config: SensorConfig) -> Result<LidarSimulator, JsValue> {
+        let sensor = Sensor::new(config)?;
+        let mut scene = Scene::new();
+        scene.load_geometry(vertices, indices)?;
+        Ok(LidarSimulator {
+            scene: SceneHandle::new(scene),
+            sensor,
+        })
+    }
+
+    /// Replace the sensor configuration at runtime.
+    ///
+    /// Throws an `InvalidConfig` error (keeping the current configuration) if
+    /// `config` is invalid.
+    pub fn set_config(&mut self, config: SensorConfig) -> Result<(), JsValue> {
+        self.sensor.set_config(config)?;
+        Ok(())
+    }
+
+    /// A handle to this simulator's scene, e.g. for [`Simulator::with_scene`].
+    pub fn scene(&self) -> SceneHandle {
+        self.scene.clone()
+    }
+
+    /// Scan `scene` from now on instead of the geometry given at construction.
+    pub fn attach_scene(&mut self, scene: &SceneHandle) {
+        self.scene = scene.clone();
+    }
+
+    /// Run a full scan from a given pose.
+    ///
+    /// * `px`, `py`, `pz`        – Sensor world-space position.
+    /// * `qx`, `qy`, `qz`, `qw` – Sensor orientation quaternion.
+    ///
+    /// Returns a new `Float32Array` `[x,y,z, x,y,z, …]` holding a copy of the
+    /// hit points.  Use [`scan_into`] to avoid the per-scan allocation.
+    ///
+    /// [`scan_into`]: LidarSimulator::scan_into
+    #[allow(clippy::too_many_arguments)]
+    pub fn scan(&mut self, px: f32, py: f32, pz: f32, qx: f32, qy: f32, qz: f32, qw: f32) -> Result<Vec<f32>, JsValue> {
+        Ok(self.run_scan(px, py, pz, qx, qy, qz, qw)?.hits.clone())
+    }
+
+    /// Run a full scan and write the hit points `[x,y,z, …]` into the
+    /// caller-owned `out` buffer, returning the number of hits written.
+    ///
+    /// `out` should be sized for a full scan (`config.total_rays() * 3`);
+    /// throws a `BufferTooSmall` error if the hits do not fit.
+    #[allow(clippy::too_many_arguments)]
+    pub fn scan_into(
+        &mut self,
+        out: &mut [f32],
+        px: f32,
+        py: f32,
+        pz: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<usize, JsValue> {
+        let result = self.run_scan(px, py, pz, qx, qy, qz, qw)?;
+        Ok(copy_hits(result, out)?)
+    }
+
+    /// Returns the last scan's hit count.
+    pub fn last_hit_count(&self) -> usize {
+        self.sensor.last_scan().hit_count
+    }
+}
+
+impl LidarSimulator {
+    /// Scan into the re-used output buffer.
+    #[allow(clippy::too_many_arguments)]
+    fn run_scan(&mut self, px: f32, py: f32, pz: f32, qx: f32, qy: f32, qz: f32, qw: f32) -> Result<&ScanResult, SimLidarError> {
+        let pose = Pose::new(Vec3::new(px, py, pz), Quat::from_xyzw(qx, qy, qz, qw));
+        self.sensor.scan(&self.scene.read(), &pose)
+    }
+}
+
+/// A streamlined LiDAR simulator designed for direct use from JavaScript.
+///
+/// Unlike [`LidarSimulator`], geometry is loaded separately via [`Simulator::load_geometry`]
+/// and scan results are returned as a zero-copy `Float32Array` view directly into
+/// Wasm linear memory.
+///
+/// # Stable hit buffer
+///
+/// The hit buffer is reserved for a full scan (`config.total_rays() * 3`
+/// floats) when the simulator is created and never reallocated by a scan, so
+/// [`hit_buffer_ptr`] stays the same across frames.  It only changes when
+/// [`set_config`] needs a larger buffer.  JS can therefore keep a long-lived
+/// view:
+///
+/// ```js
+/// let view = new Float32Array(memory.buffer, sim.hit_buffer_ptr(), sim.hit_buffer_capacity());
+/// const n = sim.scan_in_place(x, y, z, qx, qy, qz, qw);
+/// // A Wasm memory growth detaches `memory.buffer`; rebuild the view if so.
+/// if (view.buffer !== memory.buffer) view = new Float32Array(memory.buffer, sim.hit_buffer_ptr(), sim.hit_buffer_capacity());
+/// const hits = view.subarray(0, n * 3);
+/// ```
+///
+/// [`hit_buffer_ptr`]: Simulator::hit_buffer_ptr
+/// [`set_config`]: Simulator::set_config
+#[wasm_bindgen]
+pub struct Simulator {
+    scene: SceneHandle,
+    /// Owns the scan output re-used across scans; its buffers are reserved
+    /// for a full scan so their addresses stay stable between frames.
+    sensor: Sensor,
+}
+
+#[wasm_bindgen]
+impl Simulator {
+    /// Create a new `Simulator` with the given sensor configuration.
+    ///
+    /// Call [`load_geometry`] before [`perform_scan`].
+    ///
+    /// [`load_geometry`]: Simulator::load_geometry
+    /// [`perform_scan`]: Simulator::perform_scan
+    ///
+    /// Throws an `InvalidConfig` error if `config` is invalid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: SensorConfig) -> Result<Simulator, JsValue> {
+        Simulator::with_scene(config, &SceneHandle::empty())
+    }
+
+    /// Create a `Simulator` attached to a shared scene: geometry loaded
+    /// through any simulator attached to `scene` is seen by all of them.
+    ///
+    /// Throws an `InvalidConfig` error if `config` is invalid.
+    pub fn with_scene(config: SensorConfig, scene: &SceneHandle) -> Result<Simulator, JsValue> {
+        Ok(Simulator {
+            scene: scene.clone(),
+            sensor: Sensor::new(config)?,
+        })
+    }
+
+    /// A handle to this simulator's scene, to attach other simulators to.
+    pub fn scene(&self) -> SceneHandle {
+        self.scene.clone()
+    }
+
+    /// Scan `scene` from now on, sharing its geometry with every other
+    /// simulator attached to it.
+    pub fn attach_scene(&mut self, scene: &SceneHandle) {
+        self.scene = scene.clone();
+    }
+
+    /// Ingest environment geometry and (re)build the internal BVH.
+    ///
+    /// * `vertices` – Flat `Float32Array` of vertex positions `[x,y,z, …]`.
+    /// * `indices`  – Flat `Uint32Array` of triangle vertex indices.
+    ///
+    /// This method may be called multiple times to swap the environment at runtime.
+    /// Throws if the geometry is malformed; the previous environment is kept.
+    pub fn load_geometry(&mut self, vertices: &[f32], indices: &[u32]) -> Result<(), JsValue> {
+        self.scene.write().load_geometry(vertices, indices)?;
+        Ok(())
+    }
+
+    /// Ingest environment geometry where every triangle belongs to an object.
+    ///
+    /// * `object_ids` – Flat `Uint32Array` with one object id per triangle.
+    ///
+    /// Object ids index into the velocities passed to
+    /// [`set_object_velocities`].
+    ///
+    /// [`set_object_velocities`]: Simulator::set_object_velocities
+    pub fn load_geometry_with_object_ids(
+        &mut self,
+        vertices: &[f32],
+        indices: &[u32],
+        object_ids: &[u32],
+    ) -> Result<(), JsValue> {
+        self.scene.write().load_geometry_with_object_ids(vertices, indices, object_ids)?;
+        Ok(())
+    }
+
+    /// Parse a Wavefront OBJ file and use it as the environment.
+    ///
+    /// * `bytes`    – Contents of the `.obj` file.
+    /// * `grouping` – `"object"`, `"group"` or `"material"`: which statement
+    ///   (`o`, `g` or `usemtl`) starts a new object id
+    ///
+    /// Materials become semantic labels; see [`object_names`] and
+    /// [`label_names`].  Throws a `Format` error if the file is malformed; the
+    /// previous environment is kept.
+    ///
+    /// [`object_names`]: Simulator::object_names
+    /// [`label_names`]: Simulator::label_names
+    pub fn load_obj(&mut self, bytes: &[u8], grouping: &str) -> Result<(), JsValue> {
+        let grouping: ObjGrouping = grouping.parse()?;
+        self.scene.write().load_mesh(&parse_obj(bytes, grouping)?)?;
+        Ok(())
+    }
+
+    /// Parse a binary or ASCII STL file and use it as the environment.
+    ///
+    /// * `bytes` – Contents of the `.stl` file.
+    /// * `scale` – Factor applied to every coordinate (e.g. `0.001` for a
+    ///   model in millimetres)
+    ///
+    /// Identical vertices are welded.  Throws a `Format` error if the file is
+    /// malformed; the previous environment is kept.
+    pub fn load_stl(&mut self, bytes: &[u8], scale: f32) -> Result<(), JsValue> {
+        let options = StlOptions {
+            scale,
+            ..StlOptions::default()
+        };
+        self.scene.write().load_mesh(&parse_stl(bytes, &options)?)?;
+        Ok(())
+    }
+
+    /// Name of each object, indexed by object id, for geometry loaded from a
+    /// file (empty otherwise).
+    pub fn object_names(&self) -> Vec<String> {
+        self.scene.read().object_names().to_vec()
+    }
+
+    /// Name of each semantic label, indexed by label, for geometry loaded
+    /// from a file (empty otherwise).  Label `0` is unlabeled.
+    pub fn label_names(&self) -> Vec<String> {
+        self.scene.read().label_names().to_vec()
+    }
+
+    /// Assign a semantic label to each object id, reported per point by
+    /// [`last_labels`].  Throws a `MissingGeometry` error if no geometry has
+    /// been loaded; labels are kept across later geometry loads.
+    ///
+    /// * `labels` – Flat `Uint32Array` indexed by object id.
+    ///
+    /// [`last_labels`]: Simulator::last_labels
+    pub fn set_object_labels(&mut self, labels: &[u32]) -> Result<(), JsValue> {
+        self.scene.write().set_object_labels(labels)?;
+        Ok(())
+    }
+
+    /// Add terrain to the environment, next to any loaded meshes.
+    ///
+    /// Heightfields are kept when new geometry is loaded; hits report the
+    /// heightfield's `object_id`.  Can be called before any mesh is loaded.
+    pub fn add_heightfield(&mut self, heightfield: &Heightfield) {
+        self.scene.write().add_heightfield(heightfield.clone());
+    }
+
+    /// Remove every heightfield added with [`add_heightfield`].
+    ///
+    /// [`add_heightfield`]: Simulator::add_heightfield
+    pub fn clear_heightfields(&mut self) {
+        self.scene.write().clear_heightfields();
+    }
+
+    /// Add a sphere of `radius` metres around `(x, y, z)`, hit with exact
+    /// normals next to any loaded meshes.
+    ///
+    /// Primitives are kept when new geometry is loaded.  Throws a `Format`
+    /// error if the sphere is degenerate.
+    pub fn add_sphere(&mut self, x: f32, y: f32, z: f32, radius: f32, object_id: u32) -> Result<(), JsValue> {
+        self.scene.write().add_primitive(Primitive::sphere(Vec3::new(x, y, z), radius)?.with_object_id(object_id));
+        Ok(())
+    }
+
+    /// Add a box with half extents `(hx, hy, hz)` around `(x, y, z)`, rotated
+    /// by the quaternion `(qx, qy, qz, qw)`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_box(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        hx: f32,
+        hy: f32,
+        hz: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+        object_id: u32,
+    ) -> Result<(), JsValue> {
+        let rotation = Quat::from_xyzw(qx, qy, qz, qw);
+        let primitive = Primitive::cuboid(Vec3::new(x, y, z), Vec3::new(hx, hy, hz), rotation)?;
+        self.scene.write().add_primitive(primitive.with_object_id(object_id));
+        Ok(())
+    }
+
+    /// Add a capped cylinder around `(x, y, z)`, upright before being rotated
+    /// by the quaternion `(qx, qy, qz, qw)`, e.g. a pole.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_cylinder(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        radius: f32,
+        half_height: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+        object_id: u32,
+    ) -> Result<(), JsValue> {
+        let rotation = Quat::from_xyzw(qx, qy, qz, qw);
+        let primitive = Primitive::cylinder(Vec3::new(x, y, z), radius, half_height, rotation)?;
+        self.scene.write().add_primitive(primitive.with_object_id(object_id));
+        Ok(())
+    }
+
+    /// Add an infinite plane through `(x, y, z)` with normal `(nx, ny, nz)`,
+    /// e.g. a ground plane.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_plane(&mut self, x: f32, y: f32, z: f32, nx: f32, ny: f32, nz: f32, object_id: u32) -> Result<(), JsValue> {
+        self.scene.write().add_primitive(Primitive::plane(Vec3::new(x, y, z), Vec3::new(nx, ny, nz))?.with_object_id(object_id));
+        Ok(())
+    }
+
+    /// Remove every primitive added with `add_sphere`, `add_box`,
+    /// `add_cylinder` or `add_plane`.
+    pub fn clear_primitives(&mut self) {
+        self.scene.write().clear_primitives();
+    }
+
+    /// Make range and velocity noise reproducible from `seed` (a `bigint`),
+    /// or random again with `undefined`.
+    pub fn set_noise_seed(&mut self, seed: Option<u64>) {
+        self.sensor.set_noise_seed(seed);
+    }
+
+    /// Set the world-space sensor velocity (m/s) used for Doppler output.
+    pub fn set_sensor_velocity(&mut self, vx: f32, vy: f32, vz: f32) {
+        self.sensor.motion_mut().sensor_velocity = Vec3::new(vx, vy, vz);
+    }
+
+    /// Set the world-space object velocities (m/s) used for Doppler output.
+    ///
+    /// * `velocities` – Flat `Float32Array` `[vx,vy,vz, …]` indexed by object id.
+    pub fn set_object_velocities(&mut self, velocities: &[f32]) {
+        self.sensor.motion_mut().object_velocities = velocities
+            .chunks_exact(3)
+            .map(|v| Vec3::new(v[0], v[1], v[2]))
+            .collect();
+    }
+
+    /// Run a full scan from a given pose and return the hit point cloud.
+    ///
+    /// * `x`, `y`, `z`           – Sensor world-space position.
+    /// * `qx`, `qy`, `qz`, `qw` – Sensor orientation as a unit quaternion.
+    ///
+    /// Returns a `Float32Array` view `[x,y,z, x,y,z, …]` directly into Wasm
+    /// linear memory.  The view is valid until the next call to `perform_scan`.
+    ///
+    /// # Errors
+    ///
+    /// Throws a `MissingGeometry` error if [`load_geometry`] has not been
+    /// called first, or an `InvalidConfig` error if the noise settings are
+    /// unusable.
+    ///
+    /// # Safety
+    ///
+    /// The returned `Float32Array` is a direct view into Wasm linear memory.
+    /// Do not call any Wasm-allocating function while the view is alive, as
+    /// a memory grow could invalidate the underlying pointer.  See the
+    /// type-level docs for a view that survives across frames.
+    ///
+    /// [`load_geometry`]: Simulator::load_geometry
+    #[allow(clippy::too_many_arguments)]
+    pub fn perform_scan(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<Float32Array, JsValue> {
+        let result = self.run_scan(x, y, z, qx, qy, qz, qw)?;
+        // SAFETY: `result.hits` owns the backing allocation and is not resized
+        // after this point within the same call frame.  The next scan refills
+        // the same allocation in place, so the caller must consume or copy the
+        // returned view before scanning again.
+        Ok(unsafe { Float32Array::view(&result.hits) })
+    }
+
+    /// Run a full scan into the stable hit buffer and return the hit count.
+    ///
+    /// Unlike [`perform_scan`] no JS object is created; read the hits through
+    /// a long-lived view built from [`hit_buffer_ptr`] (see the type-level
+    /// docs).  Throws like [`perform_scan`].
+    ///
+    /// [`perform_scan`]: Simulator::perform_scan
+    /// [`hit_buffer_ptr`]: Simulator::hit_buffer_ptr
+    #[allow(clippy::too_many_arguments)]
+    pub fn scan_in_place(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<usize, JsValue> {
+        Ok(self.run_scan(x, y, z, qx, qy, qz, qw)?.hit_count)
+    }
+
+    /// Run a full scan and write the hit points `[x,y,z, …]` into the
+    /// caller-owned `out` buffer, returning the number of hits written.
+    ///
+    /// `out` should be sized for a full scan (`config.total_rays() * 3`);
+    /// throws a `BufferTooSmall` error if the hits do not fit, otherwise
+    /// throws like [`perform_scan`].
+    ///
+    /// [`perform_scan`]: Simulator::perform_scan
+    #[allow(clippy::too_many_arguments)]
+    pub fn scan_into(
+        &mut self,
+        out: &mut [f32],
+        x: f32,
+        y: f32,
+        z: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<usize, JsValue> {
+        let result = self.run_scan(x, y, z, qx, qy, qz, qw)?;
+        Ok(copy_hits(result, out)?)
+    }
+
+    /// Address of the stable hit buffer in Wasm linear memory.
+    pub fn hit_buffer_ptr(&self) -> *const f32 {
+        self.sensor.last_scan().hits.as_ptr()
+    }
+
+    /// Number of `f32`s reserved at [`hit_buffer_ptr`], i.e. the largest
+    /// possible scan (`config.total_rays() * 3`).
+    ///
+    /// [`hit_buffer_ptr`]: Simulator::hit_buffer_ptr
+    pub fn hit_buffer_capacity(&self) -> usize {
+        self.sensor.last_scan().hits.capacity()
+    }
+
+    /// Replace the sensor configuration without rebuilding the BVH.
+    ///
+    /// Throws an `InvalidConfig` error (keeping the current configuration) if
+    /// `config` is invalid.
+    ///
+    /// If the new configuration fires more rays, the hit buffer is grown and
+    /// [`hit_buffer_ptr`] changes.
+    ///
+    /// [`hit_buffer_ptr`]: Simulator::hit_buffer_ptr
+    pub fn set_config(&mut self, config: SensorConfig) -> Result<(), JsValue> {
+        self.sensor.set_config(config)?;
+        Ok(())
+    }
+
+    /// Returns the number of valid hits from the last scan.
+    pub fn last_hit_count(&self) -> usize {
+        self.sensor.last_scan().hit_count
+    }
+
+    /// Returns the radial velocity (m/s) of every hit from the last scan, in
+    /// the same order as the hit points.  Empty unless the sensor
+    /// configuration has `doppler` enabled.
+    pub fn last_velocities(&self) -> Vec<f32> {
+        self.sensor.last_scan().velocities.clone()
+    }
+
+    /// Returns the intensity (`[0, 1]`) of every point from the last scan.
+    pub fn last_intensities(&self) -> Vec<f32> {
+        self.sensor.last_scan().intensities.clone()
+    }
+
+    /// Returns the laser channel (ring) of every point from the last scan.
+    pub fn last_rings(&self) -> Vec<u16> {
+        self.sensor.last_scan().rings.clone()
+    }
+
+    /// Returns the time (seconds since scan start) of every point from the
+    /// last scan.
+    pub fn last_times(&self) -> Vec<f32> {
+        self.sensor.last_scan().times.clone()
+    }
+
+    /// Returns the semantic label of every point from the last scan.
+    pub fn last_labels(&self) -> Vec<u32> {
+        self.sensor.last_scan().labels.clone()
+    }
+
+    /// Serialize the last scan as a PLY file.
+    ///
+    /// * `format`       – `"ascii"` or `"binary_little_endian"` (alias `"binary"`)
+    /// * `include_pose` – Add a `sensor_pose` element with the scan pose
+    ///
+    /// Returned to JavaScript as a `Uint8Array`.
+    pub fn last_scan_ply(&self, format: &str, include_pose: bool) -> Result<Vec<u8>, JsValue> {
+        let format: PlyFormat = format.parse()?;
+        Ok(write_ply(self.sensor.last_scan(), format, include_pose))
+    }
+
+    /// Encode the last scan as a KITTI Velodyne `.bin` frame (`f32` x, y, z,
+    /// intensity per point, in the sensor's KITTI frame).
+    pub fn last_scan_kitti_bin(&self) -> Vec<u8> {
+        kitti_velodyne_bin(self.sensor.last_scan())
+    }
+
+    /// Encode the last scan's semantic labels as a SemanticKITTI `.label`
+    /// frame, or `undefined` if the scan has no labels.
+    pub fn last_scan_kitti_labels(&self) -> Option<Vec<u8>> {
+        kitti_labels(self.sensor.last_scan())
+    }
+
+    /// Encode the last scan as a CDR-serialized ROS 2
+    /// `sensor_msgs/msg/PointCloud2` message.
+    ///
+    /// * `layout`   – `"velodyne"`, `"ouster"`, or comma-separated field names
+    ///   (`x,y,z,intensity,ring,time`, …) packed in order
+    /// * `frame_id` – `header.frame_id`
+    /// * `stamp`    – `header.stamp` in seconds
+    ///
+    /// Returned to JavaScript as a `Uint8Array`.
+    pub fn last_scan_point_cloud2(&self, layout: &str, frame_id: &str, stamp: f64) -> Result<Vec<u8>, JsValue> {
+        let layout: PointCloud2Layout = layout.parse()?;
+        Ok(encode_point_cloud2(self.sensor.last_scan(), &layout, frame_id, stamp)?)
+    }
+
+    /// Serialize the last scan as a PCD file.
+    ///
+    /// * `encoding` – `"ascii"`, `"binary"` or `"binary_compressed"`
+    ///
+    /// Organized scans keep their `width × height` layout.  Returned to
+    /// JavaScript as a `Uint8Array`.
+    pub fn last_scan_pcd(&self, encoding: &str) -> Result<Vec<u8>, JsValue> {
+        let encoding: PcdEncoding = encoding.parse()?;
+        Ok(write_pcd(self.sensor.last_scan(), encoding))
+    }
+
+    /// Encode the last scan as Velodyne UDP data packets inside a pcap
+    /// capture, as broadcast by a factory-configured sensor.
+    ///
+    /// * `model`      – `"vlp16"` or `"vlp32c"`
+    /// * `scan_start` – Time of the first firing in seconds (Unix time)
+    ///
+    /// The scan must be organized with as many channels as the model has
+    /// lasers.  Returned to JavaScript as a `Uint8Array`.
+    pub fn last_scan_velodyne_pcap(&self, model: &str, scan_start: f64) -> Result<Vec<u8>, JsValue> {
+        let model: VelodyneModel = model.parse()?;
+        let packets = encode_velodyne_packets(self.sensor.last_scan(), model, scan_start)?;
+        Ok(write_velodyne_pcap(Vec::new(), &packets)?)
+    }
+
+    /// Encode the last scan as Ouster lidar packets inside a pcap capture, as
+    /// streamed by a sensor to UDP port 7502.
+    ///
+    /// * `profile`    – `"legacy"` or `"rng19"` (`RNG19_RFL8_SIG16_NIR16`)
+    /// * `frame_id`   – Frame counter written to every packet
+    /// * `scan_start` – Time of the first column in seconds (Unix time)
+    ///
+    /// The scan must be organized, and the configuration must form an Ouster
+    /// lidar mode such as `1024x10`.  Replay it with the JSON from
+    /// [`Simulator::ouster_metadata`].  Returned to JavaScript as a `Uint8Array`.
+    pub fn last_scan_ouster_pcap(&self, profile: &str, frame_id: u16, scan_start: f64) -> Result<Vec<u8>, JsValue> {
+        let profile: OusterProfile = profile.parse()?;
+        let packets = encode_ouster_packets(self.sensor.last_scan(), self.sensor.config(), profile, frame_id, scan_start)?;
+        Ok(write_ouster_pcap(Vec::new(), &packets)?)
+    }
+
+    /// Sensor metadata JSON matching [`Simulator::last_scan_ouster_pcap`] for
+    /// the current configuration and `profile`.
+    pub fn ouster_metadata(&self, profile: &str) -> Result<String, JsValue> {
+        let profile: OusterProfile = profile.parse()?;
+        Ok(ouster_metadata_json(self.sensor.config(), profile)?)
+    }
+}
+
+#[cfg(feature = "gltf")]
+#[wasm_bindgen]
+impl Simulator {
+    /// Parse a glTF 2.0 scene (`.glb`, or `.gltf` with embedded buffers) and
+    /// use it as the environment.
+    ///
+    /// Node names become object names and material names become semantic
+    /// labels; see [`object_names`] and [`label_names`].  Throws a `Format`
+    /// error if the scene is malformed; the previous environment is kept.
+    ///
+    /// [`object_names`]: Simulator::object_names
+    /// [`label_names`]: Simulator::label_names
+    pub fn load_gltf(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        self.scene.write().load_mesh(&parse_gltf(bytes)?)?;
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Simulator {
+    /// Scan along `trajectory` at `rate_hz` scans per second, entirely in
+    /// Wasm, and return every scan with its timestamp and pose.
+    ///
+    /// Throws like [`Simulator::perform_scan`], or an `InvalidArgument` error
+    /// for a bad `rate_hz`.
+    pub fn scan_trajectory(&mut self, trajectory: &Trajectory, rate_hz: f64) -> Result<ScanBatch, JsValue> {
+        let scans = self.sensor.scan_trajectory(&self.scene.read(), trajectory, rate_hz)?;
+        Ok(ScanBatch { scans })
+    }
+}
+
+impl Simulator {
+    /// Scan into the re-used output buffer.
+    #[allow(clippy::too_many_arguments)]
+    fn run_scan(&mut self, x: f32, y: f32, z: f32, qx: f32, qy: f32, qz: f32, qw: f32) -> Result<&ScanResult, SimLidarError> {
+        let pose = Pose::new(Vec3::new(x, y, z), Quat::from_xyzw(qx, qy, qz, qw));
+        self.sensor.scan(&self.scene.read(), &pose)
+    }
+}
+
+/// Copy the hit points of `result` into the front of `out`, returning the hit
+/// count, or [`SimLidarError::BufferTooSmall`] if they do not fit.
+fn copy_hits(result: &ScanResult, out: &mut [f32]) -> Result<usize, SimLidarError> {
+    let len = result.hits.len();
+    if out.len() < len {
+        return Err(SimLidarError::BufferTooSmall {
+            required: len,
+            actual: out.len(),
+        });
+    }
+    out[..len].copy_from_slice(&result.hits);
+    Ok(result.hit_count)
+}
+
+/// The scans of [`Simulator::scan_trajectory`], indexed from `0`.  Getters
+/// return `undefined` past the end.
+#[wasm_bindgen]
+pub struct ScanBatch {
+    scans: Vec<TrajectoryScan>,
+}
+
+#[wasm_bindgen]
+impl ScanBatch {
+    /// Number of scans.
+    pub fn len(&self) -> usize {
+        self.scans.len()
+    }
+
+    /// Whether the batch holds no scans.
+    pub fn is_empty(&self) -> bool {
+        self.scans.is_empty()
+    }
+
+    /// Start time of scan `index` in seconds, on the trajectory's clock.
+    pub fn timestamp(&self, index: usize) -> Option<f64> {
+        Some(self.scans.get(index)?.metadata.timestamp)
+    }
+
+    /// Sensor pose of scan `index` as `[x, y, z, qx, qy, qz, qw]`.
+    pub fn pose(&self, index: usize) -> Option<Vec<f32>> {
+        let pose = self.scans.get(index)?.metadata.pose;
+        Some([pose.position.to_array().as_slice(), pose.rotation.to_array().as_slice()].concat())
+    }
+
+    /// Hit points of scan `index` as a flat `[x0, y0, z0, …]` copy.
+    pub fn hits(&self, index: usize) -> Option<Vec<f32>> {
+        Some(self.scans.get(index)?.scan.hits.clone())
+    }
+
+    /// Number of points in scan `index`.
+    pub fn hit_count(&self, index: usize) -> Option<usize> {
+        Some(self.scans.get(index)?.scan.hit_count)
+    }
+}
+
+/// Several sensors mounted on one vehicle, scanning the scene of a
+/// [`Simulator`] (whose BVH is shared, not rebuilt).
+///
+/// ```js
+/// const rig = new LidarRig();
+/// rig.add_sensor(SensorConfig.vlp16(), 0, 2, 0, 0, 0, 0, 1);
+/// rig.add_sensor(SensorConfig.ouster_os1_64(), 2, 0.5, 0, 0, 0, 0, 1);
+/// const points = rig.scan_merged(sim, x, y, z, qx, qy, qz, qw);
+/// const owners = rig.merged_sensor_ids(); // one sensor id per point
+/// ```
+#[wasm_bindgen]
+pub struct LidarRig {
+    rig: Rig,
+}
+
+#[wasm_bindgen]
+impl LidarRig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> LidarRig {
+        LidarRig { rig: Rig::new() }
+    }
+
+    /// Mount a sensor at the given position and orientation in the vehicle
+    /// frame and return its id.  Throws an `InvalidConfig` error if `config`
+    /// is invalid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_sensor(
+        &mut self,
+        config: SensorConfig,
+        x: f32,
+        y: f32,
+        z: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<u32, JsValue> {
+        let mount = Pose::new(Vec3::new(x, y, z), Quat::from_xyzw(qx, qy, qz, qw));
+        Ok(self.rig.add_sensor(config, mount)?)
+    }
+
+    /// Number of mounted sensors.
+    pub fn sensor_count(&self) -> u32 {
+        self.rig.sensors().len() as u32
+    }
+
+    /// Scan the scene of `simulator` with every sensor from the given
+    /// vehicle pose.  Read the clouds with [`LidarRig::sensor_hits`].
+    /// Throws like [`Simulator::perform_scan`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn scan(
+        &mut self,
+        simulator: &Simulator,
+        x: f32,
+        y: f32,
+        z: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<(), JsValue> {
+        let vehicle = Pose::new(Vec3::new(x, y, z), Quat::from_xyzw(qx, qy, qz, qw));
+        self.rig.scan(&simulator.scene.read(), &vehicle)?;
+        Ok(())
+    }
+
+    /// Like [`LidarRig::scan`], returning the merged cloud of all sensors as
+    /// a flat `Float32Array` copy.  Organized misses are dropped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn scan_merged(
+        &mut self,
+        simulator: &Simulator,
+        x: f32,
+        y: f32,
+        z: f32,
+        qx: f32,
+        qy: f32,
+        qz: f32,
+        qw: f32,
+    ) -> Result<Vec<f32>, JsValue> {
+        let vehicle = Pose::new(Vec3::new(x, y, z), Quat::from_xyzw(qx, qy, qz, qw));
+        Ok(self.rig.scan_merged(&simulator.scene.read(), &vehicle)?.scan.hits.clone())
+    }
+
+    /// Hit points of sensor `id` from the last scan, or `undefined` for an
+    /// unknown id.
+    pub fn sensor_hits(&self, id: u32) -> Option<Vec<f32>> {
+        Some(self.rig.sensor(id)?.last_scan().hits.clone())
+    }
+
+    /// Sensor id of every point of the last merged scan.
+    pub fn merged_sensor_ids(&self) -> Vec<u32> {
+        self.rig.last_merged_scan().sensor_ids.clone()
+    }
+}
+
+impl Default for LidarRig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse a PCD file (ascii, binary or binary_compressed) and return its
+/// points as a flat `[x0, y0, z0, x1, …]` array.
+#[wasm_bindgen]
+pub fn read_pcd_points(bytes: &[u8]) -> Result<Vec<f32>, JsValue> {
+    Ok(read_pcd(bytes)?.hits)
+}
+
+/// Accumulates scans into an in-memory LAS 1.4 file.
+///
+/// ```js
+/// const las = new LasRecorder(0.001, x0, y0, z0);
+/// for (const pose of trajectory) {
+///   sim.perform_scan(...pose);
+///   las.add_last_scan(sim, pose.gpsTime);
+/// }
+/// const bytes = las.finish(); // Uint8Array
+/// ```
+#[wasm_bindgen]
+pub struct LasRecorder {
+    writer: LasWriter<Cursor<Vec<u8>>>,
+}
+
+#[wasm_bindgen]
+impl LasRecorder {
+    /// Create an empty recorder.
+    ///
+    /// * `scale`                       – Coordinate step in metres (e.g. `0.001`)
+    /// * `offset_x`, `offset_y`, `offset_z` – Z-up offset subtracted before quantizing
+    ///
+    /// Throws an `InvalidArgument` error unless `scale` is finite and
+    /// positive and the offset is finite.
+    #[wasm_bindgen(constructor)]
+    pub fn new(scale: f64, offset_x: f64, offset_y: f64, offset_z: f64) -> Result<LasRecorder, JsValue> {
+        let options = LasOptions {
+            scale: [scale; 3],
+            offset: [offset_x, offset_y, offset_z],
+            ..LasOptions::default()
+        };
+        Ok(LasRecorder {
+            writer: LasWriter::new(Cursor::new(Vec::new()), options)?,
+        })
+    }
+
+    /// Append the last scan of `simulator`, stamping its points relative to
+    /// `gps_time`.
+    pub fn add_last_scan(&mut self, simulator: &Simulator, gps_time: f64) -> Result<(), JsValue> {
+        self.writer.write_scan(simulator.sensor.last_scan(), gps_time)?;
+        Ok(())
+    }
+
+    /// Number of points recorded so far.
+    pub fn point_count(&self) -> f64 {
+        self.writer.point_count() as f64
+    }
+
+    /// Finish the file and return its bytes as a `Uint8Array`.
+    pub fn finish(self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.writer.finish()?.into_inner())
+    }
+}
+
+/// Collects the poses and timestamps of a KITTI odometry sequence.  Pair it
+/// with [`Simulator::last_scan_kitti_bin`] to produce the per-frame files.
+#[wasm_bindgen]
+pub struct KittiRecorder {
+    sequence: KittiSequence,
+}
+
+#[wasm_bindgen]
+impl KittiRecorder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> KittiRecorder {
+        KittiRecorder {
+            sequence: KittiSequence::new(),
+        }
+    }
+
+    /// Record the pose of the last scan of `simulator` as the next frame,
+    /// taken at `timestamp` seconds.  Returns the frame index.
+    pub fn add_last_scan(&mut self, simulator: &Simulator, timestamp: f64) -> usize {
+        self.sequence.add_scan(simulator.sensor.last_scan(), timestamp)
+    }
+
+    /// Contents of `poses.txt`.
+    pub fn poses_txt(&self) -> String {
+        self.sequence.poses_txt()
+    }
+
+    /// Contents of `times.txt`.
+    pub fn times_txt(&self) -> String {
+        self.sequence.times_txt()
+    }
+
+    /// Contents of `calib.txt`.
+    pub fn calib_txt() -> String {
+        KittiSequence::calib_txt()
+    }
+}
+
+impl Default for KittiRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records scans, sensor poses and the sensor configuration into an
+/// in-memory MCAP file for Foxglove.
+///
+/// ```js
+/// const mcap = new McapRecorder("pointcloud2", "velodyne", "lidar");
+/// mcap.add_config(sim);
+/// for (const pose of trajectory) {
+///   sim.perform_scan(...pose);
+///   mcap.add_last_scan(sim, pose.time);
+/// }
+/// const bytes = mcap.finish(); // Uint8Array
+/// ```
+#[wasm_bindgen]
+pub struct McapRecorder {
+    writer: McapWriter<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl McapRecorder {
+    /// Create an empty recording.
+    ///
+    /// * `format`   – `"pointcloud2"` (ROS 2 CDR) or `"foxglove"` (`foxglove.PointCloud` JSON)
+    /// * `layout`   – Point fields, as for [`Simulator::last_scan_point_cloud2`]
+    /// * `frame_id` – Sensor frame; poses are published from `map` to it on `/tf`
+    #[wasm_bindgen(constructor)]
+    pub fn new(format: &str, layout: &str, frame_id: &str) -> Result<McapRecorder, JsValue> {
+        let options = McapOptions {
+            format: format.parse()?,
+            layout: layout.parse()?,
+            frame_id: frame_id.to_string(),
+            ..McapOptions::default()
+        };
+        Ok(McapRecorder {
+            writer: McapWriter::new(Vec::new(), options)?,
+        })
+    }
+
+    /// Store the sensor configuration of `simulator` as metadata.
+    pub fn add_config(&mut self, simulator: &Simulator) -> Result<(), JsValue> {
+        self.writer.write_config(simulator.sensor.config())?;
+        Ok(())
+    }
+
+    /// Record the last scan of `simulator` and its pose, taken at `stamp`
+    /// seconds.
+    pub fn add_last_scan(&mut self, simulator: &Simulator, stamp: f64) -> Result<(), JsValue> {
+        self.writer.write_scan(simulator.sensor.last_scan(), stamp)?;
+        Ok(())
+    }
+
+    /// Scan along `trajectory` with `simulator` at `rate_hz` scans per
+    /// second, recording each scan at its trajectory timestamp without
+    /// returning to JS in between.  Returns the number of scans.
+    pub fn add_trajectory(&mut self, simulator: &mut Simulator, trajectory: &Trajectory, rate_hz: f64) -> Result<usize, JsValue> {
+        let scene = simulator.scene.read();
+        let count = simulator.sensor.scan_trajectory_with(&scene, trajectory, rate_hz, |metadata, scan| {
+            self.writer.write_scan(scan, metadata.timestamp)
+        })?;
+        Ok(count)
+    }
+
+    /// Number of scans recorded so far.
+    pub fn scan_count(&self) -> f64 {
+        self.writer.scan_count() as f64
+    }
+
+    /// Finish the file and return its bytes as a `Uint8Array`.
+    pub fn finish(self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.writer.finish()?)
+    }
+}
+
+#[cfg(all(test, feature = "parallel", target_feature = "atomics"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn seeded_scan() -> ScanResult {
+        let mut scene = Scene::new();
+        scene.add_primitive(Primitive::cuboid(Vec3::ZERO, Vec3::new(40.0, 10.0, 30.0), Quat::IDENTITY).unwrap());
+        let config = SensorConfig::new(512, 16, 15.0, -15.0, 0.1, 100.0, 0.05).unwrap();
+        let mut sensor = Sensor::new(config).unwrap();
+        sensor.set_noise_seed(Some(42));
+        sensor.scan(&scene, &Pose::from_position(Vec3::new(1.0, 0.5, -2.0))).unwrap().clone()
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_thread_pool_scan_matches_single_thread() {
+        wasm_bindgen_futures::JsFuture::from(init_thread_pool(4)).await.unwrap();
+        assert_eq!(rayon::current_num_threads(), 4);
+        let threaded = seeded_scan();
+        // Last: the calling thread stays a worker of this pool afterwards.
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).use_current_thread().build().unwrap();
+        let single = single.install(seeded_scan);
+        assert_eq!(threaded.hit_count, 512 * 16);
+        assert_eq!(threaded.hits, single.hits);
+        assert_eq!(threaded.rings, single.rings);
+        assert_eq!(threaded.intensities, single.intensities);
+    }
+}
@@ -21,6 +21,33 @@ beforeAll(() => {
   initSync({ module: wasmBytes });
 });
 
+/** Binary glTF container with a JSON and a BIN chunk, each padded to 4 bytes. */
+function encodeGlb(json: string, bin: Uint8Array): Uint8Array {
+  const pad = (bytes: Uint8Array, fill: number) => {
+    const out = new Uint8Array(Math.ceil(bytes.length / 4) * 4).fill(fill);
+    out.set(bytes);
+    return out;
+  };
+  const chunks = [
+    { type: 0x4e4f534a, data: pad(new TextEncoder().encode(json), 0x20) }, // "JSON"
+    { type: 0x004e4942, data: pad(bin, 0) }, // "BIN\0"
+  ];
+  const length = 12 + chunks.reduce((sum, c) => sum + 8 + c.data.length, 0);
+  const out = new Uint8Array(length);
+  const view = new DataView(out.buffer);
+  view.setUint32(0, 0x46546c67, true); // "glTF"
+  view.setUint32(4, 2, true);
+  view.setUint32(8, length, true);
+  let offset = 12;
+  for (const { type, data } of chunks) {
+    view.setUint32(offset, data.length, true);
+    view.setUint32(offset + 4, type, true);
+    out.set(data, offset + 8);
+    offset += 8 + data.length;
+  }
+  return out;
+}
+
 describe("Simulator – WebAssembly bindings", () => {
   /**
    * A 20 × 20 ground plane at y = 0 (two triangles).
@@ -144,4 +171,42 @@ describe("Simulator – WebAssembly bindings", () => {
 
     sim.free();
   });
+
+  it("load_gltf loads a GLB scene", () => {
+    // One named node instancing a 20 × 20 ground plane at y = 0.
+    const bin = new ArrayBuffer(4 * 3 * 4 + 6 * 4);
+    new Float32Array(bin, 0, 12).set([-10, 0, -10, 10, 0, -10, 10, 0, 10, -10, 0, 10]);
+    new Uint32Array(bin, 48, 6).set([0, 1, 2, 0, 2, 3]);
+    const json = JSON.stringify({
+      asset: { version: "2.0" },
+      scene: 0,
+      scenes: [{ nodes: [0] }],
+      nodes: [{ name: "ground", mesh: 0 }],
+      meshes: [{ primitives: [{ attributes: { POSITION: 0 }, indices: 1, material: 0 }] }],
+      materials: [{ name: "road" }],
+      accessors: [
+        { bufferView: 0, componentType: 5126, count: 4, type: "VEC3" },
+        { bufferView: 1, componentType: 5125, count: 6, type: "SCALAR" },
+      ],
+      bufferViews: [
+        { buffer: 0, byteOffset: 0, byteLength: 48 },
+        { buffer: 0, byteOffset: 48, byteLength: 24 },
+      ],
+      buffers: [{ byteLength: bin.byteLength }],
+    });
+    const glb = encodeGlb(json, new Uint8Array(bin));
+
+    const sim = new Simulator(new SensorConfig(36, 4, -10.0, -20.0, 0.1, 20.0, 0.0));
+    sim.load_gltf(glb);
+    const hits = sim.perform_scan(0, 1, 0, 0, 0, 0, 1).slice();
+    expect(hits.length).toBe(144 * 3);
+    for (let i = 1; i < hits.length; i += 3) {
+      expect(Math.abs(hits[i])).toBeLessThan(0.01);
+    }
+    expect(sim.object_names()).toEqual(["ground"]);
+    expect(sim.label_names()).toEqual(["", "road"]);
+
+    expect(() => sim.load_gltf(new Uint8Array([1, 2, 3]))).toThrow();
+    sim.free();
+  });
 });
@@ -4,6 +4,11 @@ import {
   OUSTER_OS1_32_CONFIG,
   OUSTER_OS1_64_CONFIG,
   totalRays,
+  toSimLidarError,
+  SimLidarError,
+  SimLidarGeometryError,
+  SimLidarMissingGeometryError,
+  SimLidarConfigError,
   type SensorConfig,
 } from "../types.js";
 
@@ -46,3 +51,37 @@ describe("SensorConfig presets", () => {
     expect(totalRays(cfg)).toBe(360 * 4);
   });
 });
+
+describe("toSimLidarError", () => {
+  it("maps geometry variants to SimLidarGeometryError", () => {
+    for (const name of ["BadIndexCount", "IndexOutOfRange", "NonFiniteVertex", "BadObjectIdCount"]) {
+      const err = toSimLidarError(name, "bad geometry");
+      expect(err).toBeInstanceOf(SimLidarGeometryError);
+      expect(err).toBeInstanceOf(SimLidarError);
+      expect(err.message).toBe("bad geometry");
+    }
+  });
+
+  it("maps MissingGeometry and InvalidConfig to their subclasses", () => {
+    expect(toSimLidarError("MissingGeometry", "")).toBeInstanceOf(SimLidarMissingGeometryError);
+    expect(toSimLidarError("InvalidConfig", "")).toBeInstanceOf(SimLidarConfigError);
+    expect(toSimLidarError("ConfigParse", "")).toBeInstanceOf(SimLidarConfigError);
+    expect(toSimLidarError("ConfigSerialize", "")).toBeInstanceOf(SimLidarConfigError);
+  });
+
+  it("keeps every config issue on SimLidarConfigError", () => {
+    const issues = [
+      { field: "min_range", message: "must be <= max_range (100), got 200" },
+      { field: "noise_stddev", message: "must be finite and >= 0, got -1" },
+    ];
+    const err = toSimLidarError("InvalidConfig", "invalid sensor config", issues);
+    expect(err).toBeInstanceOf(SimLidarConfigError);
+    expect((err as SimLidarConfigError).issues).toEqual(issues);
+  });
+
+  it("falls back to the base SimLidarError for unknown names", () => {
+    const err = toSimLidarError("TypeError", "boom");
+    expect(err.constructor).toBe(SimLidarError);
+    expect(toSimLidarError(undefined, "boom").constructor).toBe(SimLidarError);
+  });
+});
@@ -11,7 +11,14 @@
  * ```
  */
 
-export type { SensorConfig, Pose, ScanResult, Geometry, SimLidarEventHandlers } from "./types.js";
+export type {
+  SensorConfig,
+  Pose,
+  ScanResult,
+  Geometry,
+  SimLidarEventHandlers,
+  ConfigIssue,
+} from "./types.js";
 export {
   VLP16_CONFIG,
   OUSTER_OS1_32_CONFIG,
@@ -20,10 +27,21 @@ export {
   SimLidarError,
   SimLidarNotInitializedError,
   SimLidarDisposedError,
+  SimLidarGeometryError,
+  SimLidarMissingGeometryError,
+  SimLidarConfigError,
+  toSimLidarError,
 } from "./types.js";
 
-import type { SensorConfig, Pose, ScanResult, Geometry, SimLidarEventHandlers } from "./types.js";
-import { SimLidarError, SimLidarDisposedError } from "./types.js";
+import type {
+  SensorConfig,
+  Pose,
+  ScanResult,
+  Geometry,
+  SimLidarEventHandlers,
+  ConfigIssue,
+} from "./types.js";
+import { SimLidarDisposedError, toSimLidarError } from "./types.js";
 
 /**
  * `LidarClient` wraps the Wasm LiDAR simulator running inside a Web Worker.
@@ -127,7 +145,11 @@ export class LidarClient {
     }
 
     if (msg.type === "error") {
-      const err = new SimLidarError(msg.message as string);
+      const err = toSimLidarError(
+        msg.name as string | undefined,
+        msg.message as string,
+        msg.issues as ConfigIssue[] | undefined
+      );
       this._handlers.onError?.(err);
       for (const entry of this.pending.values()) {
         entry.reject(err);
@@ -294,7 +316,11 @@ export class SimLidar {
     }
 
     if (msg.type === "error") {
-      const err = new SimLidarError(msg.message as string);
+      const err = toSimLidarError(
+        msg.name as string | undefined,
+        msg.message as string,
+        msg.issues as ConfigIssue[] | undefined
+      );
       this._handlers.onError?.(err);
       for (const entry of this.pending.values()) {
         entry.reject(err);
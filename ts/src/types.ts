@@ -134,6 +134,82 @@ export class SimLidarDisposedError extends SimLidarError {
   }
 }
 
+/**
+ * Thrown when environment geometry is malformed: the index count is not a
+ * multiple of 3, an index is out of range, a vertex is NaN/infinite, or the
+ * object id buffer does not hold one id per triangle.
+ */
+export class SimLidarGeometryError extends SimLidarError {
+  constructor(message: string) {
+    super(message);
+    this.name = "SimLidarGeometryError";
+    Object.setPrototypeOf(this, new.target.prototype);
+  }
+}
+
+/**
+ * Thrown when a scan is requested before any environment geometry has been
+ * loaded via `updateEnvironment()`.
+ */
+export class SimLidarMissingGeometryError extends SimLidarError {
+  constructor(message: string) {
+    super(message);
+    this.name = "SimLidarMissingGeometryError";
+    Object.setPrototypeOf(this, new.target.prototype);
+  }
+}
+
+/**
+ * A single invalid {@link SensorConfig} field reported by the Wasm validator.
+ * `field` uses the Rust (snake_case) field name, e.g. `"min_range"`.
+ */
+export interface ConfigIssue {
+  field: string;
+  message: string;
+}
+
+/**
+ * Thrown when the sensor configuration cannot be parsed or used for scanning.
+ * `issues` lists every invalid field, not just the first one found.
+ */
+export class SimLidarConfigError extends SimLidarError {
+  readonly issues: ConfigIssue[];
+
+  constructor(message: string, issues: ConfigIssue[] = []) {
+    super(message);
+    this.issues = issues;
+    this.name = "SimLidarConfigError";
+    Object.setPrototypeOf(this, new.target.prototype);
+  }
+}
+
+/**
+ * Map an error thrown by the Wasm module onto the matching
+ * {@link SimLidarError} subclass, using the Rust `SimLidarError` variant name
+ * carried in the JS error's `name`.
+ */
+export function toSimLidarError(
+  name: string | undefined,
+  message: string,
+  issues?: ConfigIssue[]
+): SimLidarError {
+  switch (name) {
+    case "BadIndexCount":
+    case "IndexOutOfRange":
+    case "NonFiniteVertex":
+    case "BadObjectIdCount":
+      return new SimLidarGeometryError(message);
+    case "MissingGeometry":
+      return new SimLidarMissingGeometryError(message);
+    case "InvalidConfig":
+    case "ConfigParse":
+    case "ConfigSerialize":
+      return new SimLidarConfigError(message, issues);
+    default:
+      return new SimLidarError(message);
+  }
+}
+
 // ─── Observability ───────────────────────────────────────────────────────────
 
 /**
@@ -13,10 +13,10 @@
  *   { type: 'environmentUpdated', __id: string }
  *   { type: 'scan', hits: Float32Array, hitCount: number, __id: string }
  *   { type: 'destroyed' }
- *   { type: 'error', message: string }
+ *   { type: 'error', name?: string, message: string, issues?: ConfigIssue[] }
  */
 
-import type { SensorConfig, Pose } from "./types.js";
+import type { SensorConfig, Pose, ConfigIssue } from "./types.js";
 
 // The wasm-pack output is loaded dynamically so this worker is usable both in
 // browser and Node.js (via vitest) without bundler magic at worker-load time.
@@ -126,7 +126,11 @@ self.addEventListener("message", async (evt: MessageEvent) => {
       return;
     }
   } catch (err: unknown) {
+    // Wasm errors carry the Rust `SimLidarError` variant in `name`; forward it
+    // so the client can map it onto the matching `SimLidarError` subclass.
+    const name = err instanceof Error ? err.name : undefined;
     const message = err instanceof Error ? err.message : String(err);
-    self.postMessage({ type: "error", message });
+    const issues = (err as { issues?: ConfigIssue[] } | null)?.issues;
+    self.postMessage({ type: "error", name, message, issues });
   }
 });
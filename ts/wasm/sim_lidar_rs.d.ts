@@ -9,6 +9,7 @@
 /** Sensor configuration mirroring real-world LiDARs. */
 export class SensorConfig {
   free(): void;
+  /** Throws an `InvalidConfig` error (with `issues`) if any field is invalid. */
   constructor(
     horizontal_resolution: number,
     vertical_channels: number,
@@ -25,6 +26,75 @@ export class SensorConfig {
   min_range: number;
   max_range: number;
   noise_stddev: number;
+  /** Report a radial (Doppler) velocity per hit, as FMCW sensors do. */
+  doppler: boolean;
+  /** Standard deviation of Gaussian noise added to radial velocities (m/s). */
+  velocity_noise_stddev: number;
+  /**
+   * Keep one point per ray (`NaN` for misses) so scans form a
+   * `channels × horizontal_resolution` grid.
+   */
+  organized: boolean;
+  /** Rotation rate in Hz, used to timestamp points within a scan (default 10). */
+  scan_frequency_hz: number;
+  /** Throws an `InvalidConfig` error listing every invalid field. */
+  validate(): void;
+  /**
+   * Parse and validate a configuration from JSON (snake_case field names).
+   * Throws a `ConfigParse` or `InvalidConfig` error.
+   */
+  static from_json(json: string): SensorConfig;
+  /** Serialize this configuration as pretty-printed JSON. */
+  to_json(): string;
+}
+
+/**
+ * Timestamped sensor poses, interpolated linearly in position and by SLERP in
+ * rotation, for `Simulator.scan_trajectory` and `McapRecorder.add_trajectory`.
+ */
+export class Trajectory {
+  free(): void;
+  constructor();
+  /** Append a pose at `time` seconds. Throws a `Format` error unless times strictly increase. */
+  add_pose(
+    time: number,
+    x: number,
+    y: number,
+    z: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): void;
+  /** Number of keyframes. */
+  len(): number;
+  is_empty(): boolean;
+}
+
+/** Scans of `Simulator.scan_trajectory`; getters return `undefined` past the end. */
+export class ScanBatch {
+  free(): void;
+  /** Number of scans. */
+  len(): number;
+  is_empty(): boolean;
+  /** Start time of scan `index` in seconds, on the trajectory's clock. */
+  timestamp(index: number): number | undefined;
+  /** Sensor pose of scan `index` as `[x, y, z, qx, qy, qz, qw]`. */
+  pose(index: number): Float32Array | undefined;
+  /** Hit points of scan `index`. */
+  hits(index: number): Float32Array | undefined;
+  /** Number of points in scan `index`. */
+  hit_count(index: number): number | undefined;
+}
+
+/**
+ * Reference-counted scene (BVH, heightfields and primitives) shared by the
+ * simulators attached to it; geometry loaded through any of them is seen by all.
+ */
+export class SceneHandle {
+  free(): void;
+  /** A new, empty scene. */
+  constructor();
 }
 
 /**
@@ -34,12 +104,109 @@ export class SensorConfig {
 export class Simulator {
   free(): void;
   constructor(config: SensorConfig);
-  /** Ingest environment geometry and (re)build the internal BVH. */
+  /** Create a simulator attached to a shared scene. Throws `InvalidConfig`. */
+  static with_scene(config: SensorConfig, scene: SceneHandle): Simulator;
+  /** A handle to this simulator's scene, to attach other simulators to. */
+  scene(): SceneHandle;
+  /** Scan `scene` from now on, sharing its geometry with every simulator attached to it. */
+  attach_scene(scene: SceneHandle): void;
+  /**
+   * Scan along `trajectory` at `rate_hz` scans per second entirely in Wasm.
+   * Throws like `perform_scan`, or `InvalidArgument` for a bad rate.
+   */
+  scan_trajectory(trajectory: Trajectory, rate_hz: number): ScanBatch;
+  /**
+   * Ingest environment geometry and (re)build the internal BVH.
+   * Throws if the geometry is malformed; the previous environment is kept.
+   */
   load_geometry(vertices: Float32Array, indices: Uint32Array): void;
+  /** Ingest geometry tagged with one object id per triangle. */
+  load_geometry_with_object_ids(
+    vertices: Float32Array,
+    indices: Uint32Array,
+    object_ids: Uint32Array,
+  ): void;
+  /**
+   * Parse a Wavefront OBJ file and use it as the environment.
+   * `grouping` is `"object"`, `"group"` or `"material"` (which of `o`, `g` or
+   * `usemtl` starts a new object id); materials become semantic labels.
+   */
+  load_obj(bytes: Uint8Array, grouping: string): void;
+  /**
+   * Parse a binary or ASCII STL file and use it as the environment, welding
+   * identical vertices.  `scale` multiplies every coordinate (e.g. `0.001` for mm).
+   */
+  load_stl(bytes: Uint8Array, scale: number): void;
+  /**
+   * Parse a glTF 2.0 scene (`.glb`, or `.gltf` with embedded buffers) and use
+   * it as the environment.  Node names become object names and material names
+   * semantic labels.
+   */
+  load_gltf(bytes: Uint8Array): void;
+  /** Object names indexed by object id, for geometry loaded from a file. */
+  object_names(): string[];
+  /** Semantic label names indexed by label (0 = unlabeled), for geometry loaded from a file. */
+  label_names(): string[];
+  /**
+   * Assign a semantic label per object id (reported by `last_labels`).
+   * Throws a `MissingGeometry` error if no geometry has been loaded.
+   */
+  set_object_labels(labels: Uint32Array): void;
+  /**
+   * Add terrain to the environment, next to any loaded meshes.  Heightfields
+   * are kept when new geometry is loaded.
+   */
+  add_heightfield(heightfield: Heightfield): void;
+  /** Remove every heightfield added with `add_heightfield`. */
+  clear_heightfields(): void;
+  /**
+   * Add an analytic sphere, raycast exactly with exact normals next to any
+   * loaded meshes.  Primitives are kept when new geometry is loaded.
+   * Throws a `Format` error if the shape is degenerate.
+   */
+  add_sphere(x: number, y: number, z: number, radius: number, object_id: number): void;
+  /** Add a box with half extents `(hx, hy, hz)`, rotated by `(qx, qy, qz, qw)`. */
+  add_box(
+    x: number,
+    y: number,
+    z: number,
+    hx: number,
+    hy: number,
+    hz: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+    object_id: number,
+  ): void;
+  /** Add a capped cylinder, upright (along +y) before rotation by `(qx, qy, qz, qw)`. */
+  add_cylinder(
+    x: number,
+    y: number,
+    z: number,
+    radius: number,
+    half_height: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+    object_id: number,
+  ): void;
+  /** Add an infinite plane through `(x, y, z)` with normal `(nx, ny, nz)`, e.g. the ground. */
+  add_plane(x: number, y: number, z: number, nx: number, ny: number, nz: number, object_id: number): void;
+  /** Remove every analytic primitive. */
+  clear_primitives(): void;
+  /** Make noise reproducible from `seed`, or random again with `undefined`. */
+  set_noise_seed(seed?: bigint): void;
+  /** Set the world-space sensor velocity (m/s) used for Doppler output. */
+  set_sensor_velocity(vx: number, vy: number, vz: number): void;
+  /** Set per-object velocities `[vx,vy,vz, …]` (m/s) indexed by object id. */
+  set_object_velocities(velocities: Float32Array): void;
   /**
    * Run a full scan from the given pose.
    * Returns a zero-copy `Float32Array` view into Wasm linear memory.
    * Copy it immediately before making any further Wasm calls.
+   * Throws a `MissingGeometry` error if no geometry has been loaded.
    */
   perform_scan(
     x: number,
@@ -50,10 +217,91 @@ export class Simulator {
     qz: number,
     qw: number,
   ): Float32Array;
-  /** Replace the sensor configuration without rebuilding the BVH. */
+  /**
+   * Run a full scan into the stable hit buffer and return the hit count.
+   * Read the hits through a view built from `hit_buffer_ptr()`.
+   */
+  scan_in_place(
+    x: number,
+    y: number,
+    z: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): number;
+  /**
+   * Run a full scan, write `[x,y,z, …]` into the caller-owned `out` buffer and
+   * return the hit count. Throws a `BufferTooSmall` error if the hits do not fit.
+   */
+  scan_into(
+    out: Float32Array,
+    x: number,
+    y: number,
+    z: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): number;
+  /**
+   * Address of the hit buffer in Wasm linear memory. Stable across scans; only
+   * changes when `set_config` needs a larger buffer. Rebuild any view over it
+   * when `memory.buffer` is replaced by a memory growth.
+   */
+  hit_buffer_ptr(): number;
+  /** Number of floats reserved at `hit_buffer_ptr()` (`total_rays * 3`). */
+  hit_buffer_capacity(): number;
+  /** Replace the sensor configuration without rebuilding the BVH. Throws if invalid. */
   set_config(config: SensorConfig): void;
   /** Returns the number of valid hits from the last scan. */
   last_hit_count(): number;
+  /** Radial velocity (m/s) of each hit from the last scan; empty unless `doppler` is set. */
+  last_velocities(): Float32Array;
+  /** Intensity (`[0, 1]`, cosine of the incidence angle) of each point from the last scan. */
+  last_intensities(): Float32Array;
+  /** Laser channel (ring) of each point from the last scan. */
+  last_rings(): Uint16Array;
+  /** Time of each point from the last scan, in seconds since the scan started. */
+  last_times(): Float32Array;
+  /** Semantic label of each point from the last scan (`0` = unlabeled). */
+  last_labels(): Uint32Array;
+  /**
+   * Serialize the last scan as a PLY file with every per-point attribute.
+   * `format` is `"ascii"` or `"binary_little_endian"`; `include_pose` adds a
+   * `sensor_pose` element.
+   */
+  last_scan_ply(format: string, include_pose: boolean): Uint8Array;
+  /** Encode the last scan as a KITTI Velodyne `.bin` frame (x, y, z, intensity in the KITTI sensor frame). */
+  last_scan_kitti_bin(): Uint8Array;
+  /**
+   * Encode the last scan as a CDR-serialized ROS 2 `sensor_msgs/msg/PointCloud2`.
+   * `layout` is `"velodyne"`, `"ouster"`, or comma-separated field names
+   * (`x,y,z,intensity,ring,time,t,reflectivity,ambient,range`) packed in order.
+   * `stamp` is the header stamp in seconds.
+   */
+  last_scan_point_cloud2(layout: string, frame_id: string, stamp: number): Uint8Array;
+  /** Encode the last scan's labels as a SemanticKITTI `.label` frame, if it has labels. */
+  last_scan_kitti_labels(): Uint8Array | undefined;
+  /**
+   * Serialize the last scan as a PCD file.
+   * `encoding` is `"ascii"`, `"binary"` or `"binary_compressed"`.
+   */
+  last_scan_pcd(encoding: string): Uint8Array;
+  /**
+   * Encode the last scan as 1206-byte Velodyne UDP data packets in a pcap capture.
+   * `model` is `"vlp16"` or `"vlp32c"`; the scan must be organized with the
+   * model's channel count.  `scan_start` is the first firing time in seconds.
+   */
+  last_scan_velodyne_pcap(model: string, scan_start: number): Uint8Array;
+  /**
+   * Encode the last scan as Ouster lidar packets in a pcap capture.
+   * `profile` is `"legacy"` or `"rng19"`; the scan must be organized and the
+   * configuration an Ouster lidar mode such as `1024x10`.
+   */
+  last_scan_ouster_pcap(profile: string, frame_id: number, scan_start: number): Uint8Array;
+  /** Sensor metadata JSON matching `last_scan_ouster_pcap` for the current configuration. */
+  ouster_metadata(profile: string): string;
 }
 
 /**
@@ -65,7 +313,11 @@ export class LidarSimulator {
   constructor(vertices: Float32Array, indices: Uint32Array, config: SensorConfig);
   /** Replace the sensor configuration at runtime. */
   set_config(config: SensorConfig): void;
-  /** Run a full scan from the given pose. */
+  /** A handle to this simulator's scene. */
+  scene(): SceneHandle;
+  /** Scan `scene` from now on instead of the geometry given at construction. */
+  attach_scene(scene: SceneHandle): void;
+  /** Run a full scan from the given pose and return a copy of the hits. */
   scan(
     px: number,
     py: number,
@@ -75,10 +327,171 @@ export class LidarSimulator {
     qz: number,
     qw: number,
   ): Float32Array;
+  /** Run a full scan into the caller-owned `out` buffer and return the hit count. */
+  scan_into(
+    out: Float32Array,
+    px: number,
+    py: number,
+    pz: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): number;
   /** Returns the hit count from the last scan. */
   last_hit_count(): number;
 }
 
+/**
+ * Several sensors mounted on one vehicle, scanning the scene of a `Simulator`
+ * (its BVH is shared, not rebuilt). Poses are `(x, y, z)` plus a quaternion.
+ */
+export class LidarRig {
+  free(): void;
+  constructor();
+  /** Mount a sensor at a pose in the vehicle frame and return its id. Throws `InvalidConfig`. */
+  add_sensor(
+    config: SensorConfig,
+    x: number,
+    y: number,
+    z: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): number;
+  /** Number of mounted sensors. */
+  sensor_count(): number;
+  /** Scan the scene of `simulator` with every sensor from the given vehicle pose. */
+  scan(
+    simulator: Simulator,
+    x: number,
+    y: number,
+    z: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): void;
+  /** Like `scan`, returning a copy of the merged cloud (organized misses dropped). */
+  scan_merged(
+    simulator: Simulator,
+    x: number,
+    y: number,
+    z: number,
+    qx: number,
+    qy: number,
+    qz: number,
+    qw: number,
+  ): Float32Array;
+  /** Hit points of sensor `id` from the last scan, or `undefined` for an unknown id. */
+  sensor_hits(id: number): Float32Array | undefined;
+  /** Sensor id of every point of the last merged scan. */
+  merged_sensor_ids(): Uint32Array;
+}
+
+/**
+ * Accumulates scans into an in-memory LAS 1.4 file (point format 6).
+ * Points are written Z-up: simulator `(x, y, z)` becomes `(x, -z, y)`.
+ */
+export class LasRecorder {
+  free(): void;
+  /**
+   * `scale` is the coordinate step in metres; the offset is in the Z-up frame.
+   * Throws `InvalidArgument` unless `scale` is finite and positive and the offset is finite.
+   */
+  constructor(scale: number, offset_x: number, offset_y: number, offset_z: number);
+  /** Append the last scan of `simulator`, stamping points relative to `gps_time`. */
+  add_last_scan(simulator: Simulator, gps_time: number): void;
+  /** Number of points recorded so far. */
+  point_count(): number;
+  /** Finish the file and return its bytes. The recorder cannot be used afterwards. */
+  finish(): Uint8Array;
+}
+
+/** Collects poses and timestamps of a KITTI odometry sequence. */
+export class KittiRecorder {
+  free(): void;
+  constructor();
+  /** Record the pose of the last scan of `simulator` as the next frame; returns its index. */
+  add_last_scan(simulator: Simulator, timestamp: number): number;
+  /** Contents of `poses.txt` (camera-frame poses relative to the first frame). */
+  poses_txt(): string;
+  /** Contents of `times.txt` (seconds since the first frame). */
+  times_txt(): string;
+  /** Contents of `calib.txt`. */
+  static calib_txt(): string;
+}
+
+/**
+ * Terrain as a regular grid of elevations, raycast by grid marching.  Sample
+ * `(column, row)` lies at `origin + (column * spacing_x, height, row * spacing_z)`;
+ * `NaN` heights are holes.
+ */
+export class Heightfield {
+  free(): void;
+  /** Row-major heights (`rows × columns`, metres). Throws a `Format` error if malformed. */
+  constructor(heights: Float32Array, columns: number, rows: number, spacing_x: number, spacing_z: number);
+  /** Raw little-endian `f32` grid. */
+  static from_raw_f32(bytes: Uint8Array, columns: number, rows: number, spacing_x: number, spacing_z: number): Heightfield;
+  /** 8- or 16-bit PGM (`P5` or `P2`); each sample becomes `value * height_scale` metres. */
+  static from_pgm(bytes: Uint8Array, spacing_x: number, spacing_z: number, height_scale: number): Heightfield;
+  /** 8- or 16-bit grayscale PNG. */
+  static from_png(bytes: Uint8Array, spacing_x: number, spacing_z: number, height_scale: number): Heightfield;
+  /** Move the first sample to `(x, y, z)`. */
+  set_origin(x: number, y: number, z: number): void;
+  columns(): number;
+  rows(): number;
+  /** World-space height of a sample, or `NaN` if missing or out of range. */
+  height_at(column: number, row: number): number;
+  /** Object id reported for hits on the terrain (default 0). */
+  object_id: number;
+}
+
+/**
+ * Records scans, `map` → sensor poses on `/tf`, and the sensor configuration
+ * into an in-memory MCAP file for Foxglove.
+ */
+export class McapRecorder {
+  free(): void;
+  /**
+   * `format` is `"pointcloud2"` (ROS 2 CDR) or `"foxglove"` (`foxglove.PointCloud` JSON);
+   * `layout` is as for `Simulator.last_scan_point_cloud2`.
+   */
+  constructor(format: string, layout: string, frame_id: string);
+  /** Store the sensor configuration of `simulator` as metadata. */
+  add_config(simulator: Simulator): void;
+  /** Record the last scan of `simulator` and its pose, taken at `stamp` seconds. */
+  add_last_scan(simulator: Simulator, stamp: number): void;
+  /** Scan along `trajectory` at `rate_hz` and record every scan; returns the scan count. */
+  add_trajectory(simulator: Simulator, trajectory: Trajectory, rate_hz: number): number;
+  /** Number of scans recorded so far. */
+  scan_count(): number;
+  /** Finish the file and return its bytes. The recorder cannot be used afterwards. */
+  finish(): Uint8Array;
+}
+
+/**
+ * Errors thrown by the Wasm module are plain `Error`s whose `name` is the Rust
+ * `SimLidarError` variant: `BadIndexCount`, `IndexOutOfRange`,
+ * `NonFiniteVertex`, `BadObjectIdCount`, `MissingGeometry`, `InvalidConfig`,
+ * `InvalidArgument`, `ConfigParse`, `ConfigSerialize`, `BufferTooSmall`, `Format` or `Io`.
+ * `InvalidConfig` errors also carry `issues: { field: string; message: string }[]`.
+ */
+
+/**
+ * Parse a PCD file (ascii, binary or binary_compressed) and return its points
+ * as a flat `[x0, y0, z0, x1, …]` array. Throws a `Format` error if malformed.
+ */
+export function read_pcd_points(bytes: Uint8Array): Float32Array;
+
+/**
+ * Start a pool of `num_threads` Web Workers that casts the rays of every scan.
+ * Call once after `init()`, before scanning. Only exported by the threaded
+ * build (`npm run build:wasm-threads`), which needs a cross-origin isolated page.
+ */
+export const initThreadPool: ((num_threads: number) => Promise<void>) | undefined;
+
 /**
  * Synchronously initialise the Wasm module from pre-fetched bytes.
  * Suitable for Node.js test environments.